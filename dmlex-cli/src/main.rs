@@ -1,7 +1,9 @@
-use clap::{Parser,ValueEnum};
+use clap::Parser;
 use dmlex::*;
+use dmlex::homograph::{HomographOptions, HomographOrder};
 use dmlex::model::{LexicographicResource, Entry};
 use std::fs::File;
+use std::io::{Read, Write};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
@@ -10,101 +12,419 @@ struct Args {
     input: Option<String>,
     #[clap(short, long)]
     output: Option<String>,
-    #[clap(long, default_value = "xml")]
-    input_format: Format,
-    #[clap(long, default_value = "xml")]
-    output_format: Format,
+    /// Input format, detected from the content and file name if not given
+    #[clap(long)]
+    input_format: Option<String>,
+    /// Output format, guessed from the output file name if not given, otherwise XML
+    #[clap(long)]
+    output_format: Option<String>,
     #[clap(long)]
     default_namespace: Option<String>,
+    /// Treat the input as a single entry, detected from the root element if not given and the input format can tell
     #[clap(long, default_value = "false")]
     entry: bool,
     #[clap(long, default_value = "false")]
     ontolex: bool,
+    /// Additional Turtle prefix as name=namespace (may be repeated)
+    #[clap(long)]
+    prefix: Vec<String>,
+    /// Base IRI for Turtle output
+    #[clap(long)]
+    base: Option<String>,
+    /// Prefix for the default namespace in Turtle output
+    #[clap(long)]
+    data_prefix: Option<String>,
+    /// Write the resource as an HTML site with one page per entry to this directory
+    #[clap(long)]
+    html_dir: Option<String>,
+    /// Directory with the page.html, entry.html, index.html and style.css templates for HTML output
+    #[clap(long)]
+    html_templates: Option<String>,
+    /// Table of part of speech tags and Apertium symbols, separated by a tab, for dix output
+    #[clap(long)]
+    pos_mapping: Option<String>,
+    /// Only write the translations into this language for dix output
+    #[clap(long)]
+    dix_target_lang: Option<String>,
+    /// Table of SFM markers and their targets, separated by a tab, added to the MDF markers for SFM input
+    #[clap(long)]
+    sfm_mapping: Option<String>,
+    /// Language of the headwords of SFM input
+    #[clap(long)]
+    sfm_lang: Option<String>,
+    /// Write the resource as a StarDict dictionary, with this path followed by .ifo, .idx, .dict and .syn
+    #[clap(long)]
+    stardict: Option<String>,
+    /// Write the resource as a dictd dictionary, with this path followed by .index and .dict
+    #[clap(long)]
+    dictd: Option<String>,
+    /// Write the headwords and inflected forms as a Hunspell dictionary, with this path followed by .dic and .aff
+    #[clap(long)]
+    hunspell: Option<String>,
+    /// List every inflected form as a Hunspell stem instead of inferring suffix rules
+    #[clap(long, default_value = "false")]
+    hunspell_no_affixes: bool,
+    /// Combine the input with these resources, renaming identifiers that are used more than once
+    #[clap(long, num_args = 1..)]
+    combine: Vec<String>,
+    /// Only keep the entries with one of these parts of speech, with the relations and tags they need
+    #[clap(long, num_args = 1..)]
+    extract_pos: Vec<String>,
+    /// Only keep the entries whose headword is in this file, one per line, with the relations and tags they need
+    #[clap(long)]
+    extract_headwords: Option<String>,
+    /// Read the input from a directory written by --split instead of a file
+    #[clap(long)]
+    assemble: Option<String>,
+    /// Write the resource into this directory as a header file and a file for each entry, in the output format
+    #[clap(long)]
+    split: Option<String>,
+    /// Number the entries that share a headword in their input order, in the order of their parts of speech or in the order of their previous numbers
+    #[clap(long, value_parser = ["input", "pos", "previous"])]
+    number_homographs: Option<String>,
+    /// Also give headwords without homographs the number 1 when numbering homographs
+    #[clap(long, default_value = "false")]
+    number_unique_homographs: bool,
+    /// Report gaps, duplicates and stray numbers in the homograph numbering, exiting with 1 if there are any
+    #[clap(long, default_value = "false")]
+    check_homographs: bool,
+    /// Sort the entries alphabetically for the language of the resource and then by homograph number
+    #[clap(long, default_value = "false")]
+    sort: bool,
+    /// Reverse a bilingual resource into this translation language before writing it
+    #[clap(long)]
+    reverse: Option<String>,
+    /// Compare the input with this newer version of it and write the differences, exiting with 1 if there are any
+    #[clap(long)]
+    diff: Option<String>,
+    /// Write the differences as JSON instead of text
+    #[clap(long, default_value = "false")]
+    diff_json: bool,
+    /// Merge the changes in OURS and THEIRS since BASE into OURS, exiting with 1 if there are conflicts.
+    /// This works as a git merge driver with `driver = dmlex-converter --merge %O %A %B`
+    #[clap(long, num_args = 3, value_names = ["BASE", "OURS", "THEIRS"])]
+    merge: Vec<String>,
+    /// List the available input and output formats and exit
+    #[clap(long, default_value = "false")]
+    list_formats: bool,
 }
 
-#[derive(Debug,Clone,ValueEnum)]
-pub enum Format {
-    XML,
-    RDF,
-    JSON,
+impl Args {
+    fn turtle_options(&self) -> TurtleOptions {
+        let mut options = TurtleOptions::new();
+        for prefix in self.prefix.iter() {
+            match prefix.split_once('=') {
+                Some((name, namespace)) => options = options.with_prefix(name, namespace),
+                None => panic!("Prefix should be given as name=namespace: {}", prefix),
+            }
+        }
+        options.base = self.base.clone();
+        options.data_prefix = self.data_prefix.clone();
+        options
+    }
+
+    fn html_templates(&self) -> dmlex::html::HtmlTemplates {
+        match &self.html_templates {
+            Some(dir) => match dmlex::html::HtmlTemplates::from_dir(dir) {
+                Ok(templates) => templates,
+                Err(e) => panic!("Could not read HTML templates from {}: {}", dir, e),
+            },
+            None => dmlex::html::HtmlTemplates::default(),
+        }
+    }
+
+    fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            default_namespace: self.default_namespace.clone(),
+        }
+    }
+
+    fn write_options(&self) -> WriteOptions {
+        WriteOptions {
+            default_namespace: self.default_namespace.clone(),
+            ontolex: self.ontolex,
+            turtle: self.turtle_options(),
+        }
+    }
 }
 
-impl Into<dmlex::Format> for Format {
-    fn into(self) -> dmlex::Format {
-        match self {
-            Format::XML => dmlex::Format::XML,
-            Format::RDF => dmlex::Format::RDF,
-            Format::JSON => dmlex::Format::JSON,
+/// The output format given or guessed from the output file name
+fn output_format(registry : &FormatRegistry, args : &Args) -> String {
+    let output_format = match &args.output_format {
+        Some(format) => format.clone(),
+        None => args.output.as_deref().and_then(dmlex::detect::format_from_extension)
+            .unwrap_or(dmlex::Format::XML).name().to_string(),
+    };
+    if registry.writer(&output_format).is_none() {
+        panic!("Unknown output format {}, expected one of {}", output_format, registry.writer_names().join(", "));
+    }
+    output_format
+}
+
+fn open_output(args : &Args) -> Box<dyn Write> {
+    if let Some(output) = &args.output {
+        if let Ok(file) = File::create(output.clone()) {
+            Box::new(file)
+        } else {
+            panic!("Could not open output file {}", output);
         }
+    } else {
+        Box::new(std::io::stdout())
     }
 }
 
+/// Parse another resource given on the command line, detecting its format
+fn parse_other(registry : &FormatRegistry, path : &str, args : &Args) -> LexicographicResource {
+    let content = match std::fs::read(path) {
+        Ok(content) => content,
+        Err(e) => panic!("Could not read input file {}: {}", path, e),
+    };
+    let format = match &args.input_format {
+        Some(format) => format.clone(),
+        None => match registry.detect_format(&content, Some(path)) {
+            Some(format) => format,
+            None => panic!("Could not detect the format of {}, please specify --input-format", path),
+        },
+    };
+    match registry.parse_with_report(&format, &mut content.as_slice(), &args.read_options()) {
+        Ok((resource, report)) => {
+            eprint!("{}", report);
+            resource
+        },
+        Err(e) => panic!("Could not parse input file {}: {}", path, e),
+    }
+}
 
 fn main() {
     let args = Args::parse();
+    let mut registry = FormatRegistry::default();
+    if args.html_templates.is_some() {
+        registry.register_writer("html", dmlex::html::HtmlFormat { templates: args.html_templates() });
+    }
+    if args.pos_mapping.is_some() || args.dix_target_lang.is_some() {
+        let mut options = match &args.pos_mapping {
+            Some(pos_mapping) => match File::open(pos_mapping).map_err(dmlex::apertium::ApertiumError::from)
+                    .and_then(|file| dmlex::apertium::ApertiumOptions::default().read_mapping(std::io::BufReader::new(file))) {
+                Ok(options) => options,
+                Err(e) => panic!("Could not read the part of speech mapping {}: {}", pos_mapping, e),
+            },
+            None => dmlex::apertium::ApertiumOptions::default(),
+        };
+        if let Some(lang) = &args.dix_target_lang {
+            options = options.with_target_lang(lang);
+        }
+        registry.register_writer("dix", dmlex::apertium::ApertiumFormat { options });
+    }
+    if args.sfm_mapping.is_some() || args.sfm_lang.is_some() {
+        let mapping = match &args.sfm_mapping {
+            Some(sfm_mapping) => match File::open(sfm_mapping).map_err(dmlex::sfm::SfmError::from)
+                    .and_then(|file| dmlex::sfm::SfmMapping::mdf().read_mapping(std::io::BufReader::new(file))) {
+                Ok(mapping) => mapping,
+                Err(e) => panic!("Could not read the SFM marker mapping {}: {}", sfm_mapping, e),
+            },
+            None => dmlex::sfm::SfmMapping::mdf(),
+        };
+        registry.register_reader("sfm", dmlex::sfm::SfmFormat { mapping, lang_code: args.sfm_lang.clone() });
+    }
 
-    if args.entry {
-        let resource : Entry = if let Some(input) = &args.input {
-            if let Ok(file) = File::open(input.clone()) {
-                match parse_entry(file, &args.input_format.into(), &args.default_namespace) {
-                    Ok(resource) => resource,
-                    Err(e) => panic!("Could not parse input file {}: {}", input, e),
-                }
-            } else {
-                panic!("Could not open input file {}", input);
-            }
-        } else {
-            match parse_entry(std::io::stdin(), &args.input_format.into(), &args.default_namespace) {
-                Ok(resource) => resource,
-                Err(e) => panic!("Could not parse input file: {}", e),
-            }
+    if let [base, ours, theirs] = args.merge.as_slice() {
+        let result = dmlex::merge::merge(&parse_other(&registry, base, &args), &parse_other(&registry, ours, &args),
+            &parse_other(&registry, theirs, &args));
+        for conflict in result.conflicts.iter() {
+            eprintln!("{}", conflict);
+        }
+        let format = match &args.output_format {
+            Some(format) => format.clone(),
+            None => std::fs::read(ours).ok().and_then(|content| registry.detect_format(&content, Some(ours)))
+                .unwrap_or_else(|| dmlex::Format::XML.name().to_string()),
+        };
+        match File::create(ours) {
+            Ok(mut file) => if let Err(e) = registry.write_with_report(&format, &mut file, &result.resource, &args.write_options()) {
+                panic!("Could not write merged file {}: {}", ours, e);
+            },
+            Err(e) => panic!("Could not open {}: {}", ours, e),
+        }
+        if !result.is_clean() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.list_formats {
+        println!("Input formats: {}", registry.reader_names().join(", "));
+        println!("Output formats: {}", registry.writer_names().join(", "));
+        return;
+    }
+
+    if let Some(dir) = &args.assemble {
+        let resource = match dmlex::split::assemble(dir, &registry, args.input_format.as_deref(), &args.read_options()) {
+            Ok(resource) => resource,
+            Err(e) => panic!("Could not assemble {}: {}", dir, e),
         };
-        if let Some(output) = &args.output {
-            if let Ok(file) = File::create(output.clone()) {
-                match write_entry(file, &args.output_format.into(), &resource, &args.default_namespace, args.ontolex) {
-                    Ok(_) => (),
-                    Err(e) => panic!("Could not write output file: {}", e),
-                }
-            } else {
-                panic!("Could not open output file {}", output);
+        let output_format = output_format(&registry, &args);
+        process(&registry, &args, resource, dir, &output_format);
+        return;
+    }
+
+    let mut content = Vec::new();
+    if let Some(input) = &args.input {
+        if let Ok(mut file) = File::open(input.clone()) {
+            if let Err(e) = file.read_to_end(&mut content) {
+                panic!("Could not read input file {}: {}", input, e);
             }
         } else {
-            match write_entry(std::io::stdout(), &args.output_format.into(), &resource, &args.default_namespace, args.ontolex) {
-                Ok(_) => (),
-                Err(e) => panic!("Could not write output file: {}", e),
-            }
+            panic!("Could not open input file {}", input);
         }
+    } else if let Err(e) = std::io::stdin().read_to_end(&mut content) {
+        panic!("Could not read input: {}", e);
+    }
+    let input_name = args.input.clone().unwrap_or_else(|| "<stdin>".to_string());
+    let input_format = match &args.input_format {
+        Some(format) => format.clone(),
+        None => match registry.detect_format(&content, args.input.as_deref()) {
+            Some(format) => format,
+            None => panic!("Could not detect the format of {}, please specify --input-format", input_name),
+        },
+    };
+    if registry.reader(&input_format).is_none() {
+        panic!("Unknown input format {}, expected one of {}", input_format, registry.reader_names().join(", "));
+    }
+    let output_format = output_format(&registry, &args);
+    let entry = args.entry || registry.detect_root(&input_format, &content) == Some(RootKind::Entry);
 
+    if entry {
+        let resource : Entry = match registry.parse_entry(&input_format, &mut content.as_slice(), &args.read_options()) {
+            Ok(resource) => resource,
+            Err(e) => panic!("Could not parse input file {}: {}", input_name, e),
+        };
+        match registry.write_entry(&output_format, &mut open_output(&args), &resource, &args.write_options()) {
+            Ok(_) => (),
+            Err(e) => panic!("Could not write output file: {}", e),
+        }
     } else {
-        let resource : LexicographicResource = if let Some(input) = &args.input {
-            if let Ok(file) = File::open(input.clone()) {
-                match parse(file, &args.input_format.into(), &args.default_namespace) {
-                    Ok(resource) => resource,
-                    Err(e) => panic!("Could not parse input file {}: {}", input, e),
-                }
-            } else {
-                panic!("Could not open input file {}", input);
-            }
-        } else {
-            match parse(std::io::stdin(), &args.input_format.into(), &args.default_namespace) {
-                Ok(resource) => resource,
-                Err(e) => panic!("Could not parse input file: {}", e),
-            }
+        let resource : LexicographicResource = match registry.parse_with_report(&input_format, &mut content.as_slice(), &args.read_options()) {
+            Ok((resource, report)) => {
+                eprint!("{}", report);
+                resource
+            },
+            Err(e) => panic!("Could not parse input file {}: {}", input_name, e),
         };
-        if let Some(output) = &args.output {
-            if let Ok(file) = File::create(output.clone()) {
-                match write(file, &args.output_format.into(), &resource, &args.default_namespace, args.ontolex) {
-                    Ok(_) => (),
-                    Err(e) => panic!("Could not write output file: {}", e),
-                }
-            } else {
-                panic!("Could not open output file {}", output);
-            }
+        process(&registry, &args, resource, &input_name, &output_format);
+    }
+}
+
+/// Transform a resource as asked and write it. The output file is only
+/// created once there is something to write to it.
+fn process(registry : &FormatRegistry, args : &Args, resource : LexicographicResource, input_name : &str,
+        output_format : &str) {
+    let resource = if args.combine.is_empty() {
+        resource
+    } else {
+        let mut resources = vec![resource];
+        resources.extend(args.combine.iter().map(|path| parse_other(registry, path, args)));
+        let (combined, report) = dmlex::combine::combine(&resources);
+        eprint!("{}", report);
+        combined
+    };
+    let resource = if args.extract_pos.is_empty() {
+        resource
+    } else {
+        dmlex::subset::extract(&resource, dmlex::subset::has_part_of_speech(&args.extract_pos))
+    };
+    let resource = match &args.extract_headwords {
+        Some(path) => match File::open(path).and_then(|file| dmlex::subset::read_headwords(std::io::BufReader::new(file))) {
+            Ok(headwords) => dmlex::subset::extract(&resource, |entry| headwords.contains(&entry.headword)),
+            Err(e) => panic!("Could not read the headword list {}: {}", path, e),
+        },
+        None => resource,
+    };
+    if let Some(other) = &args.diff {
+        let new = parse_other(registry, other, args);
+        let result = dmlex::diff::diff(&resource, &new);
+        let mut output = open_output(args);
+        let written = if args.diff_json {
+            writeln!(output, "{}", result.to_json())
         } else {
-            match write(std::io::stdout(), &args.output_format.into(), &resource, &args.default_namespace, args.ontolex) {
-                Ok(_) => (),
-                Err(e) => panic!("Could not write output file: {}", e),
-            }
+            write!(output, "{}", result)
+        };
+        if let Err(e) = written {
+            panic!("Could not write output file: {}", e);
+        }
+        if !result.is_empty() {
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.check_homographs {
+        let report = dmlex::homograph::check_homographs(&resource);
+        if let Err(e) = write!(open_output(args), "{}", report) {
+            panic!("Could not write output file: {}", e);
+        }
+        if !report.is_empty() {
+            std::process::exit(1);
+        }
+        return;
+    }
+    let resource = match &args.reverse {
+        Some(lang) => match dmlex::reverse::reverse(&resource, Some(lang)) {
+            Ok((reversed, report)) => {
+                eprint!("{}", report);
+                reversed
+            },
+            Err(e) => panic!("Could not reverse {}: {}", input_name, e),
+        },
+        None => resource,
+    };
+    let mut resource = resource;
+    if let Some(order) = &args.number_homographs {
+        let order = match order.as_str() {
+            "pos" => HomographOrder::PartOfSpeech,
+            "previous" => HomographOrder::PreviousNumber,
+            _ => HomographOrder::InputOrder,
+        };
+        let options = HomographOptions::default().with_order(order).with_number_unique(args.number_unique_homographs);
+        dmlex::homograph::number_homographs(&mut resource, &options);
+    }
+    if args.sort {
+        dmlex::collation::sort_entries(&mut resource);
+    }
+    if let Some(dir) = &args.split {
+        if let Err(e) = dmlex::split::split(dir, &resource, registry, output_format, &args.write_options()) {
+            panic!("Could not split into {}: {}", dir, e);
+        }
+        return;
+    }
+    if let Some(html_dir) = &args.html_dir {
+        match dmlex::html::write_site(html_dir, &resource, &args.html_templates()) {
+            Ok(report) => eprint!("{}", report),
+            Err(e) => panic!("Could not write HTML to {}: {}", html_dir, e),
+        }
+        return;
+    }
+    if let Some(stardict) = &args.stardict {
+        match dmlex::stardict::write_stardict(stardict, &resource) {
+            Ok(report) => eprint!("{}", report),
+            Err(e) => panic!("Could not write StarDict files {}: {}", stardict, e),
+        }
+        return;
+    }
+    if let Some(dictd) = &args.dictd {
+        match dmlex::dictd::write_dictd(dictd, &resource) {
+            Ok(report) => eprint!("{}", report),
+            Err(e) => panic!("Could not write dictd files {}: {}", dictd, e),
         }
+        return;
+    }
+    if let Some(hunspell) = &args.hunspell {
+        let options = dmlex::hunspell::HunspellOptions { infer_affixes: !args.hunspell_no_affixes };
+        match dmlex::hunspell::write_hunspell(hunspell, &resource, &options) {
+            Ok(report) => eprint!("{}", report),
+            Err(e) => panic!("Could not write Hunspell files {}: {}", hunspell, e),
+        }
+        return;
+    }
+    match registry.write_with_report(output_format, &mut open_output(args), &resource, &args.write_options()) {
+        Ok(report) => eprint!("{}", report),
+        Err(e) => panic!("Could not write output file: {}", e),
     }
 }
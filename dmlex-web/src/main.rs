@@ -1,6 +1,6 @@
 use yew::prelude::*;
 use web_sys::wasm_bindgen::JsCast;
-use dmlex::{parse, write, Format};
+use dmlex::{Format, FormatRegistry, ReadOptions, RootKind, WriteOptions};
 
 pub enum Msg {
     ChangeInputFormat(String),
@@ -12,8 +12,11 @@ pub enum Msg {
 }
 
 pub struct App {
-    input_format : Format,
-    output_format : Format,
+    registry : FormatRegistry,
+    /// The selected input format, or `None` to detect it from the input
+    input_format : Option<String>,
+    detected_format : Option<String>,
+    output_format : String,
     input : String,
     output : Result<String, String>,
     default_namespace : String,
@@ -23,19 +26,52 @@ pub struct App {
 impl App {
     fn update(&mut self) { 
         if self.input.is_empty() {
+            self.detected_format = None;
             self.output = Ok(String::new());
             return;
         }
         let input_buf = self.input.as_bytes();
-        let resource = match parse(input_buf, &self.input_format, &Some(self.default_namespace.clone())) {
-            Ok(r) => r,
-            Err(e) => {
-                self.output = Err(format!("{:?}", e));
+        self.detected_format = match &self.input_format {
+            Some(format) => Some(format.clone()),
+            None => self.registry.detect_format(input_buf, None),
+        };
+        let input_format = match &self.detected_format {
+            Some(format) => format.clone(),
+            None => {
+                self.output = Err("Could not detect the input format".to_string());
                 return;
             }
         };
+        let read_options = ReadOptions {
+            default_namespace: Some(self.default_namespace.clone()),
+        };
+        let write_options = WriteOptions {
+            default_namespace: Some(self.default_namespace.clone()),
+            ontolex: self.ontolex,
+            ..WriteOptions::default()
+        };
+        let is_entry = self.registry.detect_root(&input_format, input_buf) == Some(RootKind::Entry);
         let mut out = Vec::new();
-        self.output = match write(&mut out, &self.output_format, &resource, &Some(self.default_namespace.clone()), self.ontolex) {
+        let result = if is_entry {
+            let entry = match self.registry.parse_entry(&input_format, &mut &input_buf[..], &read_options) {
+                Ok(e) => e,
+                Err(e) => {
+                    self.output = Err(format!("{:?}", e));
+                    return;
+                }
+            };
+            self.registry.write_entry(&self.output_format, &mut out, &entry, &write_options)
+        } else {
+            let resource = match self.registry.parse(&input_format, &mut &input_buf[..], &read_options) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.output = Err(format!("{:?}", e));
+                    return;
+                }
+            };
+            self.registry.write(&self.output_format, &mut out, &resource, &write_options)
+        };
+        self.output = match result {
             Ok(_) => Ok(String::from_utf8(out).unwrap()),
             Err(e) => Err(format!("{:?}", e)),
         };
@@ -48,8 +84,10 @@ impl Component for App {
 
     fn create(_ctx: &Context<Self>) -> Self {
         App {
-            input_format : Format::XML,
-            output_format : Format::JSON,
+            registry : FormatRegistry::default(),
+            input_format : None,
+            detected_format : None,
+            output_format : Format::JSON.name().to_string(),
             input: String::new(),
             output: Ok(String::new()),
             default_namespace: String::new(),
@@ -60,22 +98,16 @@ impl Component for App {
     fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::ChangeInputFormat(input) => {
-                match input.as_str() {
-                    "xml" => self.input_format = Format::XML,
-                    "json" => self.input_format = Format::JSON,
-                    "rdf" => self.input_format = Format::RDF,
-                    _ => self.input_format = Format::XML,
+                if self.registry.reader(&input).is_some() {
+                    self.input_format = Some(input);
+                } else {
+                    self.input_format = None;
                 }
                 self.update();
                 true
             }
             Msg::ChangeOutputFormat(output) => {
-                match output.as_str() {
-                    "xml" => self.output_format = Format::XML,
-                    "json" => self.output_format = Format::JSON,
-                    "rdf" => self.output_format = Format::RDF,
-                    _ => self.output_format = Format::XML,
-                }
+                self.output_format = output;
                 self.update();
                 true
             }
@@ -100,12 +132,8 @@ impl Component for App {
     }
 
     fn view(&self, ctx:&Context<Self>) -> Html {
-        let change_input1 = ctx.link().callback(|s| Msg::ChangeInputFormat(s));
-        let change_input2 = ctx.link().callback(|s| Msg::ChangeInputFormat(s));
-        let change_input3 = ctx.link().callback(|s| Msg::ChangeInputFormat(s));
-        let change_output1 = ctx.link().callback(|s| Msg::ChangeOutputFormat(s));
-        let change_output2 = ctx.link().callback(|s| Msg::ChangeOutputFormat(s));
-        let change_output3 = ctx.link().callback(|s| Msg::ChangeOutputFormat(s));
+        let change_input_format = ctx.link().callback(|s| Msg::ChangeInputFormat(s));
+        let change_output_format = ctx.link().callback(|s| Msg::ChangeOutputFormat(s));
         let change_input = ctx.link().callback(|s| Msg::ChangeInput(s));
         let change_default_namespace = ctx.link().callback(|s| Msg::ChangeDefaultNamespace(s));
         let change_ontolex = ctx.link().callback(|s| Msg::ChangeOntolex(s));
@@ -120,25 +148,27 @@ impl Component for App {
                     <ul class="items-center text-sm font-medium text-gray-900 sm:flex">
                         <li>
                             <div class="flex items-center ps-3">
-                                <input id="input-xml" type="radio" value="" name="input-format" class="w-4 h-4"
-                                checked={self.input_format == Format::XML} onclick={move |_| change_input1.emit("xml".to_string())}/>
-                                <label for="input-xml" class="text-gray-900">{ "XML" }</label>
-                            </div>
-                        </li>
-                        <li>
-                            <div class="flex items-center ps-3">
-                                <input id="input-json" type="radio" value="" name="input-format" class="w-4 h-4"
-                                checked={self.input_format == Format::JSON} onclick={move |_| change_input2.emit("json".to_string())}/>
-                                <label for="input-json" class="text-gray-900 ">{ "JSON" }</label>
+                                <input id="input-auto" type="radio" value="" name="input-format" class="w-4 h-4"
+                                checked={self.input_format.is_none()} onclick={
+                                    let change_input_format = change_input_format.clone();
+                                    move |_| change_input_format.emit("auto".to_string())
+                                }/>
+                                <label for="input-auto" class="text-gray-900">{ "Auto" }</label>
                             </div>
                         </li>
+                        { for self.registry.reader_names().into_iter().map(|name| {
+                            let change_input_format = change_input_format.clone();
+                            let format = name.to_string();
+                            html! {
                         <li>
                             <div class="flex items-center ps-3">
-                                <input id="input-rdf" type="radio" value="" name="input-format" class="w-4 h-4"
-                                checked={self.input_format == Format::RDF} onclick={move |_| change_input3.emit("rdf".to_string())}/>
-                                <label for="input-rdf" class="text-gray-900">{ "RDF" }</label>
+                                <input id={format!("input-{}", name)} type="radio" value="" name="input-format" class="w-4 h-4"
+                                checked={self.input_format.as_deref() == Some(name)} onclick={move |_| change_input_format.emit(format.clone())}/>
+                                <label for={format!("input-{}", name)} class="text-gray-900">{ name.to_uppercase() }</label>
                             </div>
                         </li>
+                            }
+                        }) }
                     </ul>
 
                     </div>
@@ -147,32 +177,24 @@ impl Component for App {
 
                     <h3 class="mt-4 font-semibold text-gray-900">{ "Output Format" }</h3>
                     <ul class="items-center text-sm font-medium text-gray-900 sm:flex">
+                        { for self.registry.writer_names().into_iter().map(|name| {
+                            let change_output_format = change_output_format.clone();
+                            let format = name.to_string();
+                            html! {
                         <li>
                             <div class="flex items-center ps-3">
-                                <input id="output-xml" type="radio" value="" name="output-format" class="w-4 h-4"
-                                checked={self.output_format == Format::XML} onclick={move |_| change_output1.emit("xml".to_string())}/>
-                                <label for="output-xml" class="text-gray-900">{ "XML" }</label>
-                            </div>
-                        </li>
-                        <li>
-                            <div class="flex items-center ps-3">
-                                <input id="output-json" type="radio" value="" name="output-format" class="w-4 h-4"
-                                checked={self.output_format == Format::JSON} onclick={move |_| change_output2.emit("json".to_string())}/>
-                                <label for="output-json" class="text-gray-900 ">{ "JSON" }</label>
-                            </div>
-                        </li>
-                        <li>
-                            <div class="flex items-center ps-3">
-                                <input id="output-rdf" type="radio" value="" name="output-format" class="w-4 h-4"
-                                checked={self.output_format == Format::RDF} onclick={move |_| change_output3.emit("rdf".to_string())}/>
-                                <label for="output-rdf" class="text-gray-900">{ "RDF" }</label>
+                                <input id={format!("output-{}", name)} type="radio" value="" name="output-format" class="w-4 h-4"
+                                checked={self.output_format == name} onclick={move |_| change_output_format.emit(format.clone())}/>
+                                <label for={format!("output-{}", name)} class="text-gray-900">{ name.to_uppercase() }</label>
                             </div>
                         </li>
+                            }
+                        }) }
                     </ul>
 
                     </div>
                     { 
-                        if self.input_format == Format::RDF || self.output_format == Format::RDF {
+                        if self.input_format.as_deref() == Some(Format::RDF.name()) || self.detected_format.as_deref() == Some(Format::RDF.name()) || self.output_format == Format::RDF.name() {
                             html! { <>
                     <h3 class="mt-4 font-semibold text-gray-900">{ "Default Namespace" }</h3>
                     <div class="flex w-full">
@@ -0,0 +1,320 @@
+//! Export of the headword translations of a resource as an Apertium
+//! bilingual dictionary (`.dix`)
+//!
+//! Each headword translation becomes an entry pairing the headword on the
+//! left with the translation on the right, each followed by the Apertium
+//! symbols of its part of speech. The parts of speech of the translation are
+//! used for the right side if it has any, otherwise those of the entry.
+//! Parts of speech are mapped to symbols with a table, and translations
+//! whose part of speech cannot be mapped are left out and reported.
+use crate::model::*;
+use crate::registry::{ResourceWriter, WriteOptions};
+use crate::report::ConversionReport;
+use crate::WriteError;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::{BufRead, Write};
+use thiserror::Error;
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
+
+type Result<T> = std::result::Result<T, ApertiumError>;
+
+/// The symbols of common part of speech tags, used by `ApertiumOptions::default`
+const COMMON_SYMBOLS : [(&str, &str); 22] = [
+    ("noun", "n"), ("n", "n"),
+    ("proper noun", "np"), ("propn", "np"),
+    ("verb", "vblex"), ("v", "vblex"),
+    ("adjective", "adj"), ("adj", "adj"),
+    ("adverb", "adv"), ("adv", "adv"),
+    ("pronoun", "prn"), ("pron", "prn"),
+    ("preposition", "pr"), ("adp", "pr"),
+    ("conjunction", "cnjcoo"), ("cconj", "cnjcoo"),
+    ("determiner", "det"), ("det", "det"),
+    ("numeral", "num"), ("num", "num"),
+    ("interjection", "ij"), ("intj", "ij"),
+];
+
+/// Options for the export
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApertiumOptions {
+    /// The symbols for each part of speech tag, e.g. `n-masc` to `n` and `m`
+    pub symbols: BTreeMap<String, Vec<String>>,
+    /// Only export translations into this language. Translations without a
+    /// language are exported if the resource has a single translation
+    /// language and it is this one. If not given, the only translation
+    /// language of the resource, or else of its translations, is used, as a
+    /// bilingual dictionary has one language on each side.
+    pub target_lang: Option<String>,
+}
+
+impl Default for ApertiumOptions {
+    /// Options that map common part of speech tags, such as `noun` or the
+    /// Universal Dependencies tags, to their Apertium symbols
+    fn default() -> ApertiumOptions {
+        let mut options = ApertiumOptions::empty();
+        for (tag, symbol) in COMMON_SYMBOLS {
+            options.symbols.insert(tag.to_string(), vec![symbol.to_string()]);
+        }
+        options
+    }
+}
+
+impl ApertiumOptions {
+    /// Options without any mapping of parts of speech
+    pub fn empty() -> ApertiumOptions {
+        ApertiumOptions {
+            symbols: BTreeMap::new(),
+            target_lang: None,
+        }
+    }
+
+    /// Map a part of speech tag to symbols written as in Apertium
+    /// documentation, separated by dots, e.g. `n.m`
+    pub fn with_pos(mut self, tag : &str, symbols : &str) -> ApertiumOptions {
+        self.symbols.insert(tag.to_string(), symbols.split('.').filter(|s| !s.is_empty()).map(|s| s.to_string()).collect());
+        self
+    }
+
+    pub fn with_target_lang(mut self, lang : &str) -> ApertiumOptions {
+        self.target_lang = Some(lang.to_string());
+        self
+    }
+
+    /// Add the mappings of a table with a part of speech tag and its dotted
+    /// symbols on each line, separated by a tab. Empty lines and lines
+    /// starting with `#` are skipped.
+    pub fn read_mapping<R : BufRead>(mut self, input : R) -> Result<ApertiumOptions> {
+        for (i, line) in input.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('\t') {
+                Some((tag, symbols)) => self = self.with_pos(tag.trim(), symbols.trim()),
+                None => return Err(ApertiumError::InvalidMapping(i + 1, line)),
+            }
+        }
+        Ok(self)
+    }
+
+    /// The symbols of the first part of speech that can be mapped
+    fn symbols_for<'a>(&'a self, parts_of_speech : &[String]) -> Option<&'a [String]> {
+        parts_of_speech.iter().find_map(|pos| self.symbols.get(pos).or_else(|| self.symbols.get(&pos.to_lowercase())))
+            .map(|s| s.as_slice())
+    }
+}
+
+/// The `<l>` or `<r>` element: the text with spaces as `<b/>` followed by
+/// the symbols
+fn side(name : &str, text : &str, symbols : &[String]) -> String {
+    let words : Vec<String> = text.split_whitespace().map(|w| escape_str_pcdata(w).to_string()).collect();
+    let symbols : String = symbols.iter().map(|s| format!("<s n=\"{}\"/>", escape_str_attribute(s))).collect();
+    format!("<{}>{}{}</{}>", name, words.join("<b/>"), symbols, name)
+}
+
+/// The language of a translation, or the only translation language
+fn lang_of<'a>(translation : &'a HeadwordTranslation, default_lang : Option<&'a str>) -> Option<&'a str> {
+    translation.lang_code.as_ref().map(|l| l.0.as_str()).or(default_lang)
+}
+
+/// Write the headword translations of a resource as an Apertium bilingual
+/// dictionary
+pub fn write_dix<W : Write>(mut output : W, resource : &LexicographicResource, options : &ApertiumOptions) -> Result<ConversionReport> {
+    let mut report = ConversionReport::new();
+    let default_lang = match resource.translation_languages.as_slice() {
+        [lang] => Some(lang.as_str()),
+        _ => None,
+    };
+    let target_lang = match options.target_lang.as_deref().or(default_lang) {
+        Some(lang) => Some(lang),
+        None => {
+            let langs : BTreeSet<&str> = resource.entries.iter()
+                .flat_map(|e| e.senses.iter())
+                .flat_map(|s| s.headword_translations.iter())
+                .filter_map(|t| lang_of(t, default_lang))
+                .collect();
+            if langs.len() > 1 {
+                return Err(ApertiumError::AmbiguousLanguage(langs.into_iter().collect::<Vec<_>>().join(", ")));
+            }
+            langs.into_iter().next()
+        }
+    };
+    let mut skipped : BTreeMap<&str, usize> = BTreeMap::new();
+    let mut used_symbols = BTreeSet::new();
+    let mut pairs = Vec::new();
+    let mut seen = HashSet::new();
+    for (i, entry) in resource.entries.iter().enumerate() {
+        let location = entry.id.clone().unwrap_or_else(|| format!("entry {}", i + 1));
+        let entry_symbols = options.symbols_for(&entry.parts_of_speech);
+        for translation in entry.senses.iter().flat_map(|s| s.headword_translations.iter()) {
+            let lang = lang_of(translation, default_lang);
+            if target_lang.is_some() && lang != target_lang {
+                *skipped.entry(lang.unwrap_or("no language")).or_default() += 1;
+                continue;
+            }
+            let Some(left) = entry_symbols else {
+                if entry.parts_of_speech.is_empty() {
+                    report.warn(&location, format!("Translation {} not mapped: the entry has no part of speech", translation.text));
+                } else {
+                    report.warn(&location, format!("Translation {} not mapped: no symbols for {}", translation.text, entry.parts_of_speech.join(", ")));
+                }
+                continue;
+            };
+            let right = if translation.parts_of_speech.is_empty() {
+                left
+            } else {
+                match options.symbols_for(&translation.parts_of_speech) {
+                    Some(right) => right,
+                    None => {
+                        report.warn(&location, format!("Translation {} not mapped: no symbols for {}", translation.text, translation.parts_of_speech.join(", ")));
+                        continue;
+                    }
+                }
+            };
+            let pair = (entry.headword.clone(), left.to_vec(), translation.text.clone(), right.to_vec());
+            if seen.insert(pair.clone()) {
+                used_symbols.extend(left.iter().chain(right.iter()).cloned());
+                pairs.push(pair);
+            }
+        }
+    }
+    for (lang, count) in skipped {
+        report.warn("lexicographicResource", format!("{} translation(s) into {} left out", count, lang));
+    }
+    // Whitespace is significant inside entries, so they are written on one
+    // line each rather than with an XML writer that indents them
+    writeln!(output, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(output, "<dictionary>")?;
+    writeln!(output, "  <alphabet/>")?;
+    writeln!(output, "  <sdefs>")?;
+    for symbol in used_symbols.iter() {
+        writeln!(output, "    <sdef n=\"{}\"/>", escape_str_attribute(symbol))?;
+    }
+    writeln!(output, "  </sdefs>")?;
+    writeln!(output, "  <section id=\"main\" type=\"standard\">")?;
+    for (headword, left, text, right) in pairs.iter() {
+        writeln!(output, "    <e><p>{}{}</p></e>", side("l", headword, left), side("r", text, right))?;
+    }
+    writeln!(output, "  </section>")?;
+    writeln!(output, "</dictionary>")?;
+    Ok(report)
+}
+
+/// Apertium bilingual dictionaries as a format of the registry
+#[derive(Debug, Clone, Default)]
+pub struct ApertiumFormat {
+    pub options : ApertiumOptions,
+}
+
+impl ResourceWriter for ApertiumFormat {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> std::result::Result<(), WriteError> {
+        self.write_resource_with_report(output, resource, options)?;
+        Ok(())
+    }
+
+    fn write_resource_with_report(&self, output : &mut dyn Write, resource : &LexicographicResource, _options : &WriteOptions) -> std::result::Result<ConversionReport, WriteError> {
+        Ok(write_dix(output, resource, &self.options)?)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ApertiumError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Line {0} of the mapping is not a tag and symbols separated by a tab: {1}")]
+    InvalidMapping(usize, String),
+    #[error("The resource has translations into {0}, choose the target language")]
+    AmbiguousLanguage(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml_tree::{read_tree, Element};
+    use crate::{parse_entry, Format};
+    use std::fs::File;
+
+    fn translation(text : &str, lang : &str, parts_of_speech : &[&str]) -> HeadwordTranslation {
+        HeadwordTranslation {
+            text: text.to_string(),
+            lang_code: Some(LangCode(lang.to_string())),
+            parts_of_speech: parts_of_speech.iter().map(|p| p.to_string()).collect(),
+            ..HeadwordTranslation::default()
+        }
+    }
+
+    fn entry(headword : &str, pos : &str, translations : Vec<HeadwordTranslation>) -> Entry {
+        Entry {
+            headword: headword.to_string(),
+            parts_of_speech: vec![pos.to_string()],
+            senses: vec![Sense { headword_translations: translations, ..Sense::default() }],
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn test_dix() {
+        let resource = LexicographicResource {
+            lang_code: LangCode("en".to_string()),
+            entries: vec![
+                entry("cat", "noun", vec![translation("gato", "es", &["n-masc"]), translation("Katze", "de", &[])]),
+                entry("ice cream", "noun", vec![translation("helado", "es", &[])]),
+                entry("run", "verb", vec![translation("correr", "es", &[]), translation("carrera", "es", &["noun-fem"])]),
+                entry("hello", "exclamation", vec![translation("hola", "es", &[])]),
+            ],
+            ..LexicographicResource::default()
+        };
+        let options = ApertiumOptions::default().with_pos("n-masc", "n.m").with_target_lang("es");
+        let mut out = Vec::new();
+        let report = write_dix(&mut out, &resource, &options).unwrap();
+        assert_eq!(report.warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(), vec![
+            "entry 3: Translation carrera not mapped: no symbols for noun-fem",
+            "entry 4: Translation hola not mapped: no symbols for exclamation",
+            "lexicographicResource: 1 translation(s) into de left out",
+        ]);
+        let text = String::from_utf8(out).unwrap();
+        let root = read_tree(text.as_bytes()).unwrap();
+        let sdefs : Vec<&str> = root.child("sdefs").unwrap().elements().map(|e| e.attr("n").unwrap()).collect();
+        assert_eq!(sdefs, vec!["m", "n", "vblex"]);
+        let entries : Vec<&Element> = root.child("section").unwrap().elements().collect();
+        assert_eq!(entries.len(), 3);
+        let left = entries[1].child("p").unwrap().child("l").unwrap();
+        assert_eq!(left.elements().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["b", "s"]);
+        assert_eq!(left.text(), "icecream");
+        assert_eq!(entries[2].child("p").unwrap().child("r").unwrap().text(), "correr");
+        // Without a target language, the translations must be into one language
+        let options = ApertiumOptions::default().with_pos("n-masc", "n.m");
+        assert!(matches!(write_dix(&mut Vec::new(), &resource, &options), Err(ApertiumError::AmbiguousLanguage(langs)) if langs == "de, es"));
+        // or the resource must have a single translation language
+        let mut resource = resource;
+        resource.translation_languages = vec!["es".to_string()];
+        let mut out = Vec::new();
+        let report = write_dix(&mut out, &resource, &options).unwrap();
+        assert_eq!(read_tree(out.as_slice()).unwrap().child("section").unwrap().elements().count(), 3);
+        assert_eq!(report.warnings.last().unwrap().message, "1 translation(s) into de left out");
+    }
+
+    #[test]
+    fn test_mapping_table() {
+        let options = ApertiumOptions::empty().read_mapping("# tag\tsymbols\nn-masc\tn.m\n\nverb\tvblex\n".as_bytes()).unwrap();
+        assert_eq!(options.symbols.get("n-masc"), Some(&vec!["n".to_string(), "m".to_string()]));
+        assert_eq!(options.symbols.len(), 2);
+        assert!(matches!(ApertiumOptions::empty().read_mapping("noun n".as_bytes()), Err(ApertiumError::InvalidMapping(1, _))));
+    }
+
+    #[test]
+    fn test_example_entry() {
+        let entry = parse_entry(File::open("examples/9.xml").unwrap(), &Format::XML, &None).unwrap();
+        let resource = LexicographicResource {
+            lang_code: LangCode("de".to_string()),
+            entries: vec![entry],
+            ..LexicographicResource::default()
+        };
+        let report = write_dix(&mut Vec::new(), &resource, &ApertiumOptions::default()).unwrap();
+        assert_eq!(report.warnings[0].to_string(), "treppenwitz: Translation staircase wit not mapped: no symbols for n-masc");
+        let mut out = Vec::new();
+        let report = write_dix(&mut out, &resource, &ApertiumOptions::default().with_pos("n-masc", "n.m")).unwrap();
+        assert!(report.is_empty());
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("\n    <e><p><l>Treppenwitz<s n=\"n\"/><s n=\"m\"/></l><r>staircase<b/>wit<s n=\"n\"/><s n=\"m\"/></r></p></e>\n"), "{}", text);
+    }
+}
@@ -0,0 +1,101 @@
+//! Plain text articles for offline dictionary readers, shared by the
+//! StarDict and dictd exporters
+use crate::model::*;
+use crate::report::ConversionReport;
+
+const SUPERSCRIPT_DIGITS : [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+/// The headword followed by the homograph number in superscript digits
+pub(crate) fn headword(entry : &Entry) -> String {
+    let mut headword = entry.headword.clone();
+    if let Some(number) = entry.homograph_number {
+        headword.extend(number.to_string().chars()
+            .map(|c| SUPERSCRIPT_DIGITS[c.to_digit(10).unwrap_or(0) as usize]));
+    }
+    headword
+}
+
+/// The words an entry can be looked up by: its headword and the inflected
+/// forms that differ from it
+pub(crate) fn lookup_forms(entry : &Entry) -> Vec<&str> {
+    let mut forms = vec![entry.headword.as_str()];
+    for form in entry.inflected_forms.iter() {
+        if !forms.contains(&form.text.as_str()) {
+            forms.push(form.text.as_str());
+        }
+    }
+    forms
+}
+
+/// Render an entry as plain text, optionally starting with the headword.
+/// Senses are numbered if there are several, with the headword
+/// translations after an arrow and the examples after a bullet.
+pub(crate) fn plain_text(entry : &Entry, with_headword : bool) -> String {
+    let mut lines = Vec::new();
+    let mut grammar = Vec::new();
+    if with_headword {
+        grammar.push(headword(entry));
+    }
+    for transcription in entry.pronunciations.iter().flat_map(|p| p.transcriptions.iter()) {
+        grammar.push(format!("/{}/", transcription.text));
+    }
+    grammar.extend(entry.parts_of_speech.iter().cloned());
+    grammar.extend(entry.labels.iter().cloned());
+    if !grammar.is_empty() {
+        lines.push(grammar.join(" "));
+    }
+    if !entry.inflected_forms.is_empty() {
+        let forms : Vec<String> = entry.inflected_forms.iter().map(|form| match &form.tag {
+            Some(tag) => format!("{} {}", tag, form.text),
+            None => form.text.clone(),
+        }).collect();
+        lines.push(format!("forms: {}", forms.join(", ")));
+    }
+    let numbered = entry.senses.len() > 1;
+    for (i, sense) in entry.senses.iter().enumerate() {
+        let mut parts = Vec::new();
+        if numbered {
+            parts.push(format!("{}.", i + 1));
+        }
+        if let Some(indicator) = &sense.indicator {
+            parts.push(format!("({})", indicator));
+        }
+        if !sense.labels.is_empty() {
+            parts.push(format!("[{}]", sense.labels.join(", ")));
+        }
+        let definitions : Vec<&str> = sense.definitions.iter().map(|d| d.text.as_str())
+            .chain(sense.headword_explanations.iter().map(|e| e.text.as_str()))
+            .collect();
+        if !definitions.is_empty() {
+            parts.push(definitions.join("; "));
+        }
+        lines.push(parts.join(" "));
+        if !sense.headword_translations.is_empty() {
+            let translations : Vec<String> = sense.headword_translations.iter().map(|t| match &t.lang_code {
+                Some(lang) => format!("{} ({})", t.text, lang.0),
+                None => t.text.clone(),
+            }).collect();
+            lines.push(format!("   → {}", translations.join(", ")));
+        }
+        for example in sense.examples.iter() {
+            let mut line = format!("   • {}", example.text);
+            for translation in example.example_translations.iter() {
+                line.push_str(" — ");
+                line.push_str(&translation.text);
+            }
+            lines.push(line);
+        }
+    }
+    lines.retain(|l| !l.is_empty());
+    lines.join("\n")
+}
+
+/// Warn once about the parts of a resource that are not in the articles
+pub(crate) fn note_lost(resource : &LexicographicResource, report : &mut ConversionReport) {
+    if resource.entries.iter().any(|e| !e.etymologies.is_empty()) {
+        report.warn("lexicographicResource", "Etymologies are not exported");
+    }
+    if !resource.relations.is_empty() {
+        report.warn("lexicographicResource", "Relations are not exported");
+    }
+}
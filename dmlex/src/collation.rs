@@ -0,0 +1,279 @@
+//! Alphabetical sorting of headwords for the language of a resource
+//!
+//! Words are compared on three levels, as in the Unicode collation
+//! algorithm: first by their letters without accents and case, then by their
+//! accents and then by their case, so that `cote < Cote < côte < côté`.
+//! Spaces, hyphens, apostrophes and other punctuation are ignored unless the
+//! words are otherwise equal. Accented Latin letters are compared as their
+//! base letter, and `ß`, `æ` and `œ` as `ss`, `ae` and `oe`.
+//!
+//! A language may tailor this order:
+//!
+//! * Letters can sort as separate letters after another one, such as `ñ`
+//!   after `n` in Spanish, `å`, `ä` and `ö` after `z` in Swedish, or the
+//!   Welsh digraphs `ch`, `dd`, `ff`, `ng`, `ll`, `ph`, `rh` and `th`.
+//!   Letters of several characters are matched longest first.
+//! * In Irish the prefixes of initial mutations are ignored, so that
+//!   `tAthair`, `t-athair` and `nAthair` sort with `athair` and `bhFuinneog`
+//!   with `fuinneog`. Lenition, as in `bhád`, cannot be told apart from the
+//!   spelling of the word and is not ignored.
+use crate::model::*;
+use std::cmp::Ordering;
+
+/// Accented Latin letters and the base letters they sort as. The position
+/// of a letter in its group gives its order on the accent level.
+const ACCENTED : [(&str, char); 21] = [
+    ("àáâãäåāăąǎ", 'a'),
+    ("çćĉċč", 'c'),
+    ("ďđ", 'd'),
+    ("èéêëēĕėęě", 'e'),
+    ("ĝğġģ", 'g'),
+    ("ĥħ", 'h'),
+    ("ìíîïĩīĭįıǐ", 'i'),
+    ("ĵ", 'j'),
+    ("ķ", 'k'),
+    ("ĺļľŀł", 'l'),
+    ("ñńņňŉ", 'n'),
+    ("òóôõöøōŏőơǒ", 'o'),
+    ("ŕŗř", 'r'),
+    ("śŝşšș", 's'),
+    ("ţťŧț", 't'),
+    ("ùúûüũūŭůűųưǔ", 'u'),
+    ("ŵ", 'w'),
+    ("ýÿŷ", 'y'),
+    ("źżž", 'z'),
+    ("ð", 'd'),
+    ("þ", 't'),
+];
+
+/// Letters that sort as two letters
+const EXPANSIONS : [(char, &str); 3] = [('ß', "ss"), ('æ', "ae"), ('œ', "oe")];
+
+/// The tailorings of languages as letters in order and the letter they
+/// sort after
+const TAILORINGS : [(&str, &[(&str, char)]); 9] = [
+    ("cy", &[("ch", 'c'), ("dd", 'd'), ("ff", 'f'), ("ng", 'g'), ("ll", 'l'), ("ph", 'p'), ("rh", 'r'), ("th", 't')]),
+    ("da", &[("æ", 'z'), ("ø", 'z'), ("å", 'z')]),
+    ("es", &[("ñ", 'n')]),
+    ("et", &[("š", 's'), ("z", 's'), ("ž", 's'), ("õ", 'w'), ("ä", 'w'), ("ö", 'w'), ("ü", 'w')]),
+    ("fi", &[("å", 'z'), ("ä", 'z'), ("ö", 'z')]),
+    ("is", &[("á", 'a'), ("ð", 'd'), ("é", 'e'), ("í", 'i'), ("ó", 'o'), ("ú", 'u'), ("ý", 'y'), ("þ", 'z'), ("æ", 'z'), ("ö", 'z')]),
+    ("nb", &[("æ", 'z'), ("ø", 'z'), ("å", 'z')]),
+    ("nn", &[("æ", 'z'), ("ø", 'z'), ("å", 'z')]),
+    ("sv", &[("å", 'z'), ("ä", 'z'), ("ö", 'z')]),
+];
+
+/// The prefixes of Irish eclipsis and of `t`, `n` and `h` before a capital
+/// letter, as in `mBád` or `tAthair`
+const IRISH_PREFIXES : [&str; 8] = ["bh", "b", "d", "g", "h", "m", "n", "t"];
+
+/// The number of letters that can sort after one letter
+const SLOTS : u32 = 32;
+
+/// The order of the words of a language
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Collation {
+    /// Letters that sort after another letter, in lower case and in order
+    letters: Vec<(String, char)>,
+    /// Ignore the prefixes of Irish initial mutations
+    irish_mutations: bool,
+}
+
+/// One letter of a word: its order on the letter, accent and case levels
+#[derive(Debug, Clone, Copy)]
+struct Unit {
+    primary: u32,
+    secondary: u32,
+    tertiary: u32,
+}
+
+impl Collation {
+    /// The collation of a language given by a language code such as `es` or
+    /// `cy-GB`. Languages without a tailoring use the common order.
+    pub fn for_language(lang : &str) -> Collation {
+        let language = lang.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+        let mut collation = Collation::default();
+        if let Some((_, letters)) = TAILORINGS.iter().find(|(l, _)| *l == language) {
+            for (letter, after) in letters.iter() {
+                collation = collation.with_letter(letter, *after);
+            }
+        }
+        collation.irish_mutations = language == "ga";
+        collation
+    }
+
+    /// The collation of the language of a resource
+    pub fn for_resource(resource : &LexicographicResource) -> Collation {
+        Collation::for_language(&resource.lang_code.0)
+    }
+
+    /// Sort a letter after another letter, and after the letters already
+    /// sorted after that letter
+    pub fn with_letter(mut self, letter : &str, after : char) -> Collation {
+        self.letters.push((letter.to_lowercase(), after));
+        self
+    }
+
+    /// The tailored letter at the start of a word in lower case, with its
+    /// order
+    fn tailored(&self, rest : &str) -> Option<(&str, u32)> {
+        self.letters.iter()
+            .filter(|(l, _)| !l.is_empty() && rest.starts_with(l.as_str()))
+            .max_by_key(|(l, _)| l.len())
+            .map(|(letter, after)| {
+                let slot = self.letters.iter().filter(|(_, a)| a == after)
+                    .position(|(l, _)| l == letter).unwrap_or_default() as u32;
+                (letter.as_str(), *after as u32 * SLOTS + slot + 1)
+            })
+    }
+
+    /// The word without the prefix of an initial mutation
+    fn without_prefix<'a>(&self, word : &'a str) -> &'a str {
+        if !self.irish_mutations {
+            return word;
+        }
+        for prefix in IRISH_PREFIXES.iter() {
+            if let Some(rest) = word.strip_prefix(prefix) {
+                let next = rest.chars().next();
+                if next.is_some_and(|c| c.is_uppercase()) {
+                    return rest;
+                }
+                if (*prefix == "t" || *prefix == "n") && rest.starts_with('-')
+                        && rest[1..].starts_with(['a', 'e', 'i', 'o', 'u', 'á', 'é', 'í', 'ó', 'ú']) {
+                    return &rest[1..];
+                }
+            }
+        }
+        word
+    }
+
+    /// The letters of a word with their text in lower case, skipping
+    /// punctuation
+    fn units(&self, word : &str) -> Vec<(String, Unit)> {
+        let word = self.without_prefix(word);
+        let lower = word.to_lowercase();
+        // Fall back to the word by character if lower-casing changes its length
+        let same_length = lower.chars().count() == word.chars().count();
+        let mut cases = word.chars().map(|c| if c.is_uppercase() { 2 } else { 1 });
+        let mut units = Vec::new();
+        let mut rest = lower.as_str();
+        while let Some(c) = rest.chars().next() {
+            if let Some((letter, primary)) = self.tailored(rest) {
+                let tertiary = if same_length { cases.next().unwrap_or(1) } else { 1 };
+                for _ in 1..letter.chars().count() {
+                    cases.next();
+                }
+                units.push((letter.to_string(), Unit { primary, secondary: 1, tertiary }));
+                rest = &rest[letter.len()..];
+                continue;
+            }
+            rest = &rest[c.len_utf8()..];
+            let tertiary = if same_length { cases.next().unwrap_or(1) } else { 1 };
+            if !c.is_alphanumeric() {
+                continue;
+            }
+            if let Some((_, expansion)) = EXPANSIONS.iter().find(|(e, _)| *e == c) {
+                for (i, base) in expansion.chars().enumerate() {
+                    let secondary = if i == 0 { 2 } else { 1 };
+                    units.push((base.to_string(), Unit { primary: base as u32 * SLOTS, secondary, tertiary }));
+                }
+                continue;
+            }
+            let (base, secondary) = ACCENTED.iter()
+                .find_map(|(letters, base)| letters.chars().position(|l| l == c).map(|i| (*base, i as u32 + 2)))
+                .unwrap_or((c, 1));
+            units.push((base.to_string(), Unit { primary: base as u32 * SLOTS, secondary, tertiary }));
+        }
+        units
+    }
+
+    /// The sort key of a word, which compares as the words do
+    pub fn key(&self, word : &str) -> Vec<u32> {
+        let units : Vec<Unit> = self.units(word).into_iter().map(|(_, u)| u).collect();
+        let mut key : Vec<u32> = units.iter().map(|u| u.primary).collect();
+        key.push(0);
+        key.extend(units.iter().map(|u| u.secondary));
+        key.push(0);
+        key.extend(units.iter().map(|u| u.tertiary));
+        key.push(0);
+        key.extend(word.chars().map(|c| c as u32));
+        key
+    }
+
+    /// Compare two words
+    pub fn compare(&self, a : &str, b : &str) -> Ordering {
+        self.key(a).cmp(&self.key(b))
+    }
+
+    /// Compare two entries by their headwords and then by their homograph
+    /// numbers
+    pub fn compare_entries(&self, a : &Entry, b : &Entry) -> Ordering {
+        self.compare(&a.headword, &b.headword).then_with(|| a.homograph_number.cmp(&b.homograph_number))
+    }
+
+    /// The letter a word is listed under in an alphabetical index, in upper
+    /// case and without accents unless the language sorts the accented
+    /// letter separately
+    pub fn initial(&self, word : &str) -> String {
+        match self.units(word).into_iter().next() {
+            Some((letter, _)) => {
+                let mut chars = letter.chars();
+                chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+            },
+            None => String::new(),
+        }
+    }
+}
+
+/// Sort the entries of a resource alphabetically for its language, keeping
+/// the input order of entries with the same headword and homograph number
+pub fn sort_entries(resource : &mut LexicographicResource) {
+    let collation = Collation::for_resource(resource);
+    resource.entries.sort_by_cached_key(|e| (collation.key(&e.headword), e.homograph_number));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    fn sorted(lang : &str, words : &[&str]) -> Vec<String> {
+        let collation = Collation::for_language(lang);
+        let mut words : Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        words.sort_by(|a, b| collation.compare(a, b));
+        words
+    }
+
+    #[test]
+    fn test_levels() {
+        assert_eq!(sorted("fr", &["côté", "Cote", "côte", "cote", "coter", "co-op", "coop", "Straße", "strasse", "éa"]),
+            vec!["co-op", "coop", "cote", "Cote", "côte", "côté", "coter", "éa", "strasse", "Straße"]);
+        assert_eq!(sorted("en", &["b", "B", "a", "Á", "10", "2"]), vec!["10", "2", "a", "Á", "b", "B"]);
+    }
+
+    #[test]
+    fn test_tailorings() {
+        assert_eq!(sorted("es", &["ñu", "nube", "oso", "Ñandú", "nación"]), vec!["nación", "nube", "Ñandú", "ñu", "oso"]);
+        assert_eq!(sorted("sv", &["öl", "zebra", "ål", "äpple", "ost"]), vec!["ost", "zebra", "ål", "äpple", "öl"]);
+        assert_eq!(sorted("cy-GB", &["dyn", "ddoe", "llan", "lori", "chwech", "cyw", "ngwlad", "golau", "hen"]),
+            vec!["cyw", "chwech", "dyn", "ddoe", "golau", "ngwlad", "hen", "lori", "llan"]);
+        assert_eq!(sorted("ga", &["bád", "tAthair", "athair", "mBád", "t-uisce", "bhFuinneog", "fuinneog", "uisce", "bhád"]),
+            vec!["athair", "tAthair", "bád", "mBád", "bhád", "fuinneog", "bhFuinneog", "t-uisce", "uisce"]);
+        let collation = Collation::for_language("cy");
+        assert_eq!(collation.initial("Llanelli"), "Ll");
+        assert_eq!(collation.initial("écho"), "E");
+        assert_eq!(Collation::for_language("ga").initial("tAthair"), "A");
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let mut resource = parse(File::open("examples/14.xml").unwrap(), &Format::XML, &None).unwrap();
+        resource.entries[0].homograph_number = Some(2);
+        resource.entries.push(Entry { headword: "See".to_string(), homograph_number: Some(1), ..Entry::default() });
+        resource.entries.push(Entry { headword: "see".to_string(), ..Entry::default() });
+        sort_entries(&mut resource);
+        assert_eq!(resource.entries.iter().map(|e| (e.headword.as_str(), e.homograph_number)).collect::<Vec<_>>(),
+            vec![("Meer", None), ("Ozean", None), ("see", None), ("See", Some(1)), ("See", Some(2))]);
+    }
+}
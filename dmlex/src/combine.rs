@@ -0,0 +1,247 @@
+//! Combination of several resources, such as subject area dictionaries, into
+//! one resource
+//!
+//! The entries and relations are concatenated in the order of the resources.
+//! Tags of all kinds, relation types, etymon languages and etymon types are
+//! united by their tag, keeping the first of each and reporting a differing
+//! description. Identifiers of entries, senses and collocates that are
+//! already used by an earlier resource are renamed with a number, and the
+//! members of the relations of that resource are rewritten to match. Finally
+//! the homographs are numbered across the combined entries, in the order of
+//! the resources and of their previous numbers.
+use crate::homograph::{number_homographs_in_sources, HomographOptions, HomographOrder};
+use crate::model::*;
+use crate::report::ConversionReport;
+use std::collections::{HashMap, HashSet};
+
+/// A tag that is united by its key
+trait Tag : Clone {
+    fn key(&self) -> &str;
+
+    fn description(&mut self) -> &mut Option<String>;
+
+    fn same_as(&mut self) -> Option<&mut Vec<String>> {
+        None
+    }
+}
+
+macro_rules! tag {
+    ($tag:ty, $key:ident) => {
+        tag!($tag, $key, description);
+    };
+    ($tag:ty, $key:ident, $description:ident) => {
+        impl Tag for $tag {
+            fn key(&self) -> &str {
+                &self.$key
+            }
+
+            fn description(&mut self) -> &mut Option<String> {
+                &mut self.$description
+            }
+
+            fn same_as(&mut self) -> Option<&mut Vec<String>> {
+                Some(&mut self.same_as)
+            }
+        }
+    };
+}
+
+tag!(DefinitionTypeTag, tag);
+tag!(InflectedFormTag, tag);
+tag!(LabelTag, tag);
+tag!(LabelTypeTag, tag);
+tag!(PartOfSpeechTag, tag);
+tag!(SourceIdentityTag, tag);
+tag!(RelationType, _type);
+
+impl Tag for TranscriptionSchemeTag {
+    fn key(&self) -> &str {
+        &self.tag
+    }
+
+    fn description(&mut self) -> &mut Option<String> {
+        &mut self.description
+    }
+}
+
+impl Tag for EtymonType {
+    fn key(&self) -> &str {
+        &self._type
+    }
+
+    fn description(&mut self) -> &mut Option<String> {
+        &mut self.description
+    }
+}
+
+impl Tag for EtymonLanguage {
+    fn key(&self) -> &str {
+        &self.lang_code.0
+    }
+
+    fn description(&mut self) -> &mut Option<String> {
+        &mut self.display_name
+    }
+}
+
+/// Add tags to the tags of the combined resource
+fn unite<T : Tag>(combined : &mut Vec<T>, tags : &[T], name : &str, location : &str, report : &mut ConversionReport) {
+    for tag in tags.iter() {
+        let mut tag = tag.clone();
+        match combined.iter_mut().find(|t| t.key() == tag.key()) {
+            None => combined.push(tag),
+            Some(existing) => {
+                match (existing.description().clone(), tag.description().clone()) {
+                    (None, Some(description)) => *existing.description() = Some(description),
+                    (Some(first), Some(other)) if first != other => report.warn(location,
+                        format!("{} {} is described as {:?} and as {:?}, keeping the first", name, tag.key(), first, other)),
+                    _ => (),
+                }
+                if let (Some(existing), Some(same_as)) = (existing.same_as(), tag.same_as()) {
+                    for uri in same_as.iter() {
+                        if !existing.contains(uri) {
+                            existing.push(uri.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Give an identifier a new name if it is already used
+fn rename(id : &mut String, used : &mut HashSet<String>, renamed : &mut HashMap<String, String>) {
+    if used.insert(id.clone()) {
+        return;
+    }
+    let mut n = 2;
+    while used.contains(&format!("{}-{}", id, n)) {
+        n += 1;
+    }
+    let new_id = format!("{}-{}", id, n);
+    used.insert(new_id.clone());
+    renamed.insert(id.clone(), new_id.clone());
+    *id = new_id;
+}
+
+/// Rename the identifiers of an entry, its senses and its collocates that
+/// are already used
+fn rename_ids(entry : &mut Entry, used : &mut HashSet<String>, renamed : &mut HashMap<String, String>) {
+    if let Some(id) = entry.id.as_mut() {
+        rename(id, used, renamed);
+    }
+    for sense in entry.senses.iter_mut() {
+        if let Some(id) = sense.id.as_mut() {
+            rename(id, used, renamed);
+        }
+        let collocates = sense.definitions.iter_mut().flat_map(|d| d.collocate_markers.iter_mut())
+            .chain(sense.examples.iter_mut().flat_map(|e| e.collocate_markers.iter_mut()
+                .chain(e.example_translations.iter_mut().flat_map(|t| t.collocate_markers.iter_mut()))));
+        for collocate in collocates {
+            if let Some(id) = collocate.id.as_mut() {
+                rename(id, used, renamed);
+            }
+        }
+    }
+}
+
+/// Combine several resources into one, which takes its language, title and
+/// URI from the first resource
+pub fn combine(resources : &[LexicographicResource]) -> (LexicographicResource, ConversionReport) {
+    let mut report = ConversionReport::new();
+    let mut combined = LexicographicResource::default();
+    let mut used = HashSet::new();
+    let mut sources = Vec::new();
+    for (n, resource) in resources.iter().enumerate() {
+        let location = format!("resource {}", n + 1);
+        if n == 0 {
+            combined.title = resource.title.clone();
+            combined.uri = resource.uri.clone();
+            combined.lang_code = resource.lang_code.clone();
+        } else if resource.lang_code != combined.lang_code {
+            report.warn(&location, format!("The language {} differs from {}", resource.lang_code.0, combined.lang_code.0));
+        }
+        for lang in resource.translation_languages.iter() {
+            if !combined.translation_languages.contains(lang) {
+                combined.translation_languages.push(lang.clone());
+            }
+        }
+        let mut renamed = HashMap::new();
+        for entry in resource.entries.iter() {
+            let mut entry = entry.clone();
+            rename_ids(&mut entry, &mut used, &mut renamed);
+            combined.entries.push(entry);
+            sources.push(n);
+        }
+        let mut renames : Vec<(&String, &String)> = renamed.iter().collect();
+        renames.sort();
+        for (old, new) in renames {
+            report.warn(&location, format!("{} is already used and was renamed to {}", old, new));
+        }
+        for relation in resource.relations.iter() {
+            let mut relation = relation.clone();
+            for member in relation.members.iter_mut() {
+                if let Some(new) = renamed.get(&member.ref_) {
+                    member.ref_ = new.clone();
+                }
+            }
+            combined.relations.push(relation);
+        }
+        unite(&mut combined.definition_type_tags, &resource.definition_type_tags, "definitionTypeTag", &location, &mut report);
+        unite(&mut combined.inflected_form_tags, &resource.inflected_form_tags, "inflectedFormTag", &location, &mut report);
+        unite(&mut combined.label_tags, &resource.label_tags, "labelTag", &location, &mut report);
+        unite(&mut combined.label_type_tags, &resource.label_type_tags, "labelTypeTag", &location, &mut report);
+        unite(&mut combined.part_of_speech_tags, &resource.part_of_speech_tags, "partOfSpeechTag", &location, &mut report);
+        unite(&mut combined.source_identity_tags, &resource.source_identity_tags, "sourceIdentityTag", &location, &mut report);
+        unite(&mut combined.transcription_scheme_tags, &resource.transcription_scheme_tags, "transcriptionSchemeTag", &location, &mut report);
+        unite(&mut combined.relation_types, &resource.relation_types, "relationType", &location, &mut report);
+        unite(&mut combined.etymon_languages, &resource.etymon_languages, "etymonLanguage", &location, &mut report);
+        unite(&mut combined.etymon_types, &resource.etymon_types, "etymonType", &location, &mut report);
+    }
+    number_homographs_in_sources(&mut combined, &sources, &HomographOptions::default().with_order(HomographOrder::PreviousNumber));
+    (combined, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    fn example(name : &str) -> LexicographicResource {
+        parse(File::open(format!("examples/{}", name)).unwrap(), &Format::XML, &None).unwrap()
+    }
+
+    #[test]
+    fn test_combine() {
+        let first = example("14.xml");
+        let mut second = first.clone();
+        second.entries.truncate(2);
+        second.entries[1].id = Some("das-meer-2".to_string());
+        second.relations[0].members.truncate(2);
+        second.relation_types[0].description = Some("synonyms".to_string());
+        second.part_of_speech_tags.push(PartOfSpeechTag { tag: "n-fem".to_string(), ..PartOfSpeechTag::default() });
+        let (combined, report) = combine(&[first, second]);
+        assert_eq!(report.to_string(), "resource 2: das-meer-1 is already used and was renamed to das-meer-1-2\n\
+            resource 2: die-see is already used and was renamed to die-see-2\n\
+            resource 2: die-see-1 is already used and was renamed to die-see-1-2\n\
+            resource 2: relationType synonyms is described as \"synonyms and near synonyms\" and as \"synonyms\", keeping the first\n");
+        assert_eq!(combined.entries.len(), 5);
+        assert_eq!(combined.entries[3].id.as_deref(), Some("die-see-2"));
+        assert_eq!(combined.entries[4].id.as_deref(), Some("das-meer-2"));
+        assert_eq!(combined.relations[1].members.iter().map(|m| m.ref_.as_str()).collect::<Vec<_>>(), vec!["die-see-1-2", "das-meer-1-2"]);
+        assert_eq!(combined.relation_types.len(), 1);
+        assert_eq!(combined.part_of_speech_tags.len(), 1);
+        assert_eq!(combined.entries.iter().map(|e| e.homograph_number).collect::<Vec<_>>(), vec![Some(1), Some(1), None, Some(2), Some(2)]);
+        assert!(combined.validate().is_ok());
+    }
+
+    #[test]
+    fn test_homographs() {
+        let entry = |headword : &str, number : Option<u32>| Entry { headword: headword.to_string(), homograph_number: number, ..Entry::default() };
+        let first = LexicographicResource { entries: vec![entry("bank", Some(2)), entry("bank", Some(1)), entry("bat", Some(1))], ..LexicographicResource::default() };
+        let second = LexicographicResource { entries: vec![entry("bank", None)], ..LexicographicResource::default() };
+        let (combined, _) = combine(&[first, second]);
+        assert_eq!(combined.entries.iter().map(|e| e.homograph_number).collect::<Vec<_>>(), vec![Some(2), Some(1), None, Some(3)]);
+    }
+}
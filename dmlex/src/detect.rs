@@ -0,0 +1,179 @@
+//! Detection of the format and root element of input documents
+use crate::Format;
+use crate::rdf::DMLEX;
+use sophia::graph::Graph;
+use sophia::graph::inmem::LightGraph;
+use sophia::ns::Namespace;
+use sophia::term::ns::rdf;
+use sophia::triple::stream::TripleSource;
+use std::path::Path;
+use xml::reader::{EventReader, XmlEvent};
+
+/// The kind of element at the root of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootKind {
+    LexicographicResource,
+    Entry,
+}
+
+/// Detect the format of a document. The content is examined first and the
+/// extension of the file name is used if the content is ambiguous. XML
+/// documents with a `LexicalResource` document element are read as LMF,
+/// unless they are WordNet LMF documents, see `is_wordnet_lmf`.
+pub fn detect_format(content : &[u8], file_name : Option<&str>) -> Option<Format> {
+    match sniff_format(content).or_else(|| file_name.and_then(format_from_extension)) {
+        Some(Format::XML) if xml_root_name(content).as_deref() == Some("LexicalResource")
+            && !is_wordnet_lmf(content) => Some(Format::LMF),
+        format => format,
+    }
+}
+
+/// Whether an XML document is in the Global WordNet LMF format rather than
+/// ISO LMF. Both use `LexicalResource` as the document element, but WordNet
+/// LMF gives the language of a `Lexicon` as an attribute instead of a `feat`.
+pub fn is_wordnet_lmf(content : &[u8]) -> bool {
+    for event in EventReader::new(content) {
+        match event {
+            Ok(XmlEvent::StartElement { name, attributes, .. }) if name.local_name == "Lexicon" => {
+                return attributes.iter().any(|a| a.name.local_name == "language");
+            },
+            Ok(XmlEvent::StartElement { name, .. }) if name.local_name != "LexicalResource" => return false,
+            Ok(_) => (),
+            Err(_) => return false,
+        }
+    }
+    false
+}
+
+/// Guess the format from the file extension
+pub fn format_from_extension(file_name : &str) -> Option<Format> {
+    let extension = Path::new(file_name).extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "xml" => Some(Format::XML),
+        "json" => Some(Format::JSON),
+        "rdf" | "ttl" | "turtle" | "nt" => Some(Format::RDF),
+        "lmf" => Some(Format::LMF),
+        _ => None,
+    }
+}
+
+/// Guess the format from the start of the document
+pub fn sniff_format(content : &[u8]) -> Option<Format> {
+    let text = String::from_utf8_lossy(&content[..content.len().min(1024)]);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let lower = text.to_lowercase();
+    if lower.starts_with("@prefix") || lower.starts_with("@base")
+        || lower.starts_with("prefix ") || lower.starts_with("base ")
+        || text.starts_with("_:") || text.starts_with('#') {
+        Some(Format::RDF)
+    } else if text.starts_with("<?xml") || text.starts_with("<!") {
+        Some(Format::XML)
+    } else if let Some(tag) = text.strip_prefix('<') {
+        // A Turtle document may start with an IRI, while XML starts with a tag name
+        let token = tag.split(|c : char| c == '>' || c.is_whitespace()).next().unwrap_or("");
+        if token.contains("//") || tag.starts_with('>') {
+            Some(Format::RDF)
+        } else {
+            Some(Format::XML)
+        }
+    } else if text.starts_with('{') {
+        Some(Format::JSON)
+    } else {
+        None
+    }
+}
+
+/// The local name of the document element of an XML document
+pub fn xml_root_name(content : &[u8]) -> Option<String> {
+    for event in EventReader::new(content) {
+        match event {
+            Ok(XmlEvent::StartElement { name, .. }) => return Some(name.local_name),
+            Ok(_) => (),
+            Err(_) => return None,
+        }
+    }
+    None
+}
+
+/// Detect whether the document contains a lexicographic resource or a single
+/// entry. Returns `None` if the document cannot be read in the given format.
+pub fn detect_root(content : &[u8], format : &Format) -> Option<RootKind> {
+    match format {
+        Format::XML => {
+            match xml_root_name(content)?.as_str() {
+                "lexicographicResource" => Some(RootKind::LexicographicResource),
+                "entry" => Some(RootKind::Entry),
+                _ => None,
+            }
+        },
+        Format::LMF => {
+            match xml_root_name(content)?.as_str() {
+                "LexicalResource" => Some(RootKind::LexicographicResource),
+                "LexicalEntry" => Some(RootKind::Entry),
+                _ => None,
+            }
+        },
+        Format::JSON => {
+            let value : serde_json::Value = serde_json::from_slice(content).ok()?;
+            let object = value.as_object()?;
+            if object.contains_key("headword") {
+                Some(RootKind::Entry)
+            } else {
+                Some(RootKind::LexicographicResource)
+            }
+        },
+        Format::RDF => {
+            let text = std::str::from_utf8(content).ok()?;
+            let graph : LightGraph = sophia::parser::turtle::parse_str(text).collect_triples().ok()?;
+            let dmlex = Namespace::new(DMLEX).expect("DMLEX namespace is invalid");
+            if graph.triples_with_po(&rdf::type_, &dmlex.get("LexicographicResource").ok()?).next().is_some() {
+                Some(RootKind::LexicographicResource)
+            } else if graph.triples_with_po(&rdf::type_, &dmlex.get("Entry").ok()?).next().is_some() {
+                Some(RootKind::Entry)
+            } else {
+                None
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(fname : &str) -> Vec<u8> {
+        std::fs::read(fname).unwrap()
+    }
+
+    #[test]
+    fn test_sniff_examples() {
+        for i in 0..25 {
+            assert_eq!(sniff_format(&read(&format!("examples/{}.xml", i))), Some(Format::XML));
+            assert_eq!(sniff_format(&read(&format!("examples/{}.json", i))), Some(Format::JSON));
+            assert_eq!(sniff_format(&read(&format!("examples/{}.rdf", i))), Some(Format::RDF));
+        }
+    }
+
+    #[test]
+    fn test_sniff_ambiguous() {
+        assert_eq!(sniff_format(b"<http://example.com/lr> a <http://example.com/C> ."), Some(Format::RDF));
+        assert_eq!(sniff_format(b"<dmlex:entry id=\"x\"/>"), Some(Format::XML));
+        assert_eq!(sniff_format(b"\xef\xbb\xbf  <entry/>"), Some(Format::XML));
+        assert_eq!(sniff_format(b"ex:lr a ex:C ."), None);
+        assert_eq!(detect_format(b"ex:lr a ex:C .", Some("dict.ttl")), Some(Format::RDF));
+        assert_eq!(detect_format(b"", Some("DICT.JSON")), Some(Format::JSON));
+        assert_eq!(detect_format(b"", None), None);
+        assert_eq!(detect_format(b"<LexicalResource dtdVersion=\"16\"/>", Some("dict.xml")), Some(Format::LMF));
+        assert_eq!(detect_format(b"", Some("dict.lmf")), Some(Format::LMF));
+    }
+
+    #[test]
+    fn test_detect_root() {
+        for (fname, kind) in [("examples/0", RootKind::LexicographicResource), ("examples/1", RootKind::Entry)] {
+            for (ext, format) in [("xml", Format::XML), ("json", Format::JSON), ("rdf", Format::RDF)] {
+                let content = read(&format!("{}.{}", fname, ext));
+                assert_eq!(detect_root(&content, &format), Some(kind.clone()), "{}.{}", fname, ext);
+            }
+        }
+    }
+}
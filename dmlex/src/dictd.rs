@@ -0,0 +1,130 @@
+//! Export of resources in the format of the dictd dictionary server, which
+//! is also read by GoldenDict and other offline dictionary readers
+//!
+//! A dictionary consists of a `.dict` file with the articles as plain text
+//! and an `.index` file with a line for each headword and inflected form
+//! giving the offset and length of its article. The database information
+//! is given by the usual `00-database-*` entries, and the dictionary is
+//! marked as UTF-8 and to be looked up with all characters.
+use crate::article;
+use crate::model::*;
+use crate::report::ConversionReport;
+use crate::stardict::with_suffix;
+use std::path::Path;
+
+const BASE64 : &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The files of a dictd dictionary
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictdFiles {
+    pub index: String,
+    pub dict: Vec<u8>,
+}
+
+/// Encode a number in the base 64 notation of dictd indexes, most
+/// significant digit first and without padding
+pub fn encode_number(mut n : u64) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(BASE64[(n % 64) as usize]);
+        n /= 64;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// Decode a number in the base 64 notation of dictd indexes
+pub fn decode_number(s : &str) -> Option<u64> {
+    s.bytes().try_fold(0u64, |n, b| BASE64.iter().position(|&c| c == b).map(|d| n * 64 + d as u64))
+}
+
+/// Render a resource as dictd files
+pub fn render_dictd(resource : &LexicographicResource) -> (DictdFiles, ConversionReport) {
+    let mut report = ConversionReport::new();
+    article::note_lost(resource, &mut report);
+    let mut dict = Vec::new();
+    let mut words : Vec<(String, usize, usize)> = Vec::new();
+    let title = resource.title.clone().unwrap_or_else(|| "Dictionary".to_string());
+    let mut info = vec![
+        ("00-database-utf8", String::new()),
+        ("00-database-allchars", String::new()),
+        ("00-database-short", title),
+    ];
+    if let Some(uri) = &resource.uri {
+        info.push(("00-database-url", uri.clone()));
+    }
+    for (name, value) in info {
+        let text = format!("{}\n    {}\n", name, value);
+        words.push((name.to_string(), dict.len(), text.len()));
+        dict.extend_from_slice(text.as_bytes());
+    }
+    for entry in resource.entries.iter() {
+        let text = format!("{}\n\n", article::plain_text(entry, true));
+        for form in article::lookup_forms(entry) {
+            words.push((form.to_string(), dict.len(), text.len()));
+        }
+        dict.extend_from_slice(text.as_bytes());
+    }
+    // dictd folds the case of UTF-8 dictionaries and, with all characters,
+    // compares the rest byte by byte
+    words.sort_by_cached_key(|(word, offset, _)| (word.to_lowercase(), *offset));
+    let mut index = String::new();
+    for (word, offset, length) in words {
+        index.push_str(&format!("{}\t{}\t{}\n", word.replace(['\t', '\n'], " "),
+            encode_number(offset as u64), encode_number(length as u64)));
+    }
+    (DictdFiles { index, dict }, report)
+}
+
+/// Write a resource as dictd files named after `base`, so that `out/en`
+/// gives `out/en.index` and `out/en.dict`
+pub fn write_dictd<P : AsRef<Path>>(base : P, resource : &LexicographicResource) -> std::io::Result<ConversionReport> {
+    let (files, report) = render_dictd(resource);
+    std::fs::write(with_suffix(base.as_ref(), "index"), files.index)?;
+    std::fs::write(with_suffix(base.as_ref(), "dict"), files.dict)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(encode_number(0), "A");
+        assert_eq!(encode_number(64), "BA");
+        assert_eq!(encode_number(1234567), "EtaH");
+        assert_eq!(decode_number("EtaH"), Some(1234567));
+        assert_eq!(decode_number("E-"), None);
+    }
+
+    #[test]
+    fn test_dictd() {
+        let mut resource = parse(File::open("examples/5.xml").unwrap(), &Format::XML, &None).unwrap();
+        let resource2 = parse(File::open("examples/17.xml").unwrap(), &Format::XML, &None).unwrap();
+        resource.entries.extend(resource2.entries);
+        resource.relations.extend(resource2.relations);
+        let (files, report) = render_dictd(&resource);
+        assert_eq!(report.warnings[0].message, "Relations are not exported");
+        let lines : Vec<Vec<&str>> = files.index.lines().map(|l| l.split('\t').collect()).collect();
+        assert_eq!(lines.iter().map(|l| l[0]).collect::<Vec<_>>(), vec!["00-database-allchars", "00-database-short",
+            "00-database-url", "00-database-utf8", "better safe than sorry", "folúsghlantóir", "folúsghlantóirí",
+            "folúsghlantóra", "safe"]);
+        let article = |word : &str| {
+            let line = lines.iter().find(|l| l[0] == word).unwrap();
+            let offset = decode_number(line[1]).unwrap() as usize;
+            let length = decode_number(line[2]).unwrap() as usize;
+            std::str::from_utf8(&files.dict[offset..offset + length]).unwrap().to_string()
+        };
+        assert_eq!(article("folúsghlantóra"), article("folúsghlantóir"));
+        assert_eq!(article("folúsghlantóir"), "folúsghlantóir n-masc\nforms: sg-gen folúsghlantóra, pl folúsghlantóirí\n\n");
+        assert_eq!(article("safe"), "safe\n1. (protected from harm)\n   • It isn't safe to park here.\n\
+            2. (not likely to cause harm)\n   • Is the ride safe for a small child?\n\n");
+        assert_eq!(article("00-database-url"), "00-database-url\n    http://example.com\n");
+    }
+}
@@ -0,0 +1,415 @@
+//! Semantic comparison of two versions of a lexicographic resource
+//!
+//! Entries are matched by their identifier or, failing that, by their
+//! headword, homograph number and parts of speech. The senses of matched
+//! entries are matched by identifier or by their indicator and definitions,
+//! and the remaining senses without identifiers are compared in order.
+//! Definitions and examples are matched by text and otherwise in order, so
+//! that an edited definition is shown as changed. Tags are matched by tag,
+//! relation types by type and relations by their type and members.
+//!
+//! The differences can be shown as text, with `+`, `-` and `~` for added,
+//! removed and changed elements, or serialized as JSON.
+use crate::model::*;
+use crate::validate::Validate;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+
+/// Whether an element was added, removed or changed
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    fn sign(&self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        }
+    }
+}
+
+/// A change in a property, such as the headword of an entry or the labels
+/// of a sense
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub field: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// A change in a definition, example, tag or relation, with its old and new
+/// values
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemChange {
+    pub kind: ChangeKind,
+    pub item: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// A change in a sense. Only changed senses list their changes.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SenseChange {
+    pub kind: ChangeKind,
+    pub sense: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub definitions: Vec<ItemChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<ItemChange>,
+}
+
+/// A change in an entry. Only changed entries list their changes.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryChange {
+    pub kind: ChangeKind,
+    pub entry: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub senses: Vec<SenseChange>,
+}
+
+/// The differences between two resources
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceDiff {
+    /// Changes in the properties of the resource, such as its title
+    pub fields: Vec<FieldChange>,
+    pub entries: Vec<EntryChange>,
+    /// Changes in tags of all kinds, etymon languages and etymon types
+    pub tags: Vec<ItemChange>,
+    /// Changes in relations and relation types
+    pub relations: Vec<ItemChange>,
+}
+
+impl ResourceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.entries.is_empty() && self.tags.is_empty() && self.relations.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// Compare two versions of a resource
+pub fn diff(old : &LexicographicResource, new : &LexicographicResource) -> ResourceDiff {
+    let mut result = ResourceDiff {
+        fields: field_changes(old, new, &["entries", "definitionTypeTags", "inflectedFormTags", "labelTags",
+            "labelTypeTags", "partOfSpeechTags", "sourceIdentityTags", "transcriptionSchemeTags", "relations",
+            "relationTypes", "etymonLanguages", "etymonTypes"]),
+        ..ResourceDiff::default()
+    };
+    let pairs = pair(&old.entries, &new.entries, |e| e.id.clone(), |e| e.signature(), false);
+    for (i, j) in pairs {
+        match (i.map(|i| &old.entries[i]), j.map(|j| &new.entries[j])) {
+            (Some(a), Some(b)) => {
+                let change = diff_entry(a, b);
+                if !change.fields.is_empty() || !change.senses.is_empty() {
+                    result.entries.push(change);
+                }
+            },
+            (Some(a), None) => result.entries.push(EntryChange { kind: ChangeKind::Removed, entry: entry_label(a),
+                fields: Vec::new(), senses: Vec::new() }),
+            (None, Some(b)) => result.entries.push(EntryChange { kind: ChangeKind::Added, entry: entry_label(b),
+                fields: Vec::new(), senses: Vec::new() }),
+            (None, None) => (),
+        }
+    }
+    macro_rules! tags {
+        ($field:ident, $name:expr) => {
+            result.tags.extend(items(&old.$field, &new.$field, |t| format!("{} {}", $name, t.signature()),
+                pair(&old.$field, &new.$field, |_| None, |t| t.signature(), false)));
+        };
+    }
+    tags!(definition_type_tags, "definitionTypeTag");
+    tags!(inflected_form_tags, "inflectedFormTag");
+    tags!(label_tags, "labelTag");
+    tags!(label_type_tags, "labelTypeTag");
+    tags!(part_of_speech_tags, "partOfSpeechTag");
+    tags!(source_identity_tags, "sourceIdentityTag");
+    tags!(transcription_scheme_tags, "transcriptionSchemeTag");
+    result.tags.extend(items(&old.etymon_languages, &new.etymon_languages, |l| format!("etymonLanguage {}", l.lang_code.0),
+        pair(&old.etymon_languages, &new.etymon_languages, |_| None, |l| l.lang_code.0.clone(), false)));
+    result.tags.extend(items(&old.etymon_types, &new.etymon_types, |t| format!("etymonType {}", t._type),
+        pair(&old.etymon_types, &new.etymon_types, |_| None, |t| t._type.clone(), false)));
+    result.relations.extend(items(&old.relation_types, &new.relation_types, |t| format!("relationType {}", t._type),
+        pair(&old.relation_types, &new.relation_types, |_| None, |t| t.signature(), false)));
+    result.relations.extend(items(&old.relations, &new.relations, relation_label,
+        pair(&old.relations, &new.relations, |_| None, relation_label, false)));
+    result
+}
+
+fn diff_entry(old : &Entry, new : &Entry) -> EntryChange {
+    let mut change = EntryChange {
+        kind: ChangeKind::Changed,
+        entry: entry_label(new),
+        fields: field_changes(old, new, &["senses"]),
+        senses: Vec::new(),
+    };
+    let sense_key = |s : &Sense| (s.indicator.clone(), s.definitions.iter().map(|d| d.text.clone()).collect::<Vec<_>>());
+    for (i, j) in pair(&old.senses, &new.senses, |s| s.id.clone(), sense_key, true) {
+        let label = |k : usize, s : &Sense| s.id.clone().unwrap_or_else(|| format!("{}", k + 1));
+        match (i, j) {
+            (Some(i), Some(j)) => {
+                let (a, b) = (&old.senses[i], &new.senses[j]);
+                let sense = SenseChange {
+                    kind: ChangeKind::Changed,
+                    sense: label(j, b),
+                    fields: field_changes(a, b, &["definitions", "examples"]),
+                    definitions: items(&a.definitions, &b.definitions, |_| "definition".to_string(),
+                        pair(&a.definitions, &b.definitions, |_| None, |d| d.text.clone(), true)),
+                    examples: items(&a.examples, &b.examples, |_| "example".to_string(),
+                        pair(&a.examples, &b.examples, |_| None, |e| e.text.clone(), true)),
+                };
+                if !sense.fields.is_empty() || !sense.definitions.is_empty() || !sense.examples.is_empty() {
+                    change.senses.push(sense);
+                }
+            },
+            (Some(i), None) => change.senses.push(SenseChange { kind: ChangeKind::Removed, sense: label(i, &old.senses[i]),
+                fields: Vec::new(), definitions: Vec::new(), examples: Vec::new() }),
+            (None, Some(j)) => change.senses.push(SenseChange { kind: ChangeKind::Added, sense: label(j, &new.senses[j]),
+                fields: Vec::new(), definitions: Vec::new(), examples: Vec::new() }),
+            (None, None) => (),
+        }
+    }
+    change
+}
+
+pub(crate) fn entry_label(entry : &Entry) -> String {
+    match (&entry.id, entry.homograph_number) {
+        (Some(id), _) => id.clone(),
+        (None, Some(number)) => format!("{} {}", entry.headword, number),
+        (None, None) => entry.headword.clone(),
+    }
+}
+
+pub(crate) fn relation_label(relation : &Relation) -> String {
+    let mut members : Vec<&str> = relation.members.iter().map(|m| m.ref_.as_str()).collect();
+    members.sort();
+    format!("relation {} ({})", relation._type, members.join(", "))
+}
+
+/// Pair the items of two lists, first by identifier, then by key and
+/// finally, if `in_order`, the remaining items without identifiers in the
+/// order they appear. The pairs follow the old list, followed by the items
+/// only in the new list.
+pub(crate) fn pair<T, K : Hash + Eq>(old : &[T], new : &[T], id : impl Fn(&T) -> Option<String>, key : impl Fn(&T) -> K,
+        in_order : bool) -> Vec<(Option<usize>, Option<usize>)> {
+    let mut old_match = vec![None; old.len()];
+    let mut new_match = vec![None; new.len()];
+    let mut by_id = HashMap::new();
+    for (j, item) in new.iter().enumerate() {
+        if let Some(id) = id(item) {
+            by_id.entry(id).or_insert(j);
+        }
+    }
+    for (i, item) in old.iter().enumerate() {
+        if let Some(&j) = id(item).and_then(|id| by_id.get(&id)) {
+            old_match[i] = Some(j);
+            new_match[j] = Some(i);
+        }
+    }
+    let mut by_key : HashMap<K, VecDeque<usize>> = HashMap::new();
+    for (j, item) in new.iter().enumerate().filter(|(j, _)| new_match[*j].is_none()) {
+        by_key.entry(key(item)).or_default().push_back(j);
+    }
+    for (i, item) in old.iter().enumerate() {
+        if old_match[i].is_none() {
+            if let Some(j) = by_key.get_mut(&key(item)).and_then(|queue| queue.pop_front()) {
+                old_match[i] = Some(j);
+                new_match[j] = Some(i);
+            }
+        }
+    }
+    if in_order {
+        let rest_old = (0..old.len()).filter(|&i| old_match[i].is_none() && id(&old[i]).is_none()).collect::<Vec<_>>();
+        let rest_new = (0..new.len()).filter(|&j| new_match[j].is_none() && id(&new[j]).is_none()).collect::<Vec<_>>();
+        for (i, j) in rest_old.into_iter().zip(rest_new) {
+            old_match[i] = Some(j);
+            new_match[j] = Some(i);
+        }
+    }
+    let mut pairs : Vec<(Option<usize>, Option<usize>)> = old_match.into_iter().enumerate().map(|(i, j)| (Some(i), j)).collect();
+    pairs.extend(new_match.into_iter().enumerate().filter(|(_, i)| i.is_none()).map(|(j, _)| (None, Some(j))));
+    pairs
+}
+
+/// The changes in the paired items of two lists
+fn items<T : Serialize>(old : &[T], new : &[T], label : impl Fn(&T) -> String, pairs : Vec<(Option<usize>, Option<usize>)>) -> Vec<ItemChange> {
+    let mut changes = Vec::new();
+    for (i, j) in pairs {
+        let a = i.map(|i| serde_json::to_value(&old[i]).unwrap());
+        let b = j.map(|j| serde_json::to_value(&new[j]).unwrap());
+        let kind = match (&a, &b) {
+            (Some(a), Some(b)) if a == b => continue,
+            (Some(_), Some(_)) => ChangeKind::Changed,
+            (Some(_), None) => ChangeKind::Removed,
+            (None, Some(_)) => ChangeKind::Added,
+            (None, None) => continue,
+        };
+        let item = match (i, j) {
+            (_, Some(j)) => label(&new[j]),
+            (Some(i), None) => label(&old[i]),
+            (None, None) => unreachable!(),
+        };
+        changes.push(ItemChange { kind, item, old: a, new: b });
+    }
+    changes
+}
+
+/// The changes in the properties of two elements, leaving out those that are
+/// compared separately
+fn field_changes<T : Serialize>(old : &T, new : &T, skip : &[&str]) -> Vec<FieldChange> {
+    let (Value::Object(old), Value::Object(new)) = (serde_json::to_value(old).unwrap(), serde_json::to_value(new).unwrap()) else {
+        return Vec::new();
+    };
+    let mut fields : Vec<&String> = old.keys().chain(new.keys()).filter(|k| !skip.contains(&k.as_str())).collect();
+    fields.sort();
+    fields.dedup();
+    fields.into_iter().filter(|f| old.get(*f) != new.get(*f)).map(|f| FieldChange {
+        field: f.clone(),
+        old: old.get(f).cloned(),
+        new: new.get(f).cloned(),
+    }).collect()
+}
+
+/// A value for the text output: the text of elements that have one,
+/// otherwise the JSON
+fn show(value : &Value) -> String {
+    match value.get("text") {
+        Some(Value::String(text)) => format!("{:?}", text),
+        _ => value.to_string(),
+    }
+}
+
+fn show_change(f : &mut fmt::Formatter, indent : usize, name : &str, old : &Option<Value>, new : &Option<Value>) -> fmt::Result {
+    let pad = " ".repeat(indent);
+    match (old, new) {
+        (Some(old), Some(new)) => {
+            let (old_shown, new_shown) = (show(old), show(new));
+            if old_shown == new_shown {
+                writeln!(f, "{}~ {}: {} -> {}", pad, name, old, new)
+            } else {
+                writeln!(f, "{}~ {}: {} -> {}", pad, name, old_shown, new_shown)
+            }
+        },
+        (Some(old), None) => writeln!(f, "{}- {}: {}", pad, name, show(old)),
+        (None, Some(new)) => writeln!(f, "{}+ {}: {}", pad, name, show(new)),
+        (None, None) => Ok(()),
+    }
+}
+
+impl fmt::Display for ResourceDiff {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        for field in self.fields.iter() {
+            show_change(f, 0, &field.field, &field.old, &field.new)?;
+        }
+        for entry in self.entries.iter() {
+            writeln!(f, "{} entry {}", entry.kind.sign(), entry.entry)?;
+            for field in entry.fields.iter() {
+                show_change(f, 4, &field.field, &field.old, &field.new)?;
+            }
+            for sense in entry.senses.iter() {
+                writeln!(f, "    {} sense {}", sense.kind.sign(), sense.sense)?;
+                for field in sense.fields.iter() {
+                    show_change(f, 8, &field.field, &field.old, &field.new)?;
+                }
+                for item in sense.definitions.iter().chain(sense.examples.iter()) {
+                    show_change(f, 8, &item.item, &item.old, &item.new)?;
+                }
+            }
+        }
+        for item in self.tags.iter().chain(self.relations.iter()) {
+            match item.kind {
+                ChangeKind::Changed => show_change(f, 0, &item.item, &item.old, &item.new)?,
+                _ => writeln!(f, "{} {}", item.kind.sign(), item.item)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    fn example(name : &str) -> LexicographicResource {
+        parse(File::open(format!("examples/{}", name)).unwrap(), &Format::XML, &None).unwrap()
+    }
+
+    #[test]
+    fn test_same() {
+        let resource = example("all_props.xml");
+        assert!(diff(&resource, &resource).is_empty());
+    }
+
+    #[test]
+    fn test_entries() {
+        let old = example("0.xml");
+        let mut new = old.clone();
+        new.title = Some("Example Dictionary 2".to_string());
+        new.entries[0].senses[0].definitions[0].text = "to leave a place or a person".to_string();
+        new.entries[0].senses[0].examples.remove(0);
+        new.entries[0].senses.remove(1);
+        new.entries[0].labels.push("formal".to_string());
+        new.entries.push(Entry { headword: "abbey".to_string(), ..Entry::default() });
+        let result = diff(&old, &new);
+        assert_eq!(result.to_string(), "~ title: \"Example Dictionary\" -> \"Example Dictionary 2\"\n\
+            ~ entry abandon-verb\n    \
+            + labels: [\"formal\"]\n    \
+            ~ sense abandon-verb-1\n        \
+            ~ definition: \"to suddenly leave a place or a person\" -> \"to leave a place or a person\"\n        \
+            - example: \"I'm sorry I abandoned you like that.\"\n    \
+            - sense abandon-verb-2\n\
+            + entry abbey\n");
+        let json : Value = serde_json::from_str(&result.to_json()).unwrap();
+        assert_eq!(json["entries"][0]["senses"][0]["definitions"][0]["kind"], "changed");
+        assert_eq!(json["entries"][1]["kind"], "added");
+    }
+
+    #[test]
+    fn test_match_by_signature() {
+        let old = example("0.xml");
+        let mut new = old.clone();
+        new.entries[0].id = None;
+        let result = diff(&old, &new);
+        assert_eq!(result.to_string(), "~ entry abandon\n    - id: \"abandon-verb\"\n");
+    }
+
+    #[test]
+    fn test_tags_and_relations() {
+        let old = example("14.xml");
+        let mut new = old.clone();
+        new.relations[0].members.pop();
+        new.relation_types[0].description = Some("synonyms".to_string());
+        new.part_of_speech_tags.push(PartOfSpeechTag { tag: "n-fem".to_string(), ..PartOfSpeechTag::default() });
+        let result = diff(&old, &new);
+        assert_eq!(result.to_string(), "+ partOfSpeechTag n-fem\n\
+            ~ relationType synonyms: {\"description\":\"synonyms and near synonyms\",\"memberTypes\":[{\"hint\":\"navigate\",\"min\":2,\"type\":\"sense\"}],\"type\":\"synonyms\"} -> \
+            {\"description\":\"synonyms\",\"memberTypes\":[{\"hint\":\"navigate\",\"min\":2,\"type\":\"sense\"}],\"type\":\"synonyms\"}\n\
+            - relation synonyms (das-meer-1, der-ozean-1, die-see-1)\n\
+            + relation synonyms (das-meer-1, die-see-1)\n");
+    }
+}
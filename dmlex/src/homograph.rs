@@ -0,0 +1,210 @@
+//! Numbering of homographs, the entries that share a headword
+//!
+//! The homograph number of an entry is optional in DMLex and only the
+//! combination of headword, homograph number and parts of speech has to be
+//! unique. `number_homographs` numbers the entries of each headword from 1,
+//! in their input order, in the order of their parts of speech or in the
+//! order of their previous numbers, and `number_homographs_in_sources` does
+//! so for a resource combined from several, keeping the entries of each
+//! source together. `check_homographs` reports numbering with gaps, duplicate
+//! numbers, entries without a number among numbered homographs and numbers
+//! on headwords that have no homographs.
+use crate::model::*;
+use crate::report::ConversionReport;
+use std::collections::{BTreeSet, HashMap};
+
+/// The order in which homographs are numbered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HomographOrder {
+    /// The order of the entries in the resource
+    #[default]
+    InputOrder,
+    /// The order of the part of speech tags of the resource, with entries
+    /// of undeclared parts of speech after them and entries without a part
+    /// of speech last. Entries of the same part of speech keep their input
+    /// order.
+    PartOfSpeech,
+    /// The order of the previous homograph numbers, with unnumbered entries
+    /// after the numbered ones in their input order
+    PreviousNumber,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HomographOptions {
+    pub order: HomographOrder,
+    /// Give headwords without homographs the number 1 rather than leaving
+    /// them unnumbered
+    pub number_unique: bool,
+}
+
+impl HomographOptions {
+    pub fn with_order(mut self, order : HomographOrder) -> HomographOptions {
+        self.order = order;
+        self
+    }
+
+    pub fn with_number_unique(mut self, number_unique : bool) -> HomographOptions {
+        self.number_unique = number_unique;
+        self
+    }
+}
+
+/// The indices of the entries of each headword, in the order the headwords
+/// first appear
+fn groups(entries : &[Entry]) -> Vec<(&str, Vec<usize>)> {
+    let mut groups : Vec<(&str, Vec<usize>)> = Vec::new();
+    let mut index = HashMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let n = *index.entry(entry.headword.as_str()).or_insert_with(|| {
+            groups.push((entry.headword.as_str(), Vec::new()));
+            groups.len() - 1
+        });
+        groups[n].1.push(i);
+    }
+    groups
+}
+
+/// Number the entries that share a headword consecutively from 1
+pub fn number_homographs(resource : &mut LexicographicResource, options : &HomographOptions) {
+    let sources = vec![0; resource.entries.len()];
+    number_homographs_in_sources(resource, &sources, options);
+}
+
+/// Number the entries that share a headword consecutively from 1, where
+/// `sources` gives the index of the resource each entry was combined from.
+/// The homographs from an earlier resource come first and those of each
+/// resource are in the order of the options.
+pub fn number_homographs_in_sources(resource : &mut LexicographicResource, sources : &[usize], options : &HomographOptions) {
+    let tags : HashMap<&str, usize> = resource.part_of_speech_tags.iter().enumerate()
+        .map(|(i, t)| (t.tag.as_str(), i)).collect();
+    let mut numbers = vec![None; resource.entries.len()];
+    for (_, mut group) in groups(&resource.entries) {
+        if group.len() == 1 {
+            numbers[group[0]] = if options.number_unique { Some(1) } else { None };
+            continue;
+        }
+        group.sort_by_cached_key(|&i| {
+            let entry = &resource.entries[i];
+            let key = match options.order {
+                HomographOrder::InputOrder => (0, 0, String::new()),
+                HomographOrder::PartOfSpeech => match entry.parts_of_speech.first() {
+                    Some(pos) => match tags.get(pos.as_str()) {
+                        Some(&n) => (0, n, String::new()),
+                        None => (1, 0, pos.clone()),
+                    },
+                    None => (2, 0, String::new()),
+                },
+                HomographOrder::PreviousNumber => match entry.homograph_number {
+                    Some(n) => (0, n as usize, String::new()),
+                    None => (1, 0, String::new()),
+                },
+            };
+            (sources[i], key, i)
+        });
+        for (n, i) in group.into_iter().enumerate() {
+            numbers[i] = Some(n as u32 + 1);
+        }
+    }
+    for (entry, number) in resource.entries.iter_mut().zip(numbers) {
+        entry.homograph_number = number;
+    }
+}
+
+/// Report the problems of the homograph numbers of a resource
+pub fn check_homographs(resource : &LexicographicResource) -> ConversionReport {
+    let mut report = ConversionReport::new();
+    for (headword, group) in groups(&resource.entries) {
+        let numbers : Vec<u32> = group.iter().filter_map(|&i| resource.entries[i].homograph_number).collect();
+        if group.len() == 1 {
+            if let Some(n) = numbers.first() {
+                report.warn(headword, format!("The homograph number {} is given but there are no homographs", n));
+            }
+            continue;
+        }
+        if numbers.is_empty() {
+            report.warn(headword, format!("None of the {} homographs are numbered", group.len()));
+            continue;
+        }
+        if numbers.len() < group.len() {
+            report.warn(headword, format!("{} of the {} homographs have no number", group.len() - numbers.len(), group.len()));
+        }
+        let distinct : BTreeSet<u32> = numbers.iter().copied().collect();
+        for n in distinct.iter() {
+            let count = numbers.iter().filter(|m| *m == n).count();
+            if count > 1 {
+                report.warn(headword, format!("The homograph number {} is used {} times", n, count));
+            }
+        }
+        let missing : Vec<String> = (1..=distinct.last().copied().unwrap_or_default())
+            .filter(|n| !distinct.contains(n)).map(|n| n.to_string()).collect();
+        if !missing.is_empty() {
+            report.warn(headword, format!("The homograph numbers skip {}", missing.join(", ")));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    fn entry(headword : &str, pos : &str, number : Option<u32>) -> Entry {
+        Entry {
+            headword: headword.to_string(),
+            parts_of_speech: if pos.is_empty() { Vec::new() } else { vec![pos.to_string()] },
+            homograph_number: number,
+            ..Entry::default()
+        }
+    }
+
+    fn resource() -> LexicographicResource {
+        LexicographicResource {
+            lang_code: LangCode("en".to_string()),
+            part_of_speech_tags: ["noun", "verb"].iter()
+                .map(|t| PartOfSpeechTag { tag: t.to_string(), ..PartOfSpeechTag::default() }).collect(),
+            entries: vec![entry("bank", "verb", None), entry("bat", "", Some(2)), entry("bank", "", Some(3)),
+                entry("bank", "adj", Some(3)), entry("bank", "noun", None), entry("cat", "noun", Some(1))],
+            ..LexicographicResource::default()
+        }
+    }
+
+    fn numbers(resource : &LexicographicResource) -> Vec<Option<u32>> {
+        resource.entries.iter().map(|e| e.homograph_number).collect()
+    }
+
+    #[test]
+    fn test_number() {
+        let mut resource = resource();
+        number_homographs(&mut resource, &HomographOptions::default());
+        assert_eq!(numbers(&resource), vec![Some(1), None, Some(2), Some(3), Some(4), None]);
+        assert!(check_homographs(&resource).is_empty());
+        let options = HomographOptions::default().with_order(HomographOrder::PartOfSpeech).with_number_unique(true);
+        number_homographs(&mut resource, &options);
+        assert_eq!(numbers(&resource), vec![Some(2), Some(1), Some(4), Some(3), Some(1), Some(1)]);
+        assert!(resource.validate().is_ok());
+    }
+
+    #[test]
+    fn test_number_previous() {
+        let options = HomographOptions::default().with_order(HomographOrder::PreviousNumber);
+        let mut single = resource();
+        number_homographs(&mut single, &options);
+        assert_eq!(numbers(&single), vec![Some(3), None, Some(1), Some(2), Some(4), None]);
+        let mut combined = resource();
+        number_homographs_in_sources(&mut combined, &[0, 0, 1, 1, 0, 0], &options);
+        assert_eq!(numbers(&combined), vec![Some(1), None, Some(3), Some(4), Some(2), None]);
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check_homographs(&resource()).to_string(), "bank: 2 of the 4 homographs have no number\n\
+            bank: The homograph number 3 is used 2 times\n\
+            bank: The homograph numbers skip 1, 2\n\
+            bat: The homograph number 2 is given but there are no homographs\n\
+            cat: The homograph number 1 is given but there are no homographs\n");
+        let resource = parse(File::open("examples/14.xml").unwrap(), &Format::XML, &None).unwrap();
+        assert!(check_homographs(&resource).is_empty());
+    }
+}
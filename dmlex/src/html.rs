@@ -0,0 +1,881 @@
+//! Rendering of resources as static HTML
+//!
+//! A resource is rendered either as a site, with one page per entry and an
+//! index page in the alphabetical order of the language of the resource, or
+//! as a single document that contains the index followed by all entries.
+//! Labels, parts of speech and inflected form tags are shown as
+//! abbreviations explained by the descriptions of their tags, and headword
+//! and collocate markers are highlighted with `<mark>`.
+//!
+//! Related entries and senses are shown according to the hint of the member
+//! type of their role: members with the hint `embed` are rendered inside the
+//! other members of the relation, for example subsenses inside their parent
+//! sense, members with the hint `navigate` are linked to and members with the
+//! hint `none` are not shown. Members of undeclared relation types are linked
+//! to. A sense that is embedded in another sense of the same entry is not
+//! repeated in the list of senses of the entry.
+//!
+//! The pages are themeable through `HtmlTemplates`, which contain the HTML
+//! of the pages with `{{name}}` placeholders and the stylesheet.
+use crate::collation::Collation;
+use crate::model::*;
+use crate::registry::{ResourceWriter, WriteOptions};
+use crate::report::ConversionReport;
+use crate::WriteError;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+
+/// The name of the index page of a site
+pub const INDEX_FILE : &str = "index.html";
+
+/// How deeply embedded members may be nested, which also stops relations
+/// that embed each other
+const MAX_EMBED_DEPTH : usize = 4;
+
+const PAGE_TEMPLATE : &str = r#"<!DOCTYPE html>
+<html lang="{{lang}}">
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>
+{{style}}
+</style>
+</head>
+<body>
+{{content}}
+</body>
+</html>
+"#;
+
+const ENTRY_TEMPLATE : &str = r#"<article class="entry" id="{{anchor}}">
+<header>
+<h1 class="headword">{{headword}}{{homograph_number}}</h1>
+{{grammar}}
+{{pronunciations}}
+</header>
+{{inflected_forms}}
+<ol class="senses">
+{{senses}}
+</ol>
+{{related}}
+{{etymologies}}
+<nav><a href="{{index}}">{{resource_title}}</a></nav>
+</article>
+"#;
+
+const INDEX_TEMPLATE : &str = r#"<section class="index" id="index">
+<h1>{{title}}</h1>
+<nav class="letters">{{letters}}</nav>
+{{entries}}
+</section>
+"#;
+
+const STYLE : &str = r#"body { font-family: serif; max-width: 50em; margin: auto; padding: 1em; }
+.headword { display: inline; }
+.homograph-number { font-size: 60%; }
+abbr { font-style: italic; text-decoration: none; border-bottom: 1px dotted; }
+.pos, .label { color: #555; }
+.senses > li { margin-bottom: 0.5em; }
+.indicator { font-weight: bold; }
+.example { font-style: italic; }
+.example-translation { font-style: normal; }
+mark.headword { background: none; font-weight: bold; }
+mark.collocate { background: none; text-decoration: underline; }
+.embedded { border-left: 2px solid #ccc; padding-left: 0.5em; }
+.letters a { margin-right: 0.5em; }
+"#;
+
+/// The templates of the pages. Placeholders are written as `{{name}}` and
+/// unknown placeholders are left as they are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlTemplates {
+    /// A whole page, with the placeholders `lang`, `title`, `style` and
+    /// `content`
+    pub page: String,
+    /// An entry, with the placeholders `anchor`, `headword`,
+    /// `homograph_number`, `grammar`, `pronunciations`, `inflected_forms`,
+    /// `senses`, `related`, `etymologies`, `index` (the link to the index)
+    /// and `resource_title`
+    pub entry: String,
+    /// The index, with the placeholders `title`, `letters` and `entries`
+    pub index: String,
+    /// The stylesheet inserted for `style` in the page template
+    pub style: String,
+}
+
+impl Default for HtmlTemplates {
+    fn default() -> HtmlTemplates {
+        HtmlTemplates {
+            page: PAGE_TEMPLATE.to_string(),
+            entry: ENTRY_TEMPLATE.to_string(),
+            index: INDEX_TEMPLATE.to_string(),
+            style: STYLE.to_string(),
+        }
+    }
+}
+
+impl HtmlTemplates {
+    /// Load the templates from `page.html`, `entry.html`, `index.html` and
+    /// `style.css` in a directory, using the default for missing files
+    pub fn from_dir<P : AsRef<Path>>(dir : P) -> std::io::Result<HtmlTemplates> {
+        let mut templates = HtmlTemplates::default();
+        for (file, template) in [("page.html", &mut templates.page), ("entry.html", &mut templates.entry),
+            ("index.html", &mut templates.index), ("style.css", &mut templates.style)] {
+            let path = dir.as_ref().join(file);
+            if path.exists() {
+                *template = std::fs::read_to_string(path)?;
+            }
+        }
+        Ok(templates)
+    }
+
+    pub fn with_page(mut self, page : &str) -> HtmlTemplates {
+        self.page = page.to_string();
+        self
+    }
+
+    pub fn with_entry(mut self, entry : &str) -> HtmlTemplates {
+        self.entry = entry.to_string();
+        self
+    }
+
+    pub fn with_index(mut self, index : &str) -> HtmlTemplates {
+        self.index = index.to_string();
+        self
+    }
+
+    pub fn with_style(mut self, style : &str) -> HtmlTemplates {
+        self.style = style.to_string();
+        self
+    }
+}
+
+/// A rendered page of a site
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlPage {
+    /// The file name of the page, relative to the site directory
+    pub file_name: String,
+    pub content: String,
+}
+
+/// Render a resource as a site with an index page and one page per entry
+pub fn render_site(resource : &LexicographicResource, templates : &HtmlTemplates) -> (Vec<HtmlPage>, ConversionReport) {
+    let renderer = Renderer::new(resource, templates, Layout::Site);
+    let mut pages = vec![HtmlPage {
+        file_name: INDEX_FILE.to_string(),
+        content: renderer.page(&renderer.title(), &renderer.index()),
+    }];
+    for (i, entry) in resource.entries.iter().enumerate() {
+        pages.push(HtmlPage {
+            file_name: renderer.files[i].clone(),
+            content: renderer.page(&entry.headword, &renderer.entry(i)),
+        });
+    }
+    (pages, renderer.report)
+}
+
+/// Render a resource as a single document with the index and all entries
+pub fn render_document(resource : &LexicographicResource, templates : &HtmlTemplates) -> (String, ConversionReport) {
+    let renderer = Renderer::new(resource, templates, Layout::Document);
+    let mut content = renderer.index();
+    for i in 0..resource.entries.len() {
+        content.push_str(&renderer.entry(i));
+    }
+    let page = renderer.page(&renderer.title(), &content);
+    (page, renderer.report)
+}
+
+/// Write a resource as a site to a directory, which is created if needed
+pub fn write_site<P : AsRef<Path>>(dir : P, resource : &LexicographicResource, templates : &HtmlTemplates) -> std::io::Result<ConversionReport> {
+    std::fs::create_dir_all(&dir)?;
+    let (pages, report) = render_site(resource, templates);
+    for page in pages {
+        std::fs::write(dir.as_ref().join(&page.file_name), page.content)?;
+    }
+    Ok(report)
+}
+
+/// Escape text for use in HTML content and attribute values
+pub fn escape(text : &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Replace the `{{name}}` placeholders in a template. The values are not
+/// searched for placeholders themselves.
+fn fill(template : &str, values : &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let name = after[..end].trim();
+                match values.iter().find(|(n, _)| *n == name) {
+                    Some((_, value)) => out.push_str(value),
+                    None => out.push_str(&rest[start..start + end + 4]),
+                }
+                rest = &after[end + 2..];
+            },
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Text with the spans between the markers wrapped in the given tags
+fn marked(text : &str, spans : &mut [(usize, usize, String, &'static str)]) -> String {
+    spans.sort_by_key(|s| s.0);
+    let chars : Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    for (start, end, open, close) in spans.iter() {
+        // Overlapping and invalid markers are not highlighted
+        if *start < i || *end > chars.len() || start > end {
+            continue;
+        }
+        out.push_str(&escape(&chars[i..*start].iter().collect::<String>()));
+        out.push_str(open);
+        out.push_str(&escape(&chars[*start..*end].iter().collect::<String>()));
+        out.push_str(close);
+        i = *end;
+    }
+    out.push_str(&escape(&chars[i..].iter().collect::<String>()));
+    out
+}
+
+fn with_markers(text : &str, headword_markers : &[Marker], collocate_markers : &[CollocateMarker]) -> String {
+    let mut spans = Vec::new();
+    for marker in headword_markers {
+        spans.push((marker.start_index, marker.end_index, "<mark class=\"headword\">".to_string(), "</mark>"));
+    }
+    for marker in collocate_markers {
+        let open = match &marker.lemma {
+            Some(lemma) => format!("<mark class=\"collocate\" title=\"{}\">", escape(lemma)),
+            None => "<mark class=\"collocate\">".to_string(),
+        };
+        spans.push((marker.start_index, marker.end_index, open, "</mark>"));
+    }
+    marked(text, &mut spans)
+}
+
+fn with_placeholders(text : &str, placeholder_markers : &[Marker]) -> String {
+    let mut spans : Vec<_> = placeholder_markers.iter()
+        .map(|m| (m.start_index, m.end_index, "<span class=\"placeholder\">".to_string(), "</span>"))
+        .collect();
+    marked(text, &mut spans)
+}
+
+fn lang_attr(lang_code : &Option<LangCode>) -> String {
+    match lang_code {
+        Some(lang) => format!(" lang=\"{}\"", escape(&lang.0)),
+        None => String::new(),
+    }
+}
+
+/// The file name of the page of each entry, from its id where possible
+fn file_names(entries : &[Entry]) -> Vec<String> {
+    let mut used = HashSet::new();
+    used.insert(INDEX_FILE.to_string());
+    entries.iter().enumerate().map(|(i, entry)| {
+        let stem : String = entry.id.as_deref().unwrap_or("").chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        let mut name = format!("{}.html", stem);
+        if stem.is_empty() || used.contains(&name) {
+            name = format!("entry-{}.html", i + 1);
+        }
+        used.insert(name.clone());
+        name
+    }).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Layout {
+    Site,
+    Document,
+}
+
+/// What the ref of a member refers to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    Entry(usize),
+    Sense(usize, usize),
+    Collocate(usize),
+}
+
+struct Renderer<'a> {
+    resource : &'a LexicographicResource,
+    templates : &'a HtmlTemplates,
+    layout : Layout,
+    files : Vec<String>,
+    targets : HashMap<&'a str, Target>,
+    /// The relations that each id is a member of
+    relations : HashMap<&'a str, Vec<&'a Relation>>,
+    relation_types : HashMap<&'a str, &'a RelationType>,
+    /// Senses that are shown inside another sense of their entry
+    embedded : HashSet<&'a str>,
+    /// Relation members that could not be found
+    report : ConversionReport,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(resource : &'a LexicographicResource, templates : &'a HtmlTemplates, layout : Layout) -> Renderer<'a> {
+        let mut targets = HashMap::new();
+        for (i, entry) in resource.entries.iter().enumerate() {
+            if let Some(id) = &entry.id {
+                targets.insert(id.as_str(), Target::Entry(i));
+            }
+            for (j, sense) in entry.senses.iter().enumerate() {
+                if let Some(id) = &sense.id {
+                    targets.insert(id.as_str(), Target::Sense(i, j));
+                }
+                for example in sense.examples.iter() {
+                    for marker in example.collocate_markers.iter() {
+                        if let Some(id) = &marker.id {
+                            targets.insert(id.as_str(), Target::Collocate(i));
+                        }
+                    }
+                }
+            }
+        }
+        let mut report = ConversionReport::new();
+        let mut relations : HashMap<&str, Vec<&Relation>> = HashMap::new();
+        for relation in resource.relations.iter() {
+            for member in relation.members.iter() {
+                if !targets.contains_key(member.ref_.as_str()) {
+                    report.warn(format!("relation {}", relation._type), format!("member {} not found", member.ref_));
+                }
+                relations.entry(member.ref_.as_str()).or_default().push(relation);
+            }
+        }
+        let relation_types = resource.relation_types.iter().map(|t| (t._type.as_str(), t)).collect();
+        let mut renderer = Renderer {
+            resource,
+            templates,
+            layout,
+            files: file_names(&resource.entries),
+            targets,
+            relations,
+            relation_types,
+            embedded: HashSet::new(),
+            report,
+        };
+        renderer.embedded = renderer.find_embedded_senses();
+        renderer
+    }
+
+    fn find_embedded_senses(&self) -> HashSet<&'a str> {
+        let mut embedded = HashSet::new();
+        for relation in self.resource.relations.iter() {
+            for member in relation.members.iter() {
+                let Some(Target::Sense(entry, _)) = self.targets.get(member.ref_.as_str()) else { continue };
+                if self.hint(relation, member) != Some(Hint::Embed) {
+                    continue;
+                }
+                let in_same_entry = relation.members.iter().any(|other| other.ref_ != member.ref_
+                    && matches!(self.targets.get(other.ref_.as_str()), Some(Target::Sense(e, _)) if e == entry));
+                if in_same_entry {
+                    embedded.insert(member.ref_.as_str());
+                }
+            }
+        }
+        embedded
+    }
+
+    /// The hint for a member, which is to navigate if the relation type or
+    /// the role is not declared
+    fn hint(&self, relation : &Relation, member : &Member) -> Option<Hint> {
+        match self.relation_types.get(relation._type.as_str()) {
+            Some(relation_type) => match relation_type.member_types.iter().find(|m| m.role == member.role) {
+                Some(member_type) => member_type.hint.clone(),
+                None => Some(Hint::Navigate),
+            },
+            None => Some(Hint::Navigate),
+        }
+    }
+
+    fn title(&self) -> String {
+        self.resource.title.clone().unwrap_or_else(|| "Dictionary".to_string())
+    }
+
+    fn page(&self, title : &str, content : &str) -> String {
+        fill(&self.templates.page, &[
+            ("lang", &escape(&self.resource.lang_code.0)),
+            ("title", &escape(title)),
+            ("style", &self.templates.style),
+            ("content", content),
+        ])
+    }
+
+    fn entry_anchor(&self, i : usize) -> String {
+        format!("entry-{}", self.files[i].trim_end_matches(".html"))
+    }
+
+    fn sense_anchor(&self, i : usize, j : usize) -> String {
+        match &self.resource.entries[i].senses[j].id {
+            Some(id) => escape(id),
+            None => format!("{}-sense-{}", self.entry_anchor(i), j + 1),
+        }
+    }
+
+    fn link(&self, target : Target) -> String {
+        let (entry, fragment) = match target {
+            Target::Entry(i) | Target::Collocate(i) => (i, self.entry_anchor(i)),
+            Target::Sense(i, j) => (i, self.sense_anchor(i, j)),
+        };
+        match (self.layout, target) {
+            (Layout::Site, Target::Entry(_)) | (Layout::Site, Target::Collocate(_)) => self.files[entry].clone(),
+            (Layout::Site, Target::Sense(..)) => format!("{}#{}", self.files[entry], fragment),
+            (Layout::Document, _) => format!("#{}", fragment),
+        }
+    }
+
+    fn index_link(&self) -> &'static str {
+        match self.layout {
+            Layout::Site => INDEX_FILE,
+            Layout::Document => "#index",
+        }
+    }
+
+    fn homograph_number(&self, entry : &Entry) -> String {
+        match entry.homograph_number {
+            Some(number) => format!("<sup class=\"homograph-number\">{}</sup>", number),
+            None => String::new(),
+        }
+    }
+
+    fn headword(&self, entry : &Entry) -> String {
+        with_placeholders(&entry.headword, &entry.placeholder_markers) + &self.homograph_number(entry)
+    }
+
+    /// A tag shown as an abbreviation explained by its description
+    fn tag(&self, class : &str, tag : &str, description : Option<&String>) -> String {
+        match description {
+            Some(description) => format!("<abbr class=\"{}\" title=\"{}\">{}</abbr>", class, escape(description), escape(tag)),
+            None => format!("<span class=\"{}\">{}</span>", class, escape(tag)),
+        }
+    }
+
+    fn parts_of_speech(&self, parts_of_speech : &[String]) -> String {
+        parts_of_speech.iter().map(|pos| {
+            let description = self.resource.part_of_speech_tags.iter().find(|t| &t.tag == pos).and_then(|t| t.description.as_ref());
+            self.tag("pos", pos, description)
+        }).collect::<Vec<_>>().join(" ")
+    }
+
+    fn labels(&self, labels : &[String]) -> String {
+        labels.iter().map(|label| {
+            let description = self.resource.label_tags.iter().find(|t| &t.tag == label).and_then(|t| t.description.as_ref());
+            self.tag("label", label, description)
+        }).collect::<Vec<_>>().join(" ")
+    }
+
+    fn grammar(&self, parts_of_speech : &[String], labels : &[String]) -> String {
+        let parts : Vec<String> = [self.parts_of_speech(parts_of_speech), self.labels(labels)].into_iter()
+            .filter(|s| !s.is_empty()).collect();
+        if parts.is_empty() {
+            String::new()
+        } else {
+            format!("<span class=\"grammar\">{}</span>", parts.join(" "))
+        }
+    }
+
+    fn pronunciations(&self, pronunciations : &[Pronunciation]) -> String {
+        pronunciations.iter().map(|pronunciation| {
+            let mut out = String::from("<span class=\"pronunciation\">");
+            for transcription in pronunciation.transcriptions.iter() {
+                let description = transcription.scheme.as_ref().and_then(|scheme|
+                    self.resource.transcription_scheme_tags.iter().find(|t| &t.tag == scheme)
+                        .and_then(|t| t.description.clone()).or_else(|| Some(scheme.clone())));
+                match description {
+                    Some(scheme) => out.push_str(&format!("<span class=\"transcription\" title=\"{}\">/{}/</span>", escape(&scheme), escape(&transcription.text))),
+                    None => out.push_str(&format!("<span class=\"transcription\">/{}/</span>", escape(&transcription.text))),
+                }
+            }
+            if let Some(sound_file) = &pronunciation.sound_file {
+                out.push_str(&format!(" <audio controls src=\"{}\"></audio>", escape(sound_file)));
+            }
+            let labels = self.labels(&pronunciation.labels);
+            if !labels.is_empty() {
+                out.push(' ');
+                out.push_str(&labels);
+            }
+            out.push_str("</span>");
+            out
+        }).collect::<Vec<_>>().join(" ")
+    }
+
+    fn inflected_forms(&self, forms : &[InflectedForm]) -> String {
+        if forms.is_empty() {
+            return String::new();
+        }
+        let items : Vec<String> = forms.iter().map(|form| {
+            let mut out = String::from("<li class=\"inflected-form\">");
+            if let Some(tag) = &form.tag {
+                let description = self.resource.inflected_form_tags.iter().find(|t| &t.tag == tag).and_then(|t| t.description.as_ref());
+                out.push_str(&self.tag("inflected-form-tag", tag, description));
+                out.push(' ');
+            }
+            out.push_str(&format!("<b>{}</b>", escape(&form.text)));
+            for part in [self.labels(&form.labels), self.pronunciations(&form.pronunciations)] {
+                if !part.is_empty() {
+                    out.push(' ');
+                    out.push_str(&part);
+                }
+            }
+            out.push_str("</li>");
+            out
+        }).collect();
+        format!("<ul class=\"inflected-forms\">{}</ul>", items.join(""))
+    }
+
+    fn entry(&self, i : usize) -> String {
+        let entry = &self.resource.entries[i];
+        fill(&self.templates.entry, &[
+            ("anchor", &self.entry_anchor(i)),
+            ("headword", &with_placeholders(&entry.headword, &entry.placeholder_markers)),
+            ("homograph_number", &self.homograph_number(entry)),
+            ("grammar", &self.grammar(&entry.parts_of_speech, &entry.labels)),
+            ("pronunciations", &self.pronunciations(&entry.pronunciations)),
+            ("inflected_forms", &self.inflected_forms(&entry.inflected_forms)),
+            ("senses", &self.senses(i, &mut vec![])),
+            ("related", &self.related(entry.id.as_deref(), &mut vec![])),
+            ("etymologies", &self.etymologies(entry)),
+            ("index", self.index_link()),
+            ("resource_title", &escape(&self.title())),
+        ])
+    }
+
+    fn senses(&self, i : usize, path : &mut Vec<&'a str>) -> String {
+        let entry = &self.resource.entries[i];
+        let mut out = String::new();
+        for (j, sense) in entry.senses.iter().enumerate() {
+            if sense.id.as_deref().map(|id| self.embedded.contains(id)).unwrap_or(false) {
+                continue;
+            }
+            out.push_str(&self.sense(i, j, path));
+        }
+        out
+    }
+
+    fn sense(&self, i : usize, j : usize, path : &mut Vec<&'a str>) -> String {
+        let sense = &self.resource.entries[i].senses[j];
+        let mut out = format!("<li class=\"sense\" id=\"{}\">", self.sense_anchor(i, j));
+        if let Some(indicator) = &sense.indicator {
+            out.push_str(&format!("<span class=\"indicator\">{}</span> ", escape(indicator)));
+        }
+        let labels = self.labels(&sense.labels);
+        if !labels.is_empty() {
+            out.push_str(&labels);
+            out.push(' ');
+        }
+        for definition in sense.definitions.iter() {
+            out.push_str(&format!("<div class=\"definition\">{}</div>",
+                with_markers(&definition.text, &definition.headword_markers, &definition.collocate_markers)));
+        }
+        for explanation in sense.headword_explanations.iter() {
+            out.push_str(&format!("<div class=\"headword-explanation\"{}>{}</div>", lang_attr(&explanation.lang_code),
+                with_placeholders(&explanation.text, &explanation.placeholder_markers)));
+        }
+        if !sense.headword_translations.is_empty() {
+            let translations : Vec<String> = sense.headword_translations.iter().map(|translation| {
+                let mut t = format!("<span class=\"headword-translation\"{}>{}</span>", lang_attr(&translation.lang_code),
+                    with_placeholders(&translation.text, &translation.placeholder_markers));
+                let grammar = self.grammar(&translation.parts_of_speech, &translation.labels);
+                if !grammar.is_empty() {
+                    t.push(' ');
+                    t.push_str(&grammar);
+                }
+                t
+            }).collect();
+            out.push_str(&format!("<div class=\"headword-translations\">{}</div>", translations.join(", ")));
+        }
+        if !sense.examples.is_empty() {
+            out.push_str("<ul class=\"examples\">");
+            for example in sense.examples.iter() {
+                out.push_str(&self.example(example));
+            }
+            out.push_str("</ul>");
+        }
+        if let Some(id) = sense.id.as_deref() {
+            path.push(id);
+            out.push_str(&self.related(Some(id), path));
+            path.pop();
+        }
+        out.push_str("</li>\n");
+        out
+    }
+
+    fn example(&self, example : &Example) -> String {
+        let mut out = format!("<li class=\"example\">{}",
+            with_markers(&example.text, &example.headword_markers, &example.collocate_markers));
+        let labels = self.labels(&example.labels);
+        if !labels.is_empty() {
+            out.push(' ');
+            out.push_str(&labels);
+        }
+        if example.source_identity.is_some() || example.source_elaboration.is_some() {
+            let source : Vec<&str> = example.source_identity.iter().chain(example.source_elaboration.iter())
+                .map(|s| s.as_str()).collect();
+            out.push_str(&format!(" <span class=\"source\">{}</span>", escape(&source.join(", "))));
+        }
+        if !example.example_translations.is_empty() {
+            out.push_str("<ul class=\"example-translations\">");
+            for translation in example.example_translations.iter() {
+                out.push_str(&format!("<li class=\"example-translation\"{}>{}</li>", lang_attr(&translation.lang_code),
+                    with_markers(&translation.text, &translation.headword_markers, &translation.collocate_markers)));
+            }
+            out.push_str("</ul>");
+        }
+        out.push_str("</li>");
+        out
+    }
+
+    /// The members related to an entry or sense, embedded or linked to
+    /// according to their hints
+    fn related(&self, id : Option<&'a str>, path : &mut Vec<&'a str>) -> String {
+        let Some(id) = id else { return String::new() };
+        let mut links = Vec::new();
+        let mut embedded = String::new();
+        for relation in self.relations.get(id).map(|r| r.as_slice()).unwrap_or(&[]) {
+            for member in relation.members.iter().filter(|m| m.ref_ != id) {
+                let Some(target) = self.targets.get(member.ref_.as_str()) else { continue };
+                match self.hint(relation, member) {
+                    Some(Hint::Embed) if path.len() < MAX_EMBED_DEPTH && !path.contains(&member.ref_.as_str()) => {
+                        path.push(member.ref_.as_str());
+                        embedded.push_str(&self.embed(*target, path));
+                        path.pop();
+                    },
+                    Some(Hint::None) => (),
+                    _ => {
+                        let description = self.relation_types.get(relation._type.as_str())
+                            .and_then(|t| t.description.clone())
+                            .or_else(|| relation.description.clone())
+                            .unwrap_or_else(|| relation._type.clone());
+                        links.push(format!("<li class=\"relation\"><span class=\"relation-type\">{}</span> <a href=\"{}\">{}</a></li>",
+                            escape(&description), escape(&self.link(*target)), self.target_name(*target)));
+                    }
+                }
+            }
+        }
+        let mut out = String::new();
+        if !embedded.is_empty() {
+            out.push_str(&format!("<ol class=\"embedded\">{}</ol>", embedded));
+        }
+        if !links.is_empty() {
+            out.push_str(&format!("<ul class=\"related\">{}</ul>", links.join("")));
+        }
+        out
+    }
+
+    fn target_name(&self, target : Target) -> String {
+        match target {
+            Target::Entry(i) | Target::Collocate(i) => self.headword(&self.resource.entries[i]),
+            Target::Sense(i, j) => {
+                let entry = &self.resource.entries[i];
+                match &entry.senses[j].indicator {
+                    Some(indicator) => format!("{} ({})", self.headword(entry), escape(indicator)),
+                    None => format!("{} {}", self.headword(entry), j + 1),
+                }
+            }
+        }
+    }
+
+    fn embed(&self, target : Target, path : &mut Vec<&'a str>) -> String {
+        match target {
+            Target::Sense(i, j) => self.sense(i, j, path),
+            Target::Entry(i) | Target::Collocate(i) => {
+                let entry = &self.resource.entries[i];
+                format!("<li class=\"embedded-entry\"><a class=\"headword\" href=\"{}\">{}</a> {}<ol class=\"senses\">{}</ol></li>\n",
+                    escape(&self.link(Target::Entry(i))), self.headword(entry),
+                    self.grammar(&entry.parts_of_speech, &entry.labels), self.senses(i, path))
+            }
+        }
+    }
+
+    fn etymologies(&self, entry : &Entry) -> String {
+        entry.etymologies.iter().map(|etymology| {
+            let mut out = String::from("<div class=\"etymology\">");
+            if let Some(description) = &etymology.description {
+                out.push_str(&escape(description));
+            }
+            for etymon in etymology.etymons.iter() {
+                let units : Vec<String> = etymon.etymon_units.iter().map(|unit| {
+                    let mut u = format!("<span class=\"etymon-unit\" lang=\"{}\">{}{}</span>", escape(&unit.lang_code.0),
+                        if unit.reconstructed == Some(true) { "*" } else { "" }, escape(&unit.text));
+                    if let Some(translation) = &unit.translation {
+                        u.push_str(&format!(" ‘{}’", escape(translation)));
+                    }
+                    u
+                }).collect();
+                out.push_str(&format!(" <span class=\"etymon\">{}</span>", units.join(", ")));
+            }
+            out.push_str("</div>");
+            out
+        }).collect()
+    }
+
+    /// The entries in alphabetical order, grouped by their first letter
+    fn index(&self) -> String {
+        let collation = Collation::for_resource(self.resource);
+        let mut order : Vec<usize> = (0..self.resource.entries.len()).collect();
+        order.sort_by_cached_key(|&i| {
+            let entry = &self.resource.entries[i];
+            (collation.key(&entry.headword), entry.homograph_number, i)
+        });
+        let mut letters = Vec::new();
+        let mut entries = String::new();
+        let mut current : Option<String> = None;
+        for i in order {
+            let entry = &self.resource.entries[i];
+            let letter = collation.initial(&entry.headword);
+            if current.as_ref() != Some(&letter) {
+                if current.is_some() {
+                    entries.push_str("</ul>\n");
+                }
+                let anchor = format!("letter-{}", letters.len() + 1);
+                letters.push(format!("<a href=\"#{}\">{}</a>", anchor, escape(&letter)));
+                entries.push_str(&format!("<h2 id=\"{}\">{}</h2>\n<ul>\n", anchor, escape(&letter)));
+                current = Some(letter);
+            }
+            entries.push_str(&format!("<li><a href=\"{}\">{}</a> {}</li>\n", escape(&self.link(Target::Entry(i))),
+                self.headword(entry), self.parts_of_speech(&entry.parts_of_speech)));
+        }
+        if current.is_some() {
+            entries.push_str("</ul>\n");
+        }
+        fill(&self.templates.index, &[
+            ("title", &escape(&self.title())),
+            ("letters", &letters.join("")),
+            ("entries", &entries),
+        ])
+    }
+}
+
+/// HTML as a format of the registry, written as a single document with the
+/// index and all entries. Use `write_site` for one page per entry.
+#[derive(Debug, Clone, Default)]
+pub struct HtmlFormat {
+    pub templates : HtmlTemplates,
+}
+
+impl ResourceWriter for HtmlFormat {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> Result<(), WriteError> {
+        self.write_resource_with_report(output, resource, options)?;
+        Ok(())
+    }
+
+    fn write_resource_with_report(&self, output : &mut dyn Write, resource : &LexicographicResource, _options : &WriteOptions) -> Result<ConversionReport, WriteError> {
+        let (document, report) = render_document(resource, &self.templates);
+        output.write_all(document.as_bytes())?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_entry, Format};
+    use std::fs::File;
+
+    fn page<'a>(pages : &'a [HtmlPage], file_name : &str) -> &'a str {
+        &pages.iter().find(|p| p.file_name == file_name).unwrap().content
+    }
+
+    #[test]
+    fn test_site_pages() {
+        let resource = parse(File::open("examples/0.xml").unwrap(), &Format::XML, &None).unwrap();
+        let (pages, report) = render_site(&resource, &HtmlTemplates::default());
+        assert!(report.is_empty(), "{}", report);
+        assert_eq!(pages.iter().map(|p| p.file_name.as_str()).collect::<Vec<_>>(), vec!["index.html", "abandon-verb.html"]);
+        let index = page(&pages, "index.html");
+        assert!(index.contains("<title>Example Dictionary</title>"));
+        assert!(index.contains("<h2 id=\"letter-1\">A</h2>"));
+        assert!(index.contains("<a href=\"abandon-verb.html\">abandon</a>"));
+        let entry = page(&pages, "abandon-verb.html");
+        assert!(entry.contains("<html lang=\"en\">"));
+        assert!(entry.contains("<li class=\"example\">I&#39;m sorry I abandoned you like that.</li>"));
+        assert!(entry.contains("<a href=\"index.html\">Example Dictionary</a>"));
+    }
+
+    #[test]
+    fn test_tag_descriptions() {
+        let resource = parse(File::open("examples/5.xml").unwrap(), &Format::XML, &None).unwrap();
+        let (document, _) = render_document(&resource, &HtmlTemplates::default());
+        assert!(document.contains("<abbr class=\"pos\" title=\"masculine noun\">n-masc</abbr>"));
+        assert!(document.contains("<abbr class=\"inflected-form-tag\" title=\"singular genitive\">sg-gen</abbr> <b>folúsghlantóra</b>"));
+    }
+
+    #[test]
+    fn test_markers() {
+        let entry = parse_entry(File::open("examples/22.xml").unwrap(), &Format::XML, &None).unwrap();
+        let resource = LexicographicResource {
+            lang_code: LangCode("en".to_string()),
+            entries: vec![entry],
+            ..LexicographicResource::default()
+        };
+        let (document, _) = render_document(&resource, &HtmlTemplates::default());
+        assert!(document.contains("The coroner <mark class=\"collocate\" title=\"perform\">performed</mark> an <mark class=\"headword\">autopsy</mark>."));
+        assert!(document.contains("<li class=\"example-translation\" lang=\"cs\">Koroner <mark class=\"collocate\" title=\"provést\">provedl</mark>"));
+    }
+
+    #[test]
+    fn test_embedded_subsenses() {
+        let resource = parse(File::open("examples/16.xml").unwrap(), &Format::XML, &None).unwrap();
+        let (pages, _) = render_site(&resource, &HtmlTemplates::default());
+        let entry = page(&pages, "colour.html");
+        // The subsenses are shown inside their parent only
+        assert_eq!(entry.matches("id=\"colour-2\"").count(), 1);
+        let parent = entry.find("id=\"colour-1\"").unwrap();
+        let embedded = entry.find("<ol class=\"embedded\">").unwrap();
+        let child = entry.find("id=\"colour-2\"").unwrap();
+        let next = entry.find("id=\"colour-4\"").unwrap();
+        assert!(parent < embedded && embedded < child && child < next);
+        // The parent has the hint none, so the subsenses do not link back to it
+        assert!(!entry.contains("<ul class=\"related\">"));
+    }
+
+    #[test]
+    fn test_embedded_and_linked_entries() {
+        let resource = parse(File::open("examples/17.xml").unwrap(), &Format::XML, &None).unwrap();
+        let (pages, _) = render_site(&resource, &HtmlTemplates::default());
+        let safe = page(&pages, "safe.html");
+        assert!(safe.contains("<li class=\"embedded-entry\"><a class=\"headword\" href=\"better-safe.html\">better safe than sorry</a>"));
+        let better_safe = page(&pages, "better-safe.html");
+        assert!(better_safe.contains("<span class=\"relation-type\">subentrying</span> <a href=\"safe.html#safe-1\">safe (protected from harm)</a>"));
+    }
+
+    #[test]
+    fn test_templates() {
+        let resource = parse(File::open("examples/0.xml").unwrap(), &Format::XML, &None).unwrap();
+        let templates = HtmlTemplates::default()
+            .with_page("<main data-title=\"{{title}}\">{{content}}</main>")
+            .with_entry("<h1>{{headword}}</h1>{{grammar}}{{unknown}}")
+            .with_index("");
+        let (pages, _) = render_site(&resource, &templates);
+        assert_eq!(page(&pages, "abandon-verb.html"),
+            "<main data-title=\"abandon\"><h1>abandon</h1><span class=\"grammar\"><span class=\"pos\">verb</span></span>{{unknown}}</main>");
+    }
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(fill("{{a}} and {{ b }} but not {{c}} or {{", &[("a", "{{b}}"), ("b", "2")]), "{{b}} and 2 but not {{c}} or {{");
+    }
+}
@@ -0,0 +1,260 @@
+//! Export of the headwords and inflected forms of a resource as a Hunspell
+//! spell-checking dictionary
+//!
+//! The plain dictionary lists every headword and inflected form as its own
+//! stem. With affix inference, each inflected form that shares a beginning
+//! with its headword is turned into a suffix rule that strips the rest of the
+//! headword and adds the rest of the form. Rules that apply to exactly the
+//! same stems are grouped under one flag, so that the compressed dictionary
+//! accepts exactly the words of the plain one. Forms that share nothing with
+//! their headword, such as suppletive forms, are listed as their own stems.
+//! Multi-word headwords and forms are split into words, as Hunspell checks
+//! single words.
+use crate::model::*;
+use crate::report::ConversionReport;
+use crate::stardict::with_suffix;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+/// Single character flags are used as long as there are enough letters
+const LETTER_FLAGS : &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Options for the export
+#[derive(Debug, Clone, PartialEq)]
+pub struct HunspellOptions {
+    /// Infer suffix rules from the inflected forms rather than listing every
+    /// form as a stem
+    pub infer_affixes: bool,
+}
+
+impl Default for HunspellOptions {
+    fn default() -> HunspellOptions {
+        HunspellOptions { infer_affixes: true }
+    }
+}
+
+/// The files of a Hunspell dictionary
+#[derive(Debug, Clone, PartialEq)]
+pub struct HunspellFiles {
+    pub dic: String,
+    pub aff: String,
+}
+
+/// A suffix rule: strip the end of the stem and add a suffix, for stems
+/// that end with the condition
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct SuffixRule {
+    strip: String,
+    add: String,
+}
+
+impl SuffixRule {
+    /// The rule that derives a form from a stem, if they share a beginning
+    fn derive(stem : &str, form : &str) -> Option<SuffixRule> {
+        let common = stem.chars().zip(form.chars()).take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8()).sum::<usize>();
+        if common == 0 || stem == form {
+            None
+        } else {
+            Some(SuffixRule {
+                strip: stem[common..].to_string(),
+                add: form[common..].to_string(),
+            })
+        }
+    }
+
+    fn aff_line(&self, flag : &str) -> String {
+        let or_zero = |s : &str| if s.is_empty() { "0".to_string() } else { s.to_string() };
+        let condition = if self.strip.is_empty() { ".".to_string() } else { self.strip.clone() };
+        format!("SFX {} {} {} {}", flag, or_zero(&self.strip), or_zero(&self.add), condition)
+    }
+}
+
+/// Render a resource as Hunspell files
+pub fn render_hunspell(resource : &LexicographicResource, options : &HunspellOptions) -> (HunspellFiles, ConversionReport) {
+    let mut report = ConversionReport::new();
+    // The rules of each stem, empty for stems without rules
+    let mut stems : BTreeMap<String, BTreeSet<SuffixRule>> = BTreeMap::new();
+    for entry in resource.entries.iter() {
+        let single_word = !entry.headword.contains(char::is_whitespace);
+        for word in entry.headword.split_whitespace() {
+            stems.entry(word.to_string()).or_default();
+        }
+        for form in entry.inflected_forms.iter() {
+            let rule = if options.infer_affixes && single_word && !form.text.contains(char::is_whitespace) {
+                SuffixRule::derive(&entry.headword, &form.text)
+            } else {
+                None
+            };
+            match rule {
+                Some(rule) => {
+                    stems.entry(entry.headword.clone()).or_default().insert(rule);
+                },
+                None => {
+                    if options.infer_affixes && single_word && form.text != entry.headword {
+                        report.warn(entry.id.clone().unwrap_or_else(|| entry.headword.clone()),
+                            format!("{} is not formed with a suffix and is listed as a stem", form.text));
+                    }
+                    for word in form.text.split_whitespace() {
+                        stems.entry(word.to_string()).or_default();
+                    }
+                }
+            }
+        }
+    }
+    // Group the rules that apply to the same stems under one flag
+    let mut stems_of_rule : BTreeMap<&SuffixRule, Vec<&str>> = BTreeMap::new();
+    for (stem, rules) in stems.iter() {
+        for rule in rules.iter() {
+            stems_of_rule.entry(rule).or_default().push(stem.as_str());
+        }
+    }
+    let mut groups : BTreeMap<Vec<&str>, Vec<&SuffixRule>> = BTreeMap::new();
+    for (rule, rule_stems) in stems_of_rule {
+        groups.entry(rule_stems).or_default().push(rule);
+    }
+    let numeric = groups.len() > LETTER_FLAGS.len();
+    let flag_name = |i : usize| if numeric {
+        (i + 1).to_string()
+    } else {
+        LETTER_FLAGS.chars().nth(i).unwrap().to_string()
+    };
+    let mut flags_of_stem : HashMap<&str, Vec<String>> = HashMap::new();
+    let mut aff = String::from("SET UTF-8\n");
+    if numeric {
+        aff.push_str("FLAG num\n");
+    }
+    aff.push_str(&format!("TRY {}\n", try_characters(stems.keys())));
+    for (i, (group_stems, rules)) in groups.iter().enumerate() {
+        let flag = flag_name(i);
+        aff.push_str(&format!("\nSFX {} Y {}\n", flag, rules.len()));
+        for rule in rules.iter() {
+            aff.push_str(&rule.aff_line(&flag));
+            aff.push('\n');
+        }
+        for stem in group_stems.iter() {
+            flags_of_stem.entry(stem).or_default().push(flag.clone());
+        }
+    }
+    let mut dic = format!("{}\n", stems.len());
+    for stem in stems.keys() {
+        dic.push_str(stem);
+        if let Some(flags) = flags_of_stem.get(stem.as_str()) {
+            dic.push('/');
+            dic.push_str(&flags.join(if numeric { "," } else { "" }));
+        }
+        dic.push('\n');
+    }
+    (HunspellFiles { dic, aff }, report)
+}
+
+/// The characters of the words, most frequent first, for suggestions
+fn try_characters<'a, I : Iterator<Item=&'a String>>(words : I) -> String {
+    let mut counts : HashMap<char, usize> = HashMap::new();
+    for c in words.flat_map(|w| w.chars()) {
+        *counts.entry(c).or_default() += 1;
+    }
+    let mut chars : Vec<(char, usize)> = counts.into_iter().collect();
+    chars.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    chars.into_iter().map(|(c, _)| c).collect()
+}
+
+/// Write a resource as Hunspell files named after `base`, so that `out/en`
+/// gives `out/en.dic` and `out/en.aff`
+pub fn write_hunspell<P : AsRef<Path>>(base : P, resource : &LexicographicResource, options : &HunspellOptions) -> std::io::Result<ConversionReport> {
+    let (files, report) = render_hunspell(resource, options);
+    std::fs::write(with_suffix(base.as_ref(), "dic"), files.dic)?;
+    std::fs::write(with_suffix(base.as_ref(), "aff"), files.aff)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    /// All the words accepted by a dictionary, by applying its suffix rules
+    fn expand(files : &HunspellFiles) -> BTreeSet<String> {
+        let numeric = files.aff.contains("FLAG num");
+        let mut rules : HashMap<&str, Vec<(&str, &str, &str)>> = HashMap::new();
+        for line in files.aff.lines().filter(|l| l.starts_with("SFX ")) {
+            let fields : Vec<&str> = line.split(' ').collect();
+            if fields.len() == 5 {
+                let strip = if fields[2] == "0" { "" } else { fields[2] };
+                let add = if fields[3] == "0" { "" } else { fields[3] };
+                rules.entry(fields[1]).or_default().push((strip, add, fields[4]));
+            }
+        }
+        let mut words = BTreeSet::new();
+        for line in files.dic.lines().skip(1) {
+            let (stem, flags) = line.split_once('/').unwrap_or((line, ""));
+            words.insert(stem.to_string());
+            let flags : Vec<String> = if numeric {
+                flags.split(',').filter(|f| !f.is_empty()).map(|f| f.to_string()).collect()
+            } else {
+                flags.chars().map(|c| c.to_string()).collect()
+            };
+            for flag in flags {
+                for (strip, add, condition) in rules[flag.as_str()].iter() {
+                    if *condition == "." || stem.ends_with(condition) {
+                        words.insert(format!("{}{}", &stem[..stem.len() - strip.len()], add));
+                    }
+                }
+            }
+        }
+        words
+    }
+
+    fn entry(headword : &str, forms : &[&str]) -> Entry {
+        Entry {
+            headword: headword.to_string(),
+            inflected_forms: forms.iter().map(|f| InflectedForm { text: f.to_string(), ..InflectedForm::default() }).collect(),
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn test_plain() {
+        let resource = parse(File::open("examples/5.xml").unwrap(), &Format::XML, &None).unwrap();
+        let (files, report) = render_hunspell(&resource, &HunspellOptions { infer_affixes: false });
+        assert!(report.is_empty());
+        assert_eq!(files.dic, "3\nfolúsghlantóir\nfolúsghlantóirí\nfolúsghlantóra\n");
+        assert!(!files.aff.contains("SFX"));
+    }
+
+    #[test]
+    fn test_inferred_affixes() {
+        let resource = LexicographicResource {
+            entries: vec![
+                entry("cat", &["cats"]),
+                entry("dog", &["dogs"]),
+                entry("city", &["cities"]),
+                entry("walk", &["walks", "walked", "walking"]),
+                entry("go", &["goes", "went", "gone"]),
+                entry("ice cream", &["ice creams"]),
+            ],
+            ..LexicographicResource::default()
+        };
+        let (files, report) = render_hunspell(&resource, &HunspellOptions::default());
+        assert_eq!(report.warnings[0].to_string(), "go: went is not formed with a suffix and is listed as a stem");
+        assert_eq!(files.dic, "9\ncat/A\ncity/B\ncream\ncreams\ndog/A\ngo/C\nice\nwalk/AD\nwent\n");
+        assert!(files.aff.contains("\nSFX B Y 1\nSFX B y ies y\n"), "{}", files.aff);
+        assert!(files.aff.contains("\nSFX D Y 2\nSFX D 0 ed .\nSFX D 0 ing .\n"), "{}", files.aff);
+        let (plain, _) = render_hunspell(&resource, &HunspellOptions { infer_affixes: false });
+        assert_eq!(expand(&files), expand(&plain));
+    }
+
+    #[test]
+    fn test_numeric_flags() {
+        let entries : Vec<Entry> = (0..60).map(|i| {
+            let suffix = format!("{}", char::from_u32(0x3b1 + (i % 24)).unwrap()).repeat(1 + (i / 24) as usize);
+            entry(&format!("stem{}", i), &[&format!("stem{}{}", i, suffix)])
+        }).collect();
+        let resource = LexicographicResource { entries, ..LexicographicResource::default() };
+        let (files, _) = render_hunspell(&resource, &HunspellOptions::default());
+        assert!(files.aff.contains("FLAG num\n"));
+        let (plain, _) = render_hunspell(&resource, &HunspellOptions { infer_affixes: false });
+        assert_eq!(expand(&files), expand(&plain));
+    }
+}
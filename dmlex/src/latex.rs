@@ -0,0 +1,482 @@
+//! Print-ready LaTeX output
+//!
+//! Entries are sorted by a collation order and grouped in sections by their
+//! first letter. Every element of an entry is wrapped in a macro, such as
+//! `\dmlexheadword` or `\dmlexpos`, whose definition is written to the
+//! preamble and can be changed with `LatexStyle` to restyle the output
+//! without touching the entries. The default preamble loads `fontspec`, so
+//! the output should be typeset with XeLaTeX or LuaLaTeX and a font that has
+//! the IPA characters of the transcriptions.
+use crate::collation::Collation;
+use crate::model::*;
+use crate::registry::{ResourceWriter, WriteOptions};
+use crate::report::ConversionReport;
+use crate::WriteError;
+use std::collections::BTreeSet;
+use std::io::Write;
+
+/// The macros used in the output with their default definitions. All
+/// macros take a single argument.
+const MACROS : [(&str, &str); 18] = [
+    ("section", "\\section*{#1}"),
+    ("entry", "\\par\\noindent\\hangindent=1em #1\\par"),
+    ("headword", "\\textbf{#1}"),
+    ("homograph", "\\textsuperscript{#1}"),
+    ("pos", "\\textit{#1}"),
+    ("label", "\\textit{#1}"),
+    ("pronunciation", "/#1/"),
+    ("inflectedform", "\\textbf{#1}"),
+    ("sense", "\\textbf{#1.}"),
+    ("indicator", "(\\textit{#1})"),
+    ("definition", "#1"),
+    ("translation", "#1"),
+    ("lang", "\\textsc{#1}"),
+    ("example", "\\textit{#1}"),
+    ("exampletranslation", "#1"),
+    ("headwordmarker", "\\textbf{#1}"),
+    ("collocatemarker", "#1"),
+    ("etymology", "[#1]"),
+];
+
+/// The definitions of the macros and the document setup
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatexStyle {
+    /// The macro names, without the `dmlex` prefix, and their definitions
+    pub macros: Vec<(String, String)>,
+    pub document_class: String,
+    /// Lines added to the preamble before the macro definitions
+    pub preamble: Vec<String>,
+}
+
+impl Default for LatexStyle {
+    fn default() -> LatexStyle {
+        LatexStyle {
+            macros: MACROS.iter().map(|(name, definition)| (name.to_string(), definition.to_string())).collect(),
+            document_class: "\\documentclass[twocolumn]{article}".to_string(),
+            preamble: vec!["\\usepackage{fontspec}".to_string()],
+        }
+    }
+}
+
+impl LatexStyle {
+    /// Change the definition of a macro, given without the `dmlex` prefix
+    pub fn with_macro(mut self, name : &str, definition : &str) -> LatexStyle {
+        match self.macros.iter_mut().find(|(n, _)| n == name) {
+            Some(m) => m.1 = definition.to_string(),
+            None => self.macros.push((name.to_string(), definition.to_string())),
+        }
+        self
+    }
+
+    pub fn with_document_class(mut self, document_class : &str) -> LatexStyle {
+        self.document_class = document_class.to_string();
+        self
+    }
+
+    pub fn with_preamble(mut self, line : &str) -> LatexStyle {
+        self.preamble.push(line.to_string());
+        self
+    }
+}
+
+/// Options for the LaTeX output
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatexOptions {
+    pub style: LatexStyle,
+    /// The letters of the alphabet in collation order, which may contain
+    /// several characters such as `ch`. Entries are sorted by their
+    /// lowercased headwords and characters that are not in the alphabet
+    /// sort after it. If empty, headwords are sorted with the collation of
+    /// the language of the resource.
+    pub alphabet: Vec<String>,
+    /// Write a complete document with a preamble, rather than only the
+    /// macro definitions and the entries for inclusion in another document
+    pub standalone: bool,
+}
+
+impl Default for LatexOptions {
+    fn default() -> LatexOptions {
+        LatexOptions {
+            style: LatexStyle::default(),
+            alphabet: Vec::new(),
+            standalone: true,
+        }
+    }
+}
+
+impl LatexOptions {
+    pub fn with_style(mut self, style : LatexStyle) -> LatexOptions {
+        self.style = style;
+        self
+    }
+
+    /// Set the collation order from the letters separated by spaces, e.g.
+    /// `"a b c ch d"`
+    pub fn with_alphabet(mut self, alphabet : &str) -> LatexOptions {
+        self.alphabet = alphabet.split_whitespace().map(|l| l.to_lowercase()).collect();
+        self
+    }
+
+    pub fn with_standalone(mut self, standalone : bool) -> LatexOptions {
+        self.standalone = standalone;
+        self
+    }
+
+    /// The letters of a word as indices in the alphabet, with the longest
+    /// letter matched first
+    fn letters(&self, word : &str) -> Vec<(usize, String)> {
+        let word = word.to_lowercase();
+        let mut letters = Vec::new();
+        let mut rest = word.as_str();
+        while let Some(c) = rest.chars().next() {
+            let letter = self.alphabet.iter().enumerate()
+                .filter(|(_, l)| !l.is_empty() && rest.starts_with(l.as_str()))
+                .max_by_key(|(_, l)| l.len());
+            match letter {
+                Some((i, l)) => {
+                    letters.push((i, l.clone()));
+                    rest = &rest[l.len()..];
+                },
+                None => {
+                    letters.push((self.alphabet.len() + c as usize, c.to_string()));
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        letters
+    }
+
+    fn collation_key(&self, collation : &Collation, word : &str) -> Vec<u32> {
+        if self.alphabet.is_empty() {
+            return collation.key(word);
+        }
+        self.letters(word).into_iter().map(|(i, _)| i as u32).collect()
+    }
+
+    /// The section header of a word, its first letter in upper case
+    fn initial(&self, collation : &Collation, word : &str) -> String {
+        if self.alphabet.is_empty() {
+            return collation.initial(word);
+        }
+        match self.letters(word).into_iter().next() {
+            Some((_, letter)) => {
+                let mut chars = letter.chars();
+                chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+            },
+            None => String::new(),
+        }
+    }
+}
+
+/// Escape the characters that are special in LaTeX
+pub fn escape(text : &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn command(name : &str, argument : &str) -> String {
+    format!("\\dmlex{}{{{}}}", name, argument)
+}
+
+/// Text with the headword and collocate markers wrapped in their macros
+fn with_markers(text : &str, headword_markers : &[Marker], collocate_markers : &[CollocateMarker]) -> String {
+    let mut spans : Vec<(usize, usize, &str)> = headword_markers.iter()
+        .map(|m| (m.start_index, m.end_index, "headwordmarker"))
+        .chain(collocate_markers.iter().map(|m| (m.start_index, m.end_index, "collocatemarker")))
+        .collect();
+    spans.sort_by_key(|s| s.0);
+    let chars : Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    for (start, end, name) in spans {
+        if start < i || end > chars.len() || start > end {
+            continue;
+        }
+        out.push_str(&escape(&chars[i..start].iter().collect::<String>()));
+        out.push_str(&command(name, &escape(&chars[start..end].iter().collect::<String>())));
+        i = end;
+    }
+    out.push_str(&escape(&chars[i..].iter().collect::<String>()));
+    out
+}
+
+/// Whether a transcription is in IPA, which is assumed for transcriptions
+/// without a scheme
+fn is_ipa(transcription : &Transcription) -> bool {
+    match &transcription.scheme {
+        Some(scheme) => scheme.to_lowercase().contains("ipa"),
+        None => true,
+    }
+}
+
+/// Write a resource as LaTeX
+pub fn write_latex<W : Write>(mut output : W, resource : &LexicographicResource, options : &LatexOptions) -> std::io::Result<ConversionReport> {
+    let mut report = ConversionReport::new();
+    if options.standalone {
+        writeln!(output, "{}", options.style.document_class)?;
+        for line in options.style.preamble.iter() {
+            writeln!(output, "{}", line)?;
+        }
+    }
+    for (name, definition) in options.style.macros.iter() {
+        writeln!(output, "\\providecommand{{\\dmlex{}}}[1]{{{}}}", name, definition)?;
+    }
+    if options.standalone {
+        writeln!(output, "\\begin{{document}}")?;
+        if let Some(title) = &resource.title {
+            writeln!(output, "\\title{{{}}}\n\\date{{}}\n\\maketitle", escape(title))?;
+        }
+    }
+    let mut entries : Vec<&Entry> = resource.entries.iter().collect();
+    let collation = Collation::for_resource(resource);
+    entries.sort_by_cached_key(|e| (options.collation_key(&collation, &e.headword), e.homograph_number));
+    let mut section = None;
+    let mut skipped_schemes = BTreeSet::new();
+    for entry in entries {
+        let initial = options.initial(&collation, &entry.headword);
+        if section.as_ref() != Some(&initial) {
+            writeln!(output, "\n{}\n", command("section", &escape(&initial)))?;
+            section = Some(initial);
+        }
+        writeln!(output, "{}", command("entry", &entry_body(entry, &mut skipped_schemes)))?;
+    }
+    for scheme in skipped_schemes {
+        report.warn("lexicographicResource", format!("Transcriptions in the scheme {} are not printed", scheme));
+    }
+    if options.standalone {
+        writeln!(output, "\\end{{document}}")?;
+    }
+    Ok(report)
+}
+
+fn entry_body(entry : &Entry, skipped_schemes : &mut BTreeSet<String>) -> String {
+    let mut parts = Vec::new();
+    let mut headword = command("headword", &escape(&entry.headword));
+    if let Some(number) = entry.homograph_number {
+        headword.push_str(&command("homograph", &number.to_string()));
+    }
+    parts.push(headword);
+    let pronunciations = pronunciations(&entry.pronunciations, skipped_schemes);
+    if !pronunciations.is_empty() {
+        parts.push(pronunciations);
+    }
+    for pos in entry.parts_of_speech.iter() {
+        parts.push(command("pos", &escape(pos)));
+    }
+    for label in entry.labels.iter() {
+        parts.push(command("label", &escape(label)));
+    }
+    if !entry.inflected_forms.is_empty() {
+        let forms : Vec<String> = entry.inflected_forms.iter().map(|form| {
+            match &form.tag {
+                Some(tag) => format!("{} {}", command("label", &escape(tag)), command("inflectedform", &escape(&form.text))),
+                None => command("inflectedform", &escape(&form.text)),
+            }
+        }).collect();
+        parts.push(format!("({})", forms.join(", ")));
+    }
+    let numbered = entry.senses.len() > 1;
+    for (i, sense) in entry.senses.iter().enumerate() {
+        let mut sense_parts = Vec::new();
+        if numbered {
+            sense_parts.push(command("sense", &(i + 1).to_string()));
+        }
+        if let Some(indicator) = &sense.indicator {
+            sense_parts.push(command("indicator", &escape(indicator)));
+        }
+        for label in sense.labels.iter() {
+            sense_parts.push(command("label", &escape(label)));
+        }
+        let definitions : Vec<String> = sense.definitions.iter()
+            .map(|d| command("definition", &with_markers(&d.text, &d.headword_markers, &d.collocate_markers)))
+            .collect();
+        if !definitions.is_empty() {
+            sense_parts.push(definitions.join("; "));
+        }
+        sense_parts.extend(translations(&sense.headword_translations, skipped_schemes));
+        for example in sense.examples.iter() {
+            let mut text = command("example", &with_markers(&example.text, &example.headword_markers, &example.collocate_markers));
+            for translation in example.example_translations.iter() {
+                text.push(' ');
+                text.push_str(&command("exampletranslation",
+                    &with_markers(&translation.text, &translation.headword_markers, &translation.collocate_markers)));
+            }
+            sense_parts.push(text);
+        }
+        parts.push(sense_parts.join(" "));
+    }
+    for etymology in entry.etymologies.iter() {
+        let mut text = etymology.description.as_deref().map(escape).unwrap_or_default();
+        for etymon in etymology.etymons.iter() {
+            for unit in etymon.etymon_units.iter() {
+                if !text.is_empty() {
+                    text.push_str(", ");
+                }
+                text.push_str(&format!("{} {}{}", command("lang", &escape(&unit.lang_code.0)),
+                    if unit.reconstructed == Some(true) { "*" } else { "" }, command("example", &escape(&unit.text))));
+            }
+        }
+        if !text.is_empty() {
+            parts.push(command("etymology", &text));
+        }
+    }
+    parts.join(" ")
+}
+
+fn pronunciations(pronunciations : &[Pronunciation], skipped_schemes : &mut BTreeSet<String>) -> String {
+    let mut transcriptions = Vec::new();
+    for transcription in pronunciations.iter().flat_map(|p| p.transcriptions.iter()) {
+        if is_ipa(transcription) {
+            transcriptions.push(command("pronunciation", &escape(&transcription.text)));
+        } else if let Some(scheme) = &transcription.scheme {
+            skipped_schemes.insert(scheme.clone());
+        }
+    }
+    transcriptions.join(", ")
+}
+
+/// The headword translations grouped by language
+fn translations(translations : &[HeadwordTranslation], skipped_schemes : &mut BTreeSet<String>) -> Vec<String> {
+    let mut langs : Vec<Option<&str>> = Vec::new();
+    for translation in translations.iter() {
+        let lang = translation.lang_code.as_ref().map(|l| l.0.as_str());
+        if !langs.contains(&lang) {
+            langs.push(lang);
+        }
+    }
+    langs.into_iter().map(|lang| {
+        let texts : Vec<String> = translations.iter()
+            .filter(|t| t.lang_code.as_ref().map(|l| l.0.as_str()) == lang)
+            .map(|t| {
+                let mut text = command("translation", &escape(&t.text));
+                let pronunciations = pronunciations(&t.pronunciations, skipped_schemes);
+                if !pronunciations.is_empty() {
+                    text.push(' ');
+                    text.push_str(&pronunciations);
+                }
+                for pos in t.parts_of_speech.iter() {
+                    text.push(' ');
+                    text.push_str(&command("pos", &escape(pos)));
+                }
+                text
+            }).collect();
+        match lang {
+            Some(lang) => format!("{} {}", command("lang", &escape(lang)), texts.join(", ")),
+            None => texts.join(", "),
+        }
+    }).collect()
+}
+
+/// LaTeX as a format of the registry
+#[derive(Debug, Clone, Default)]
+pub struct LatexFormat {
+    pub options : LatexOptions,
+}
+
+impl ResourceWriter for LatexFormat {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> Result<(), WriteError> {
+        self.write_resource_with_report(output, resource, options)?;
+        Ok(())
+    }
+
+    fn write_resource_with_report(&self, output : &mut dyn Write, resource : &LexicographicResource, _options : &WriteOptions) -> Result<ConversionReport, WriteError> {
+        Ok(write_latex(output, resource, &self.options)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_entry, Format};
+    use std::fs::File;
+
+    fn latex(resource : &LexicographicResource, options : &LatexOptions) -> String {
+        let mut out = Vec::new();
+        write_latex(&mut out, resource, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn entry(headword : &str, homograph_number : Option<u32>) -> Entry {
+        Entry { headword: headword.to_string(), homograph_number, ..Entry::default() }
+    }
+
+    #[test]
+    fn test_entry() {
+        let resource = parse(File::open("examples/0.xml").unwrap(), &Format::XML, &None).unwrap();
+        let text = latex(&resource, &LatexOptions::default());
+        assert!(text.starts_with("\\documentclass[twocolumn]{article}\n\\usepackage{fontspec}\n\\providecommand{\\dmlexsection}[1]{\\section*{#1}}\n"));
+        assert!(text.contains("\\dmlexsection{A}"));
+        assert!(text.contains("\\dmlexentry{\\dmlexheadword{abandon} \\dmlexpos{verb} \\dmlexsense{1} \\dmlexdefinition{to suddenly leave a place or a person} \\dmlexexample{I'm sorry I abandoned you like that.} \\dmlexexample{Abandon ship!} \\dmlexsense{2} \\dmlexlabel{mostly-passive}"));
+        assert!(text.ends_with("\\end{document}\n"));
+    }
+
+    #[test]
+    fn test_markers_and_translations() {
+        let entry = parse_entry(File::open("examples/22.xml").unwrap(), &Format::XML, &None).unwrap();
+        let resource = LexicographicResource {
+            lang_code: LangCode("en".to_string()),
+            entries: vec![entry],
+            ..LexicographicResource::default()
+        };
+        let text = latex(&resource, &LatexOptions::default().with_standalone(false));
+        assert!(!text.contains("\\begin{document}"));
+        assert_eq!(text.lines().last().unwrap(),
+            "\\dmlexentry{\\dmlexheadword{autopsy} \\dmlexlang{cs} \\dmlextranslation{pitva} \\dmlexexample{The coroner \\dmlexcollocatemarker{performed} an \\dmlexheadwordmarker{autopsy}.} \\dmlexexampletranslation{Koroner \\dmlexcollocatemarker{provedl} \\dmlexheadwordmarker{pitvu}.}}");
+    }
+
+    #[test]
+    fn test_collation_and_homographs() {
+        let resource = LexicographicResource {
+            lang_code: LangCode("cs".to_string()),
+            entries: vec![entry("chata", None), entry("cena", None), entry("hrad", None), entry("bod", Some(2)), entry("bod", Some(1))],
+            ..LexicographicResource::default()
+        };
+        let options = LatexOptions::default().with_standalone(false).with_alphabet("a b c d e f g h ch i");
+        let text = latex(&resource, &options);
+        let lines : Vec<&str> = text.lines().filter(|l| l.starts_with("\\dmlexentry") || l.starts_with("\\dmlexsection")).collect();
+        assert_eq!(lines, vec![
+            "\\dmlexsection{B}",
+            "\\dmlexentry{\\dmlexheadword{bod}\\dmlexhomograph{1}}",
+            "\\dmlexentry{\\dmlexheadword{bod}\\dmlexhomograph{2}}",
+            "\\dmlexsection{C}",
+            "\\dmlexentry{\\dmlexheadword{cena}}",
+            "\\dmlexsection{H}",
+            "\\dmlexentry{\\dmlexheadword{hrad}}",
+            "\\dmlexsection{Ch}",
+            "\\dmlexentry{\\dmlexheadword{chata}}",
+        ]);
+    }
+
+    #[test]
+    fn test_pronunciations_and_style() {
+        let mut entry = entry("R&D", None);
+        entry.pronunciations.push(Pronunciation {
+            transcriptions: vec![
+                Transcription { text: "ˌɑːr ən ˈdiː".to_string(), scheme: Some("en-fonipa".to_string()) },
+                Transcription { text: "ar en dee".to_string(), scheme: Some("en-x-respell".to_string()) },
+            ],
+            ..Pronunciation::default()
+        });
+        let resource = LexicographicResource { entries: vec![entry], ..LexicographicResource::default() };
+        let style = LatexStyle::default().with_macro("pronunciation", "[#1]").with_macro("headword", "\\textsf{#1}");
+        let mut out = Vec::new();
+        let report = write_latex(&mut out, &resource, &LatexOptions::default().with_style(style)).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("\\providecommand{\\dmlexpronunciation}[1]{[#1]}"));
+        assert!(text.contains("\\providecommand{\\dmlexheadword}[1]{\\textsf{#1}}"));
+        assert!(text.contains("\\dmlexentry{\\dmlexheadword{R\\&D} \\dmlexpronunciation{ˌɑːr ən ˈdiː}}"));
+        assert_eq!(report.warnings[0].message, "Transcriptions in the scheme en-x-respell are not printed");
+    }
+}
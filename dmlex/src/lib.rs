@@ -1,20 +1,44 @@
+pub mod apertium;
+mod article;
+pub mod collation;
+pub mod combine;
+pub mod detect;
+pub mod dictd;
+pub mod diff;
+pub mod homograph;
+pub mod html;
+pub mod hunspell;
+pub mod latex;
+pub mod lift;
+pub mod lmf;
+pub mod merge;
 pub mod model;
 pub mod rdf;
 mod serialization;
 pub mod read_xml;
+pub mod registry;
+pub mod report;
+pub mod reverse;
+pub mod sfm;
+pub mod shacl;
+pub mod split;
+pub mod stardict;
+pub mod subset;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod tabular;
+pub mod tei;
+pub mod turtle;
+pub mod wordnet;
 pub mod write_xml;
 mod validate;
+pub mod xml_tree;
 
 use crate::model::{LexicographicResource, Entry};
-use crate::rdf::ToRDF;
 use crate::write_xml::WriteXML;
 use sophia::graph::inmem::LightGraph;
 use sophia::graph::inmem::{OpsWrapper, GenericGraph};
-use sophia::iri::IriBox;
 use sophia::ns::Namespace;
-use sophia::prefix::PrefixBox;
-use sophia::serializer::TripleSerializer;
-use sophia::serializer::turtle::{TurtleSerializer, TurtleConfig};
 use sophia::term::factory::RcTermFactory;
 use sophia::triple::stream::TripleSource;
 use std::convert::Infallible;
@@ -25,6 +49,10 @@ use thiserror::Error;
 #[macro_use]
 extern crate lazy_static;
 
+pub use crate::detect::{detect_format, detect_root, RootKind};
+pub use crate::registry::{FormatRegistry, ReadOptions, ResourceReader, ResourceWriter, WriteOptions};
+pub use crate::turtle::TurtleOptions;
+
 type Graph = OpsWrapper<GenericGraph<u16, RcTermFactory>>;
 
 #[derive(Debug,Clone,PartialEq,Eq)]
@@ -32,6 +60,30 @@ pub enum Format {
     XML,
     RDF,
     JSON,
+    /// ISO 24613 Lexical Markup Framework XML
+    LMF,
+}
+
+impl Format {
+    /// The name of the format in the default `FormatRegistry`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::XML => "xml",
+            Format::RDF => "rdf",
+            Format::JSON => "json",
+            Format::LMF => "lmf",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Format> {
+        match name {
+            "xml" => Some(Format::XML),
+            "rdf" => Some(Format::RDF),
+            "json" => Some(Format::JSON),
+            "lmf" => Some(Format::LMF),
+            _ => None,
+        }
+    }
 }
 
 pub fn parse<R : Read>(input: R, format: &Format, default_namespace : &Option<String>) -> Result<LexicographicResource, ParseError> {
@@ -43,6 +95,10 @@ pub fn parse<R : Read>(input: R, format: &Format, default_namespace : &Option<St
             let mut buf_read = BufReader::new(input);
             let graph : LightGraph = sophia::parser::turtle::parse_bufread(&mut buf_read).collect_triples()
                 .map_err(|e| ParseError::TurtleError(format!("{}", e)))?;
+            let report = shacl::validate(&graph)?;
+            if !report.conforms() {
+                return Err(ParseError::ShapeViolations(report));
+            }
             if let Some(default_namespace) = &default_namespace {
                 let ns = Namespace::new(default_namespace)?;
                 Ok(crate::rdf::read_lexicographic_resource(&graph, &ns)?)
@@ -52,52 +108,34 @@ pub fn parse<R : Read>(input: R, format: &Format, default_namespace : &Option<St
         },
         Format::JSON => {
             Ok(serde_json::from_reader(input)?)
+        },
+        Format::LMF => {
+            Ok(lmf::read_lmf(input)?.0)
         }
     }
 }
 
-pub fn write<W : Write>(mut output: W, format: &Format, resource: &LexicographicResource,
+pub fn write<W : Write>(output: W, format: &Format, resource: &LexicographicResource,
     default_namespace : &Option<String>, ontolex : bool) -> Result<(), WriteError> {
+    write_with_options(output, format, resource, default_namespace, ontolex, &TurtleOptions::default())
+}
+
+pub fn write_with_options<W : Write>(mut output: W, format: &Format, resource: &LexicographicResource,
+    default_namespace : &Option<String>, ontolex : bool, turtle_options : &TurtleOptions) -> Result<(), WriteError> {
     match format {
         Format::XML => {
             let mut writer = xml::EmitterConfig::new().perform_indent(true).create_writer(&mut output);
             Ok(resource.write_xml(&mut writer)?)
         },
         Format::RDF => {
-            if let Some(ns) = &default_namespace {
-                let mut g = Graph::new();
-                let ns2 = Namespace::new(ns)?;
-                let dmlex = Namespace::new(crate::rdf::DMLEX).expect("DMLEX namespace is invalid");
-                resource.to_rdf(&mut g, &ns2, &dmlex, 0, ontolex, &None)?;
-                let mut serializer = TurtleSerializer::new_with_config(output,
-                    TurtleConfig::new().with_pretty(true)
-                    .with_own_prefix_map(
-                                vec![
-                                (
-                                    PrefixBox::new_unchecked("rdf".into()),
-                                    IriBox::new_unchecked("http://www.w3.org/1999/02/22-rdf-syntax-ns#".into()),
-                                ),
-                                (
-                                    PrefixBox::new_unchecked("rdfs".into()),
-                                    IriBox::new_unchecked("http://www.w3.org/2000/01/rdf-schema#".into()),
-                                ),
-                                (
-                                    PrefixBox::new_unchecked("xsd".into()),
-                                    IriBox::new_unchecked("http://www.w3.org/2001/XMLSchema#".into()),
-                                ),
-                                (
-                                    PrefixBox::new_unchecked("dmlex".into()),
-                                    IriBox::new_unchecked(crate::rdf::DMLEX.into()),
-                                ),
-                            ]));
-                serializer.serialize_graph(&g)?;
-                Ok(())
-            } else {
-                Err(WriteError::NoDefaultNamespace)
-            }
+            turtle::write_turtle(output, resource, default_namespace, ontolex, turtle_options)
         },
         Format::JSON => {
             Ok(serde_json::to_writer_pretty(output, resource)?)
+        },
+        Format::LMF => {
+            lmf::write_lmf(output, resource)?;
+            Ok(())
         }
     }
 }
@@ -112,6 +150,10 @@ pub fn parse_entry<R : Read>(input: R, format: &Format, default_namespace : &Opt
             let mut buf_read = BufReader::new(input);
             let graph : LightGraph = sophia::parser::turtle::parse_bufread(&mut buf_read).collect_triples()
                 .map_err(|e| ParseError::TurtleError(format!("{}", e)))?;
+            let report = shacl::validate(&graph)?;
+            if !report.conforms() {
+                return Err(ParseError::ShapeViolations(report));
+            }
             if let Some(default_namespace) = &default_namespace {
                 let ns = Namespace::new(default_namespace)?;
                 Ok(crate::rdf::read_entry(&graph, &ns)?)
@@ -121,52 +163,34 @@ pub fn parse_entry<R : Read>(input: R, format: &Format, default_namespace : &Opt
         },
         Format::JSON => {
             Ok(serde_json::from_reader(input)?)
+        },
+        Format::LMF => {
+            Ok(lmf::read_lmf_entry(input)?.0)
         }
     }
 }
 
-pub fn write_entry<W : Write>(mut output: W, format: &Format, resource: &Entry,
+pub fn write_entry<W : Write>(output: W, format: &Format, resource: &Entry,
     default_namespace : &Option<String>, ontolex : bool) -> Result<(), WriteError> {
+    write_entry_with_options(output, format, resource, default_namespace, ontolex, &TurtleOptions::default())
+}
+
+pub fn write_entry_with_options<W : Write>(mut output: W, format: &Format, resource: &Entry,
+    default_namespace : &Option<String>, ontolex : bool, turtle_options : &TurtleOptions) -> Result<(), WriteError> {
     match format {
         Format::XML => {
             let mut writer = xml::EmitterConfig::new().perform_indent(true).create_writer(&mut output);
             Ok(resource.write_xml(&mut writer)?)
         },
         Format::RDF => {
-            if let Some(ns) = &default_namespace {
-                let mut g = Graph::new();
-                let ns2 = Namespace::new(ns)?;
-                let dmlex = Namespace::new(crate::rdf::DMLEX).expect("DMLEX namespace is invalid");
-                resource.to_rdf(&mut g, &ns2, &dmlex, 0, ontolex, &None)?;
-                let mut serializer = TurtleSerializer::new_with_config(output,
-                    TurtleConfig::new().with_pretty(true)
-                    .with_own_prefix_map(
-                                vec![
-                                (
-                                    PrefixBox::new_unchecked("rdf".into()),
-                                    IriBox::new_unchecked("http://www.w3.org/1999/02/22-rdf-syntax-ns#".into()),
-                                ),
-                                (
-                                    PrefixBox::new_unchecked("rdfs".into()),
-                                    IriBox::new_unchecked("http://www.w3.org/2000/01/rdf-schema#".into()),
-                                ),
-                                (
-                                    PrefixBox::new_unchecked("xsd".into()),
-                                    IriBox::new_unchecked("http://www.w3.org/2001/XMLSchema#".into()),
-                                ),
-                                (
-                                    PrefixBox::new_unchecked("dmlex".into()),
-                                    IriBox::new_unchecked(crate::rdf::DMLEX.into()),
-                                ),
-                            ]));
-                serializer.serialize_graph(&g)?;
-                Ok(())
-            } else {
-                Err(WriteError::NoDefaultNamespace)
-            }
+            turtle::write_turtle(output, resource, default_namespace, ontolex, turtle_options)
         },
         Format::JSON => {
             Ok(serde_json::to_writer(output, resource)?)
+        },
+        Format::LMF => {
+            lmf::write_lmf_entry(output, resource)?;
+            Ok(())
         }
     }
 }
@@ -184,6 +208,27 @@ pub enum ParseError {
     InvalidNamespace(#[from] sophia::term::iri::error::InvalidIri),
     #[error("Turtle error: {0}")]
     TurtleError(String),
+    #[error("RDF does not conform to the DMLEX shapes: {0}")]
+    ShapeViolations(shacl::ValidationReport),
+    #[error("Unknown format: {0}")]
+    UnknownFormat(String),
+    #[error("TEI error: {0}")]
+    TeiError(#[from] tei::TeiError),
+    #[error("LMF error: {0}")]
+    LmfError(#[from] lmf::LmfError),
+    #[error("WordNet error: {0}")]
+    WordNetError(#[from] wordnet::WordNetError),
+    #[error("LIFT error: {0}")]
+    LiftError(#[from] lift::LiftError),
+    #[error("Table error: {0}")]
+    TabularError(#[from] tabular::TabularError),
+    #[error("SFM error: {0}")]
+    SfmError(#[from] sfm::SfmError),
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] sqlite::SqliteError),
+    #[error("This format does not support reading single entries")]
+    EntryNotSupported,
 }
 
 #[derive(Error, Debug)]
@@ -202,5 +247,28 @@ pub enum WriteError {
     TurtleError(#[from] sophia::triple::stream::StreamError<Infallible, std::io::Error>),
     #[error("XML Write error: {0}")]
     XmlWriteError(#[from] xml::writer::Error),
+    #[error("Invalid prefix: {0}")]
+    InvalidPrefix(#[from] sophia::prefix::InvalidPrefix),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Unknown format: {0}")]
+    UnknownFormat(String),
+    #[error("TEI error: {0}")]
+    TeiError(#[from] tei::TeiError),
+    #[error("LMF error: {0}")]
+    LmfError(#[from] lmf::LmfError),
+    #[error("WordNet error: {0}")]
+    WordNetError(#[from] wordnet::WordNetError),
+    #[error("LIFT error: {0}")]
+    LiftError(#[from] lift::LiftError),
+    #[error("Table error: {0}")]
+    TabularError(#[from] tabular::TabularError),
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] sqlite::SqliteError),
+    #[error("Apertium error: {0}")]
+    ApertiumError(#[from] apertium::ApertiumError),
+    #[error("This format does not support writing single entries")]
+    EntryNotSupported,
 }
 
@@ -0,0 +1,663 @@
+//! Import and export of SIL Lexicon Interchange FormaT (LIFT) XML
+//!
+//! The language of the first `<lexical-unit>` form is the language of the
+//! resource. Glosses and lexical unit forms in other writing systems become
+//! headword translations, definition forms in the language of the resource
+//! become definitions and those in other writing systems headword
+//! explanations, and example translations keep the language of their forms.
+//! The grammatical info of the senses becomes the part of speech of the
+//! entry, and the senses of an entry with several parts of speech are split
+//! into one entry per part of speech. Relations are written on the element
+//! of their first member.
+use crate::model::*;
+use crate::registry::{ReadOptions, ResourceReader, ResourceWriter, WriteOptions};
+use crate::report::{note_unmapped_tags, ConversionReport};
+use crate::xml_tree::{location, read_tree, unmapped, write_tree, Element, Node};
+use crate::{ParseError, WriteError};
+use std::io::{Read, Write};
+use thiserror::Error;
+
+pub static LIFT_VERSION : &str = "0.13";
+
+type Result<T> = std::result::Result<T, LiftError>;
+
+/// Read a LIFT document
+pub fn read_lift<R : Read>(input : R) -> Result<(LexicographicResource, ConversionReport)> {
+    let root = read_tree(input)?;
+    if root.name != "lift" {
+        return Err(LiftError::UnexpectedRoot(root.name));
+    }
+    let mut report = ConversionReport::new();
+    let lang = root.elements_named("entry")
+        .filter_map(|e| e.child("lexical-unit"))
+        .flat_map(|l| l.elements_named("form"))
+        .find_map(|f| f.attr("lang"));
+    let mut resource = LexicographicResource {
+        lang_code: match lang {
+            Some(lang) => LangCode(lang.to_string()),
+            None => {
+                report.warn(location(&root), "No language given for the lexical units, using 'und'");
+                LangCode("und".to_string())
+            }
+        },
+        ..LexicographicResource::default()
+    };
+    for child in root.elements() {
+        match child.name.as_str() {
+            "entry" => {
+                let entries = read_entry(child, &resource.lang_code.0, &mut resource.relations, &mut report)?;
+                resource.entries.extend(entries);
+            },
+            "header" => (),
+            _ => unmapped(&mut report, child, &root),
+        }
+    }
+    resource.translation_languages = translation_languages(&resource);
+    resource.relation_types = crate::lmf::relation_types(&resource.relations, &resource.entries);
+    Ok((resource, report))
+}
+
+/// Write a resource as a LIFT document
+pub fn write_lift<W : Write>(output : W, resource : &LexicographicResource) -> Result<ConversionReport> {
+    let mut report = ConversionReport::new();
+    let lang = resource.lang_code.0.as_str();
+    // Translations without a language are in the only translation language,
+    // if there is just one
+    let translation_lang = match resource.translation_languages.as_slice() {
+        [only] => only.as_str(),
+        _ => lang,
+    };
+    let mut entries : Vec<Element> = resource.entries.iter()
+        .map(|entry| write_entry(entry, lang, translation_lang, &mut report))
+        .collect();
+    write_relations(resource, &mut entries, &mut report);
+    let mut root = Element::new("lift")
+        .with_attr("version", LIFT_VERSION)
+        .with_attr("producer", "dmlex-converter");
+    for entry in entries {
+        root.push(entry);
+    }
+    let here = "lexicographicResource";
+    if resource.title.is_some() || resource.uri.is_some() {
+        report.warn(here, "The title and URI are not mapped");
+    }
+    if !resource.relation_types.is_empty() {
+        report.warn(here, format!("{} relation type(s) not mapped, they are regenerated from the relations when reading",
+            resource.relation_types.len()));
+    }
+    note_unmapped_tags(resource, here, &mut report);
+    write_tree(output, &root, None)?;
+    Ok(report)
+}
+
+/// The LIFT format for use in a `FormatRegistry`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiftFormat;
+
+impl ResourceReader for LiftFormat {
+    fn read_resource(&self, input : &mut dyn Read, options : &ReadOptions) -> std::result::Result<LexicographicResource, ParseError> {
+        Ok(self.read_resource_with_report(input, options)?.0)
+    }
+
+    fn read_resource_with_report(&self, input : &mut dyn Read, _options : &ReadOptions) -> std::result::Result<(LexicographicResource, ConversionReport), ParseError> {
+        Ok(read_lift(input)?)
+    }
+}
+
+impl ResourceWriter for LiftFormat {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> std::result::Result<(), WriteError> {
+        self.write_resource_with_report(output, resource, options)?;
+        Ok(())
+    }
+
+    fn write_resource_with_report(&self, output : &mut dyn Write, resource : &LexicographicResource, _options : &WriteOptions) -> std::result::Result<ConversionReport, WriteError> {
+        Ok(write_lift(output, resource)?)
+    }
+}
+
+/// The language and text of the `<form>` children of a multitext element
+fn forms(elem : &Element) -> Vec<(Option<&str>, String)> {
+    elem.elements_named("form")
+        .map(|f| (f.attr("lang"), f.child_text("text").unwrap_or_default()))
+        .collect()
+}
+
+fn form(lang : &str, text : &str) -> Element {
+    Element::new("form").with_attr("lang", lang)
+        .with_child(Element::new("text").with_text(text))
+}
+
+fn gloss(lang : &str, text : &str) -> Element {
+    Element::new("gloss").with_attr("lang", lang)
+        .with_child(Element::new("text").with_text(text))
+}
+
+/// Read an entry, which is split into one entry per part of speech if its
+/// senses have different ones. The first of them keeps the identifier,
+/// pronunciations, etymologies and senses without a part of speech.
+fn read_entry(elem : &Element, lang : &str, relations : &mut Vec<Relation>, report : &mut ConversionReport) -> Result<Vec<Entry>> {
+    let mut entry = Entry {
+        id: elem.attr("id").map(|s| s.to_string()),
+        ..Entry::default()
+    };
+    if let Some(order) = elem.attr("order") {
+        match order.parse() {
+            Ok(n) => entry.homograph_number = Some(n),
+            Err(_) => report.warn(location(elem), format!("Homograph number {} is not a number", order)),
+        }
+    }
+    let mut headword = None;
+    let mut variants = Vec::new();
+    // The senses of each part of speech, in the order of first use
+    let mut groups : Vec<(Option<String>, Vec<Sense>)> = Vec::new();
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "lexical-unit" if headword.is_none() => {
+                let (forms, others) : (Vec<_>, Vec<_>) = forms(child).into_iter()
+                    .partition(|(l, _)| l.is_none() || *l == Some(lang));
+                headword = forms.into_iter().next().map(|(_, text)| text);
+                variants = others.into_iter().map(|(form_lang, text)| HeadwordTranslation {
+                    text,
+                    lang_code: form_lang.map(|l| LangCode(l.to_string())),
+                    ..HeadwordTranslation::default()
+                }).collect();
+            },
+            "pronunciation" => entry.pronunciations.push(read_pronunciation(child, report)),
+            "sense" => {
+                let (sense, pos) = read_sense(child, lang, relations, report);
+                match groups.iter_mut().find(|(p, _)| pos.is_none() || p.is_none() || *p == pos) {
+                    Some((p, senses)) => {
+                        if p.is_none() {
+                            *p = pos;
+                        }
+                        senses.push(sense);
+                    },
+                    None => groups.push((pos, vec![sense])),
+                }
+            },
+            "etymology" => entry.etymologies.push(read_etymology(child, lang)),
+            "relation" => read_relation(child, &entry.id, relations, report),
+            _ => unmapped(report, child, elem),
+        }
+    }
+    entry.headword = headword.ok_or_else(|| LiftError::MissingLexicalUnit(location(elem)))?;
+    if !variants.is_empty() && groups.is_empty() {
+        report.warn(location(elem), "Lexical unit forms in other writing systems of an entry without senses not mapped");
+    }
+    for sense in groups.iter_mut().flat_map(|(_, senses)| senses.iter_mut()) {
+        sense.headword_translations.extend(variants.iter().cloned());
+    }
+    if groups.len() > 1 {
+        report.warn(location(elem), format!("Senses with the parts of speech {} are split into one entry each",
+            groups.iter().filter_map(|(p, _)| p.as_deref()).collect::<Vec<_>>().join(", ")));
+    }
+    let mut entries = Vec::new();
+    for (i, (pos, senses)) in groups.into_iter().enumerate() {
+        let split = if i == 0 {
+            entry.clone()
+        } else {
+            Entry {
+                id: entry.id.as_ref().map(|id| format!("{}-{}", id, i + 1)),
+                headword: entry.headword.clone(),
+                homograph_number: entry.homograph_number,
+                ..Entry::default()
+            }
+        };
+        entries.push(Entry { parts_of_speech: pos.into_iter().collect(), senses, ..split });
+    }
+    if entries.is_empty() {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn read_pronunciation(elem : &Element, report : &mut ConversionReport) -> Pronunciation {
+    let mut pronunciation = Pronunciation {
+        transcriptions: forms(elem).into_iter().map(|(lang, text)| Transcription {
+            text,
+            scheme: lang.map(|l| l.to_string()),
+        }).collect(),
+        ..Pronunciation::default()
+    };
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "form" => (),
+            "media" if pronunciation.sound_file.is_none() => {
+                pronunciation.sound_file = child.attr("href").map(|s| s.to_string());
+            },
+            _ => unmapped(report, child, elem),
+        }
+    }
+    pronunciation
+}
+
+/// Read a sense with the part of speech of its grammatical info, which DMLEX
+/// only has on the entry
+fn read_sense(elem : &Element, lang : &str, relations : &mut Vec<Relation>,
+    report : &mut ConversionReport) -> (Sense, Option<String>) {
+    let mut sense = Sense {
+        id: elem.attr("id").map(|s| s.to_string()),
+        ..Sense::default()
+    };
+    let mut pos = None;
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "grammatical-info" if pos.is_none() => pos = child.attr("value").map(|s| s.to_string()),
+            "gloss" => sense.headword_translations.push(HeadwordTranslation {
+                text: child.child_text("text").unwrap_or_default(),
+                lang_code: child.attr("lang").map(|l| LangCode(l.to_string())),
+                ..HeadwordTranslation::default()
+            }),
+            "definition" => {
+                for (form_lang, text) in forms(child) {
+                    if form_lang.is_none() || form_lang == Some(lang) {
+                        sense.definitions.push(Definition {
+                            text,
+                            ..Definition::default()
+                        });
+                    } else {
+                        sense.headword_explanations.push(HeadwordExplanation {
+                            text,
+                            lang_code: form_lang.map(|l| LangCode(l.to_string())),
+                            ..HeadwordExplanation::default()
+                        });
+                    }
+                }
+            },
+            "example" => sense.examples.push(read_example(child, lang, report)),
+            "relation" => read_relation(child, &sense.id, relations, report),
+            _ => unmapped(report, child, elem),
+        }
+    }
+    (sense, pos)
+}
+
+fn read_example(elem : &Element, lang : &str, report : &mut ConversionReport) -> Example {
+    let mut example = Example {
+        source_elaboration: elem.attr("source").map(|s| s.to_string()),
+        ..Example::default()
+    };
+    let mut text = None;
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "form" => {
+                let form_lang = child.attr("lang");
+                let form_text = child.child_text("text").unwrap_or_default();
+                if text.is_none() && (form_lang.is_none() || form_lang == Some(lang)) {
+                    text = Some(form_text);
+                } else {
+                    example.example_translations.push(ExampleTranslation {
+                        text: form_text,
+                        lang_code: form_lang.map(|l| LangCode(l.to_string())),
+                        ..ExampleTranslation::default()
+                    });
+                }
+            },
+            "translation" => {
+                example.example_translations.extend(forms(child).into_iter().map(|(form_lang, text)| ExampleTranslation {
+                    text,
+                    lang_code: form_lang.map(|l| LangCode(l.to_string())),
+                    ..ExampleTranslation::default()
+                }));
+            },
+            _ => unmapped(report, child, elem),
+        }
+    }
+    example.text = text.unwrap_or_default();
+    example
+}
+
+fn read_etymology(elem : &Element, lang : &str) -> Etymology {
+    let source = elem.attr("source");
+    let glosses = elem.elements_named("gloss")
+        .filter(|g| g.attr("lang").is_none() || g.attr("lang") == Some(lang))
+        .map(|g| g.child_text("text").unwrap_or_default())
+        .collect::<Vec<_>>();
+    Etymology {
+        description: None,
+        etymons: vec![Etymon {
+            _type: elem.attr("type").map(|s| s.to_string()),
+            etymon_units: forms(elem).into_iter().map(|(form_lang, text)| EtymonUnit {
+                lang_code: LangCode(form_lang.or(source).unwrap_or("und").to_string()),
+                text,
+                ..EtymonUnit::default()
+            }).collect(),
+            translation: if glosses.is_empty() { None } else { Some(glosses.join("; ")) },
+            ..Etymon::default()
+        }],
+    }
+}
+
+fn read_relation(elem : &Element, id : &Option<String>, relations : &mut Vec<Relation>, report : &mut ConversionReport) {
+    match (id, elem.attr("ref").filter(|r| !r.is_empty())) {
+        (Some(id), Some(target)) => relations.push(Relation {
+            _type: elem.attr("type").unwrap_or("related").to_string(),
+            members: vec![
+                Member { ref_: id.clone(), ..Member::default() },
+                Member { ref_: target.to_string(), ..Member::default() },
+            ],
+            ..Relation::default()
+        }),
+        (None, _) => report.warn(location(elem), "Relation from an element without an id not mapped"),
+        (_, None) => report.warn(location(elem), "Relation without a target not mapped"),
+    }
+}
+
+/// The languages of the translations, in order of first use
+fn translation_languages(resource : &LexicographicResource) -> Vec<String> {
+    let mut languages : Vec<String> = Vec::new();
+    for sense in resource.entries.iter().flat_map(|e| e.senses.iter()) {
+        let langs = sense.headword_translations.iter().filter_map(|t| t.lang_code.as_ref())
+            .chain(sense.examples.iter()
+                .flat_map(|e| e.example_translations.iter())
+                .filter_map(|t| t.lang_code.as_ref()));
+        for lang in langs {
+            if !languages.contains(&lang.0) {
+                languages.push(lang.0.clone());
+            }
+        }
+    }
+    languages
+}
+
+fn write_entry(entry : &Entry, lang : &str, translation_lang : &str, report : &mut ConversionReport) -> Element {
+    let here = format!("entry {}", entry.id.as_deref().unwrap_or(&entry.headword));
+    let mut elem = Element::new("entry")
+        .with_opt_attr("id", &entry.id)
+        .with_opt_attr("order", &entry.homograph_number.map(|n| n.to_string()))
+        .with_child(Element::new("lexical-unit").with_child(form(lang, &entry.headword)));
+    for pronunciation in entry.pronunciations.iter() {
+        let mut p = Element::new("pronunciation");
+        for transcription in pronunciation.transcriptions.iter() {
+            p.push(form(transcription.scheme.as_deref().unwrap_or(lang), &transcription.text));
+        }
+        if let Some(sound_file) = &pronunciation.sound_file {
+            p.push(Element::new("media").with_attr("href", sound_file));
+        }
+        if !pronunciation.labels.is_empty() {
+            report.warn(&here, "Pronunciation labels are not mapped");
+        }
+        elem.push(p);
+    }
+    if entry.parts_of_speech.len() > 1 {
+        report.warn(&here, "Only the first part of speech is mapped");
+    }
+    if entry.senses.is_empty() && !entry.parts_of_speech.is_empty() {
+        report.warn(&here, "Parts of speech of entries without senses are not mapped");
+    }
+    for sense in entry.senses.iter() {
+        elem.push(write_sense(sense, entry.parts_of_speech.first(), lang, translation_lang, &here, report));
+    }
+    for etymology in entry.etymologies.iter() {
+        if etymology.description.is_some() {
+            report.warn(&here, "Etymology descriptions are not mapped");
+        }
+        for etymon in etymology.etymons.iter() {
+            let source = etymon.etymon_units.first().map(|u| u.lang_code.0.clone());
+            let mut e = Element::new("etymology")
+                .with_attr("type", etymon._type.as_deref().unwrap_or("inherited"))
+                .with_attr("source", source.as_deref().unwrap_or("und"));
+            for unit in etymon.etymon_units.iter() {
+                e.push(form(&unit.lang_code.0, &unit.text));
+            }
+            if let Some(translation) = &etymon.translation {
+                e.push(gloss(lang, translation));
+            }
+            if etymon.when.is_some() || etymon.note.is_some() {
+                report.warn(&here, "Etymon dates and notes are not mapped");
+            }
+            elem.push(e);
+        }
+    }
+    if !entry.labels.is_empty() || !entry.inflected_forms.is_empty() || !entry.placeholder_markers.is_empty() {
+        report.warn(&here, "Entry labels, inflected forms and placeholder markers are not mapped");
+    }
+    elem
+}
+
+fn write_sense(sense : &Sense, pos : Option<&String>, lang : &str, default_lang : &str, entry_loc : &str, report : &mut ConversionReport) -> Element {
+    let here = format!("{}, sense {}", entry_loc, sense.id.as_deref().unwrap_or(""));
+    let mut elem = Element::new("sense").with_opt_attr("id", &sense.id);
+    if let Some(pos) = pos {
+        elem.push(Element::new("grammatical-info").with_attr("value", pos));
+    }
+    for translation in sense.headword_translations.iter() {
+        let translation_lang = translation.lang_code.as_ref().map(|l| l.0.as_str()).unwrap_or(default_lang);
+        elem.push(gloss(translation_lang, &translation.text));
+        if !translation.parts_of_speech.is_empty() || !translation.labels.is_empty()
+            || !translation.pronunciations.is_empty() || !translation.inflected_forms.is_empty()
+            || !translation.placeholder_markers.is_empty() {
+            report.warn(&here, "Only the text and language of headword translations are mapped");
+        }
+    }
+    if !sense.definitions.is_empty() || !sense.headword_explanations.is_empty() {
+        let mut definition = Element::new("definition");
+        for (i, d) in sense.definitions.iter().enumerate() {
+            if i == 0 {
+                definition.push(form(lang, &d.text));
+            } else {
+                report.warn(&here, "Only the first definition is mapped");
+            }
+            if d.definition_type.is_some() || !d.headword_markers.is_empty() || !d.collocate_markers.is_empty() {
+                report.warn(&here, "Definition types and markers are not mapped");
+            }
+        }
+        for explanation in sense.headword_explanations.iter() {
+            let explanation_lang = explanation.lang_code.as_ref().map(|l| l.0.as_str()).unwrap_or(default_lang);
+            definition.push(form(explanation_lang, &explanation.text));
+        }
+        elem.push(definition);
+    }
+    for example in sense.examples.iter() {
+        let mut e = Element::new("example")
+            .with_opt_attr("source", &example.source_elaboration)
+            .with_child(form(lang, &example.text));
+        for translation in example.example_translations.iter() {
+            let translation_lang = translation.lang_code.as_ref().map(|l| l.0.as_str()).unwrap_or(default_lang);
+            e.push(Element::new("translation").with_child(form(translation_lang, &translation.text)));
+        }
+        if example.source_identity.is_some() || !example.labels.is_empty() || example.sound_file.is_some()
+            || !example.headword_markers.is_empty() || !example.collocate_markers.is_empty() {
+            report.warn(&here, "Example source identities, labels, sound files and markers are not mapped");
+        }
+        elem.push(e);
+    }
+    if sense.indicator.is_some() || !sense.labels.is_empty() {
+        report.warn(&here, "Sense indicators and labels are not mapped");
+    }
+    elem
+}
+
+/// Add each relation to the entry or sense of its first member
+fn write_relations(resource : &LexicographicResource, entries : &mut [Element], report : &mut ConversionReport) {
+    for (index, relation) in resource.relations.iter().enumerate() {
+        let here = format!("relation {} ({})", index + 1, relation._type);
+        let source = match relation.members.first() {
+            Some(source) if relation.members.len() >= 2 => source,
+            _ => {
+                report.warn(&here, "Relations with fewer than two members are not mapped");
+                continue;
+            }
+        };
+        if relation.members.len() > 2 {
+            report.warn(&here, format!("Relation with {} members written as {} relations from the first member",
+                relation.members.len(), relation.members.len() - 1));
+        }
+        if relation.members.iter().any(|m| m.role.is_some() || m.obverse_listing_order.is_some()) {
+            report.warn(&here, "Member roles and obverse listing orders are not mapped");
+        }
+        if relation.description.is_some() {
+            report.warn(&here, "Relation descriptions are not mapped");
+        }
+        let target = match find_element(entries, &source.ref_) {
+            Some(target) => target,
+            None => {
+                report.warn(&here, format!("Member {} is not an entry or a sense", source.ref_));
+                continue;
+            }
+        };
+        for member in relation.members.iter().skip(1) {
+            target.push(Element::new("relation")
+                .with_attr("type", &relation._type)
+                .with_attr("ref", &member.ref_));
+        }
+    }
+}
+
+/// The `entry` or `sense` element with the given id
+fn find_element<'a>(entries : &'a mut [Element], id : &str) -> Option<&'a mut Element> {
+    for entry in entries.iter_mut() {
+        if entry.attr("id") == Some(id) {
+            return Some(entry);
+        }
+        let sense = entry.children.iter_mut().find_map(|child| match child {
+            Node::Element(e) if e.name == "sense" && e.attr("id") == Some(id) => Some(e),
+            _ => None,
+        });
+        if sense.is_some() {
+            return sense;
+        }
+    }
+    None
+}
+
+#[derive(Error, Debug)]
+pub enum LiftError {
+    #[error("XML error: {0}")]
+    Xml(#[from] xml::reader::Error),
+    #[error("XML write error: {0}")]
+    XmlWrite(#[from] xml::writer::Error),
+    #[error("Expected a LIFT document but found <{0}>")]
+    UnexpectedRoot(String),
+    #[error("Entry at {0} has no <lexical-unit>")]
+    MissingLexicalUnit(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_entry, Format};
+    use std::fs::File;
+
+    fn round_trip(fname : &str) -> (LexicographicResource, LexicographicResource, ConversionReport) {
+        let resource = parse(File::open(fname).unwrap(), &Format::XML, &None).unwrap();
+        let mut out = Vec::new();
+        let report = write_lift(&mut out, &resource).unwrap();
+        let (resource2, read_report) = read_lift(out.as_slice()).unwrap();
+        assert!(read_report.is_empty(), "{}", read_report);
+        (resource, resource2, report)
+    }
+
+    #[test]
+    fn test_round_trip_lift_bilingual() {
+        let resource = LexicographicResource {
+            lang_code: LangCode("ga".to_string()),
+            entries: vec![parse_entry(File::open("examples/11.xml").unwrap(), &Format::XML, &None).unwrap()],
+            translation_languages: vec!["en".to_string(), "de".to_string(), "cs".to_string()],
+            ..LexicographicResource::default()
+        };
+        let mut out = Vec::new();
+        let report = write_lift(&mut out, &resource).unwrap();
+        assert!(report.is_empty(), "{}", report);
+        let (resource2, read_report) = read_lift(out.as_slice()).unwrap();
+        assert!(read_report.is_empty(), "{}", read_report);
+        assert_eq!(resource, resource2);
+    }
+
+    #[test]
+    fn test_write_single_translation_language() {
+        let (_, resource2, _) = round_trip("examples/14.xml");
+        assert_eq!(resource2.entries[0].senses[0].headword_translations[0].lang_code, Some(LangCode("en".to_string())));
+    }
+
+    #[test]
+    fn test_round_trip_lift_relations() {
+        let (resource, resource2, report) = round_trip("examples/15.xml");
+        assert_eq!(resource.relations, resource2.relations);
+        assert!(report.warnings.iter().any(|w| w.message.starts_with("1 relation type(s) not mapped")));
+    }
+
+    #[test]
+    fn test_read_lift() {
+        let doc = r#"<?xml version="1.0" encoding="UTF-8"?>
+<lift version="0.13" producer="SIL.FLEx 9.1">
+  <header><ranges/></header>
+  <entry id="haus_1" order="1">
+    <lexical-unit><form lang="tpi"><text>haus</text></form></lexical-unit>
+    <trait name="morph-type" value="stem"/>
+    <pronunciation><form lang="tpi-fonipa"><text>haus</text></form><media href="haus.wav"/></pronunciation>
+    <sense id="haus_1_1">
+      <grammatical-info value="Noun"/>
+      <gloss lang="en"><text>house</text></gloss>
+      <gloss lang="de"><text>Haus</text></gloss>
+      <definition><form lang="en"><text>a building where people live</text></form></definition>
+      <example source="field notes">
+        <form lang="tpi"><text>Mi go long haus.</text></form>
+        <translation type="Free translation"><form lang="en"><text>I go home.</text></form></translation>
+      </example>
+      <relation type="synonym" ref="ples_1_1"/>
+    </sense>
+    <etymology type="borrowed" source="de"><form lang="de"><text>Haus</text></form><gloss lang="tpi"><text>haus</text></gloss></etymology>
+  </entry>
+  <entry id="ples_1">
+    <lexical-unit><form lang="tpi"><text>ples</text></form></lexical-unit>
+    <sense id="ples_1_1"><gloss lang="en"><text>place</text></gloss></sense>
+  </entry>
+</lift>"#;
+        let (resource, report) = read_lift(doc.as_bytes()).unwrap();
+        assert_eq!(resource.lang_code.0, "tpi");
+        assert_eq!(resource.translation_languages, vec!["en", "de"]);
+        let entry = &resource.entries[0];
+        assert_eq!(entry.headword, "haus");
+        assert_eq!(entry.homograph_number, Some(1));
+        assert_eq!(entry.parts_of_speech, vec!["Noun"]);
+        assert_eq!(entry.pronunciations[0].transcriptions[0].scheme, Some("tpi-fonipa".to_string()));
+        assert_eq!(entry.pronunciations[0].sound_file, Some("haus.wav".to_string()));
+        let sense = &entry.senses[0];
+        assert_eq!(sense.headword_translations[1].lang_code, Some(LangCode("de".to_string())));
+        assert!(sense.definitions.is_empty());
+        assert_eq!(sense.headword_explanations[0].text, "a building where people live");
+        assert_eq!(sense.examples[0].text, "Mi go long haus.");
+        assert_eq!(sense.examples[0].source_elaboration, Some("field notes".to_string()));
+        assert_eq!(sense.examples[0].example_translations[0].text, "I go home.");
+        assert_eq!(entry.etymologies[0].etymons[0].etymon_units[0].lang_code.0, "de");
+        assert_eq!(entry.etymologies[0].etymons[0].translation, Some("haus".to_string()));
+        assert_eq!(resource.relations[0].members[1].ref_, "ples_1_1");
+        let messages : Vec<&str> = report.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(messages, vec!["<trait> in <entry> not mapped"]);
+        assert_eq!(report.warnings[0].location, "line 6");
+    }
+
+    #[test]
+    fn test_sense_parts_of_speech() {
+        let doc = r#"<lift version="0.13">
+  <entry id="run_1" order="1">
+    <lexical-unit><form lang="en"><text>run</text></form><form lang="en-Shaw"><text>𐑮𐑳𐑯</text></form></lexical-unit>
+    <sense id="run_1_1"><grammatical-info value="Noun"/><definition><form lang="en"><text>an act of running</text></form></definition></sense>
+    <sense id="run_1_2"><grammatical-info value="Verb"/><definition><form lang="en"><text>to move fast</text></form></definition></sense>
+    <sense id="run_1_3"><definition><form lang="en"><text>a series</text></form></definition></sense>
+  </entry>
+</lift>"#;
+        let (resource, report) = read_lift(doc.as_bytes()).unwrap();
+        assert_eq!(report.to_string(), "line 2: Senses with the parts of speech Noun, Verb are split into one entry each\n");
+        assert_eq!(resource.entries.len(), 2);
+        let (noun, verb) = (&resource.entries[0], &resource.entries[1]);
+        assert_eq!((noun.id.as_deref(), noun.parts_of_speech.as_slice()), (Some("run_1"), &["Noun".to_string()][..]));
+        assert_eq!((verb.id.as_deref(), verb.parts_of_speech.as_slice()), (Some("run_1-2"), &["Verb".to_string()][..]));
+        assert_eq!(noun.senses.iter().map(|s| s.id.as_deref().unwrap()).collect::<Vec<_>>(), vec!["run_1_1", "run_1_3"]);
+        assert_eq!((verb.headword.as_str(), verb.homograph_number), ("run", Some(1)));
+        assert_eq!(verb.senses[0].headword_translations, vec![HeadwordTranslation {
+            text: "𐑮𐑳𐑯".to_string(),
+            lang_code: Some(LangCode("en-Shaw".to_string())),
+            ..HeadwordTranslation::default()
+        }]);
+        assert_eq!(resource.translation_languages, vec!["en-Shaw"]);
+        assert!(resource.validate().is_ok());
+        let mut out = Vec::new();
+        let write_report = write_lift(&mut out, &resource).unwrap();
+        assert!(write_report.is_empty(), "{}", write_report);
+        let (resource2, read_report) = read_lift(out.as_slice()).unwrap();
+        assert!(read_report.is_empty(), "{}", read_report);
+        assert_eq!(resource.entries, resource2.entries);
+    }
+}
@@ -0,0 +1,614 @@
+//! Import and export of ISO 24613 Lexical Markup Framework (LMF) XML
+//!
+//! The mapping follows the LMF core and morphology packages as serialized
+//! by the revision 16 DTD: `LexicalEntry`, `Lemma`, `WordForm`, `Sense`,
+//! `Definition` and `SenseExample` correspond to `Entry`, `InflectedForm`,
+//! `Sense`, `Definition` and `Example`, while binary relations are written as
+//! `SenseRelation` or `RelatedForm` depending on whether the first member is
+//! a sense or an entry. Properties are given as `<feat att=".." val=".."/>`.
+//! LMF has no declaration of relation types, so these are regenerated from
+//! the relations when reading. Anything else is listed in the
+//! `ConversionReport` returned with the result.
+use crate::model::*;
+use crate::report::{note_unmapped_tags, ConversionReport};
+use crate::xml_tree::{location, read_tree, unmapped, write_tree, Element};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use thiserror::Error;
+
+type Result<T> = std::result::Result<T, LmfError>;
+
+/// The relation type used for LMF relations without a `type` or `label`
+static DEFAULT_RELATION_TYPE : &str = "related";
+
+/// Read an LMF `<LexicalResource>`
+pub fn read_lmf<R : Read>(input : R) -> Result<(LexicographicResource, ConversionReport)> {
+    let root = read_tree(input)?;
+    if root.name != "LexicalResource" {
+        return Err(LmfError::UnexpectedRoot(root.name));
+    }
+    let mut report = ConversionReport::new();
+    let mut resource = LexicographicResource::default();
+    let mut lang = None;
+    for child in root.elements() {
+        match child.name.as_str() {
+            "GlobalInformation" => {
+                for (att, val) in feats(child) {
+                    match att {
+                        "label" | "title" => resource.title = Some(val.to_string()),
+                        "uri" => resource.uri = Some(val.to_string()),
+                        "languageCoding" => (),
+                        _ => unmapped_feat(&mut report, child, att),
+                    }
+                }
+            },
+            "Lexicon" => {
+                for (att, val) in feats(child) {
+                    match att {
+                        "language" | "languageIdentifier" if lang.is_none() => lang = Some(val.to_string()),
+                        "label" if resource.title.is_none() => resource.title = Some(val.to_string()),
+                        _ => unmapped_feat(&mut report, child, att),
+                    }
+                }
+                for elem in child.elements() {
+                    match elem.name.as_str() {
+                        "LexicalEntry" => resource.entries.push(read_entry(elem, &mut resource.relations, &mut report)?),
+                        "feat" => (),
+                        _ => unmapped(&mut report, elem, child),
+                    }
+                }
+            },
+            "feat" => (),
+            _ => unmapped(&mut report, child, &root),
+        }
+    }
+    resource.lang_code = match lang {
+        Some(lang) => LangCode(lang),
+        None => {
+            report.warn(location(&root), "No language given for the lexicon, using 'und'");
+            LangCode("und".to_string())
+        }
+    };
+    resource.relation_types = relation_types(&resource.relations, &resource.entries);
+    Ok((resource, report))
+}
+
+/// Read a single LMF `<LexicalEntry>`. Relations to other entries cannot be
+/// represented and are reported.
+pub fn read_lmf_entry<R : Read>(input : R) -> Result<(Entry, ConversionReport)> {
+    let root = read_tree(input)?;
+    if root.name != "LexicalEntry" {
+        return Err(LmfError::UnexpectedRoot(root.name));
+    }
+    let mut report = ConversionReport::new();
+    let mut relations = Vec::new();
+    let entry = read_entry(&root, &mut relations, &mut report)?;
+    if !relations.is_empty() {
+        report.warn(location(&root), format!("{} relation(s) not mapped", relations.len()));
+    }
+    Ok((entry, report))
+}
+
+/// Write a resource as an LMF `<LexicalResource>`
+pub fn write_lmf<W : Write>(output : W, resource : &LexicographicResource) -> Result<ConversionReport> {
+    let mut report = ConversionReport::new();
+    let mut global = Element::new("GlobalInformation")
+        .with_child(feat("languageCoding", "ISO 639"));
+    if let Some(title) = &resource.title {
+        global.push(feat("label", title));
+    }
+    if let Some(uri) = &resource.uri {
+        global.push(feat("uri", uri));
+    }
+    let mut lexicon = Element::new("Lexicon")
+        .with_child(feat("language", &resource.lang_code.0));
+    let mut entries : Vec<Element> = resource.entries.iter()
+        .map(|entry| write_entry(entry, &mut report))
+        .collect();
+    write_relations(resource, &mut entries, &mut report);
+    for entry in entries {
+        lexicon.push(entry);
+    }
+    let root = Element::new("LexicalResource")
+        .with_attr("dtdVersion", "16")
+        .with_child(global)
+        .with_child(lexicon);
+    let here = "lexicographicResource";
+    if !resource.translation_languages.is_empty() {
+        report.warn(here, "Translation languages are not mapped");
+    }
+    if !resource.relation_types.is_empty() {
+        report.warn(here, format!("{} relation type(s) not mapped, they are regenerated from the relations when reading",
+            resource.relation_types.len()));
+    }
+    note_unmapped_tags(resource, here, &mut report);
+    write_tree(output, &root, None)?;
+    Ok(report)
+}
+
+/// Write a single entry as an LMF `<LexicalEntry>`
+pub fn write_lmf_entry<W : Write>(output : W, entry : &Entry) -> Result<ConversionReport> {
+    let mut report = ConversionReport::new();
+    let elem = write_entry(entry, &mut report);
+    write_tree(output, &elem, None)?;
+    Ok(report)
+}
+
+fn unmapped_feat(report : &mut ConversionReport, elem : &Element, att : &str) {
+    report.warn(location(elem), format!("feat {} of <{}> not mapped", att, elem.name));
+}
+
+fn feat(att : &str, val : &str) -> Element {
+    Element::new("feat").with_attr("att", att).with_attr("val", val)
+}
+
+/// The `att`/`val` pairs of the `<feat>` children of an element, including
+/// those of its `FormRepresentation` and `TextRepresentation` children
+fn feats(elem : &Element) -> Vec<(&str, &str)> {
+    let mut result = Vec::new();
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "feat" => {
+                if let (Some(att), Some(val)) = (child.attr("att"), child.attr("val")) {
+                    result.push((att, val));
+                }
+            },
+            "FormRepresentation" | "TextRepresentation" => result.extend(feats(child)),
+            _ => (),
+        }
+    }
+    result
+}
+
+fn first_feat(elem : &Element, att : &str) -> Option<String> {
+    feats(elem).into_iter().find(|(a, _)| *a == att).map(|(_, v)| v.to_string())
+}
+
+fn is_feat(elem : &Element) -> bool {
+    matches!(elem.name.as_str(), "feat" | "FormRepresentation" | "TextRepresentation")
+}
+
+fn read_entry(elem : &Element, relations : &mut Vec<Relation>, report : &mut ConversionReport) -> Result<Entry> {
+    let mut entry = Entry {
+        id: elem.attr("id").map(|s| s.to_string()),
+        ..Entry::default()
+    };
+    for (att, val) in feats(elem) {
+        match att {
+            "partOfSpeech" => entry.parts_of_speech.push(val.to_string()),
+            "usage" => entry.labels.push(val.to_string()),
+            "homographNumber" => match val.parse() {
+                Ok(n) => entry.homograph_number = Some(n),
+                Err(_) => report.warn(location(elem), format!("Homograph number {} is not a number", val)),
+            },
+            _ => unmapped_feat(report, elem, att),
+        }
+    }
+    let mut headword = None;
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "Lemma" if headword.is_none() => {
+                headword = first_feat(child, "writtenForm");
+                entry.pronunciations.extend(read_prons(child));
+            },
+            "WordForm" => {
+                let tag = feats(child).into_iter()
+                    .filter(|(att, _)| !matches!(*att, "writtenForm" | "phoneticForm" | "usage"))
+                    .map(|(_, val)| val)
+                    .collect::<Vec<_>>();
+                entry.inflected_forms.push(InflectedForm {
+                    text: first_feat(child, "writtenForm").unwrap_or_default(),
+                    tag: if tag.is_empty() { None } else { Some(tag.join(" ")) },
+                    labels: usages(child),
+                    pronunciations: read_prons(child),
+                });
+            },
+            "RelatedForm" => match (&entry.id, child.attr("targets")) {
+                (Some(id), Some(targets)) => relations.extend(read_relations(id, targets, child)),
+                _ => unmapped(report, child, elem),
+            },
+            "Sense" => entry.senses.push(read_sense(child, relations, report)),
+            _ if is_feat(child) => (),
+            _ => unmapped(report, child, elem),
+        }
+    }
+    entry.headword = headword.ok_or_else(|| LmfError::MissingLemma(location(elem)))?;
+    Ok(entry)
+}
+
+fn usages(elem : &Element) -> Vec<String> {
+    feats(elem).into_iter()
+        .filter(|(att, _)| *att == "usage")
+        .map(|(_, val)| val.to_string())
+        .collect()
+}
+
+fn read_prons(form : &Element) -> Vec<Pronunciation> {
+    feats(form).into_iter()
+        .filter(|(att, _)| *att == "phoneticForm")
+        .map(|(_, val)| Pronunciation {
+            transcriptions: vec![Transcription {
+                text: val.to_string(),
+                scheme: None,
+            }],
+            ..Pronunciation::default()
+        })
+        .collect()
+}
+
+/// Read a `SenseRelation` or `RelatedForm` from the element with the given
+/// id to each of its targets
+fn read_relations(id : &str, targets : &str, elem : &Element) -> Vec<Relation> {
+    let _type = first_feat(elem, "type")
+        .or_else(|| first_feat(elem, "label"))
+        .unwrap_or_else(|| DEFAULT_RELATION_TYPE.to_string());
+    targets.split_whitespace().map(|target| Relation {
+        _type: _type.clone(),
+        description: first_feat(elem, "description"),
+        members: vec![
+            Member {
+                ref_: id.to_string(),
+                role: first_feat(elem, "sourceRole"),
+                obverse_listing_order: None,
+            },
+            Member {
+                ref_: target.to_string(),
+                role: first_feat(elem, "targetRole"),
+                obverse_listing_order: None,
+            },
+        ],
+    }).collect()
+}
+
+fn read_sense(elem : &Element, relations : &mut Vec<Relation>, report : &mut ConversionReport) -> Sense {
+    let mut sense = Sense {
+        id: elem.attr("id").map(|s| s.to_string()),
+        ..Sense::default()
+    };
+    for (att, val) in feats(elem) {
+        match att {
+            "usage" => sense.labels.push(val.to_string()),
+            "indicator" => sense.indicator = Some(val.to_string()),
+            _ => unmapped_feat(report, elem, att),
+        }
+    }
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "Definition" => sense.definitions.push(Definition {
+                text: first_feat(child, "text").or_else(|| first_feat(child, "writtenForm")).unwrap_or_default(),
+                definition_type: first_feat(child, "definitionType"),
+                ..Definition::default()
+            }),
+            "SenseExample" => sense.examples.push(Example {
+                text: first_feat(child, "text").or_else(|| first_feat(child, "writtenForm")).unwrap_or_default(),
+                source_elaboration: first_feat(child, "source"),
+                labels: usages(child),
+                ..Example::default()
+            }),
+            "Equivalent" => sense.headword_translations.push(HeadwordTranslation {
+                text: first_feat(child, "writtenForm").unwrap_or_default(),
+                lang_code: first_feat(child, "language").map(LangCode),
+                ..HeadwordTranslation::default()
+            }),
+            "SenseRelation" => match (&sense.id, child.attr("targets")) {
+                (Some(id), Some(targets)) => relations.extend(read_relations(id, targets, child)),
+                _ => unmapped(report, child, elem),
+            },
+            _ if is_feat(child) => (),
+            _ => unmapped(report, child, elem),
+        }
+    }
+    sense
+}
+
+/// Declare a relation type for each type of relation used, with a member
+/// type for each role and kind of member
+pub(crate) fn relation_types(relations : &[Relation], entries : &[Entry]) -> Vec<RelationType> {
+    let entry_ids : HashSet<&str> = entries.iter().filter_map(|e| e.id.as_deref()).collect();
+    let mut relation_types : Vec<RelationType> = Vec::new();
+    for relation in relations.iter() {
+        let index = match relation_types.iter().position(|t| t._type == relation._type) {
+            Some(index) => index,
+            None => {
+                relation_types.push(RelationType {
+                    _type: relation._type.clone(),
+                    ..RelationType::default()
+                });
+                relation_types.len() - 1
+            }
+        };
+        for member in relation.members.iter() {
+            let _type = if entry_ids.contains(member.ref_.as_str()) {
+                MemberTypeType::Entry
+            } else {
+                MemberTypeType::Sense
+            };
+            let member_types = &mut relation_types[index].member_types;
+            if !member_types.iter().any(|m| m.role == member.role && m._type == _type) {
+                member_types.push(MemberType {
+                    role: member.role.clone(),
+                    _type,
+                    ..MemberType::default()
+                });
+            }
+        }
+    }
+    relation_types
+}
+
+fn write_entry(entry : &Entry, report : &mut ConversionReport) -> Element {
+    let here = format!("entry {}", entry.id.as_deref().unwrap_or(&entry.headword));
+    let mut elem = Element::new("LexicalEntry").with_opt_attr("id", &entry.id);
+    for pos in entry.parts_of_speech.iter() {
+        elem.push(feat("partOfSpeech", pos));
+    }
+    if let Some(n) = entry.homograph_number {
+        elem.push(feat("homographNumber", &n.to_string()));
+    }
+    for label in entry.labels.iter() {
+        elem.push(feat("usage", label));
+    }
+    let mut lemma = Element::new("Lemma").with_child(feat("writtenForm", &entry.headword));
+    write_prons(&mut lemma, &entry.pronunciations, &here, report);
+    elem.push(lemma);
+    for form in entry.inflected_forms.iter() {
+        let mut word_form = Element::new("WordForm").with_child(feat("writtenForm", &form.text));
+        if let Some(tag) = &form.tag {
+            word_form.push(feat("grammaticalTag", tag));
+        }
+        for label in form.labels.iter() {
+            word_form.push(feat("usage", label));
+        }
+        write_prons(&mut word_form, &form.pronunciations, &here, report);
+        elem.push(word_form);
+    }
+    for sense in entry.senses.iter() {
+        elem.push(write_sense(sense, &here, report));
+    }
+    if !entry.placeholder_markers.is_empty() {
+        report.warn(&here, "Placeholder markers are not mapped");
+    }
+    if !entry.etymologies.is_empty() {
+        report.warn(&here, "Etymologies are not mapped");
+    }
+    elem
+}
+
+fn write_prons(form : &mut Element, prons : &[Pronunciation], here : &str, report : &mut ConversionReport) {
+    for pron in prons.iter() {
+        for transcription in pron.transcriptions.iter() {
+            form.push(feat("phoneticForm", &transcription.text));
+            if transcription.scheme.is_some() {
+                report.warn(here, "Transcription schemes are not mapped");
+            }
+        }
+        if pron.sound_file.is_some() {
+            report.warn(here, "Pronunciation sound files are not mapped");
+        }
+        if !pron.labels.is_empty() {
+            report.warn(here, "Pronunciation labels are not mapped");
+        }
+    }
+}
+
+fn write_sense(sense : &Sense, entry_loc : &str, report : &mut ConversionReport) -> Element {
+    let here = format!("{}, sense {}", entry_loc, sense.id.as_deref().unwrap_or(""));
+    let mut elem = Element::new("Sense").with_opt_attr("id", &sense.id);
+    for label in sense.labels.iter() {
+        elem.push(feat("usage", label));
+    }
+    if let Some(indicator) = &sense.indicator {
+        elem.push(feat("indicator", indicator));
+    }
+    for definition in sense.definitions.iter() {
+        let mut def = Element::new("Definition").with_child(feat("text", &definition.text));
+        if let Some(definition_type) = &definition.definition_type {
+            def.push(feat("definitionType", definition_type));
+        }
+        if !definition.headword_markers.is_empty() || !definition.collocate_markers.is_empty() {
+            report.warn(&here, "Markers in definitions are not mapped");
+        }
+        elem.push(def);
+    }
+    for example in sense.examples.iter() {
+        let mut ex = Element::new("SenseExample").with_child(feat("text", &example.text));
+        if let Some(source) = &example.source_elaboration {
+            ex.push(feat("source", source));
+        }
+        for label in example.labels.iter() {
+            ex.push(feat("usage", label));
+        }
+        if !example.example_translations.is_empty() {
+            report.warn(&here, "Example translations are not mapped");
+        }
+        if example.source_identity.is_some() {
+            report.warn(&here, "Example source identities are not mapped");
+        }
+        if example.sound_file.is_some() {
+            report.warn(&here, "Example sound files are not mapped");
+        }
+        if !example.headword_markers.is_empty() || !example.collocate_markers.is_empty() {
+            report.warn(&here, "Markers in examples are not mapped");
+        }
+        elem.push(ex);
+    }
+    for translation in sense.headword_translations.iter() {
+        let mut equivalent = Element::new("Equivalent");
+        if let Some(lang_code) = &translation.lang_code {
+            equivalent.push(feat("language", &lang_code.0));
+        }
+        equivalent.push(feat("writtenForm", &translation.text));
+        if !translation.parts_of_speech.is_empty() || !translation.labels.is_empty()
+            || !translation.pronunciations.is_empty() || !translation.inflected_forms.is_empty()
+            || !translation.placeholder_markers.is_empty() {
+            report.warn(&here, "Only the text and language of headword translations are mapped");
+        }
+        elem.push(equivalent);
+    }
+    if !sense.headword_explanations.is_empty() {
+        report.warn(&here, "Headword explanations are not mapped");
+    }
+    elem
+}
+
+/// Add each relation to the element of its first member, as a
+/// `SenseRelation` if that is a sense and a `RelatedForm` if it is an entry
+fn write_relations(resource : &LexicographicResource, entries : &mut [Element], report : &mut ConversionReport) {
+    for (index, relation) in resource.relations.iter().enumerate() {
+        let here = format!("relation {} ({})", index + 1, relation._type);
+        let source = match relation.members.first() {
+            Some(source) if relation.members.len() >= 2 => source,
+            _ => {
+                report.warn(&here, "Relations with fewer than two members are not mapped");
+                continue;
+            }
+        };
+        if relation.members.len() > 2 {
+            report.warn(&here, format!("Relation with {} members written as {} relations from the first member",
+                relation.members.len(), relation.members.len() - 1));
+        }
+        if relation.members.iter().any(|m| m.obverse_listing_order.is_some()) {
+            report.warn(&here, "Obverse listing orders are not mapped");
+        }
+        let target = match find_target(resource, entries, &source.ref_) {
+            Some(target) => target,
+            None => {
+                report.warn(&here, format!("Member {} is not an entry or a sense", source.ref_));
+                continue;
+            }
+        };
+        let name = if target.name == "Sense" { "SenseRelation" } else { "RelatedForm" };
+        for member in relation.members.iter().skip(1) {
+            let mut rel = Element::new(name)
+                .with_attr("targets", &member.ref_)
+                .with_child(feat("type", &relation._type));
+            if let Some(description) = &relation.description {
+                rel.push(feat("description", description));
+            }
+            if let Some(role) = &source.role {
+                rel.push(feat("sourceRole", role));
+            }
+            if let Some(role) = &member.role {
+                rel.push(feat("targetRole", role));
+            }
+            target.push(rel);
+        }
+    }
+}
+
+/// The `LexicalEntry` or `Sense` element with the given id
+fn find_target<'a>(resource : &LexicographicResource, entries : &'a mut [Element], id : &str) -> Option<&'a mut Element> {
+    let index = resource.entries.iter().position(|e| e.id.as_deref() == Some(id)
+        || e.senses.iter().any(|s| s.id.as_deref() == Some(id)))?;
+    let entry = &mut entries[index];
+    if entry.attr("id") == Some(id) {
+        return Some(entry);
+    }
+    entry.children.iter_mut().find_map(|child| match child {
+        crate::xml_tree::Node::Element(e) if e.name == "Sense" && e.attr("id") == Some(id) => Some(e),
+        _ => None,
+    })
+}
+
+#[derive(Error, Debug)]
+pub enum LmfError {
+    #[error("XML error: {0}")]
+    Xml(#[from] xml::reader::Error),
+    #[error("XML write error: {0}")]
+    XmlWrite(#[from] xml::writer::Error),
+    #[error("Expected an LMF document but found <{0}>")]
+    UnexpectedRoot(String),
+    #[error("Lexical entry at {0} has no <Lemma> with a writtenForm")]
+    MissingLemma(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    fn round_trip(fname : &str) -> (LexicographicResource, LexicographicResource, ConversionReport) {
+        let resource = parse(File::open(fname).unwrap(), &Format::XML, &None).unwrap();
+        let mut out = Vec::new();
+        let report = write_lmf(&mut out, &resource).unwrap();
+        let (resource2, read_report) = read_lmf(out.as_slice()).unwrap();
+        assert!(read_report.is_empty(), "{}", read_report);
+        (resource, resource2, report)
+    }
+
+    #[test]
+    fn test_round_trip_lmf_0() {
+        let (resource, resource2, report) = round_trip("examples/0.xml");
+        assert!(report.is_empty(), "{}", report);
+        assert_eq!(resource, resource2);
+    }
+
+    #[test]
+    fn test_round_trip_lmf_relations() {
+        for fname in ["examples/12.xml", "examples/16.xml", "examples/17.xml"] {
+            let (resource, resource2, _) = round_trip(fname);
+            assert_eq!(resource.entries, resource2.entries, "{}", fname);
+            assert_eq!(resource.relations, resource2.relations, "{}", fname);
+            assert_eq!(resource.relation_types.iter().map(|t| &t._type).collect::<Vec<_>>(),
+                resource2.relation_types.iter().map(|t| &t._type).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_write_nary_relation() {
+        let resource = parse(File::open("examples/14.xml").unwrap(), &Format::XML, &None).unwrap();
+        let mut out = Vec::new();
+        let report = write_lmf(&mut out, &resource).unwrap();
+        assert!(report.warnings.iter().any(|w| w.message == "Relation with 3 members written as 2 relations from the first member"));
+        let (resource2, _) = read_lmf(out.as_slice()).unwrap();
+        assert_eq!(resource2.relations.len(), 2);
+        assert!(resource2.relations.iter().all(|r| r.members[0].ref_ == "die-see-1"));
+    }
+
+    #[test]
+    fn test_read_lmf() {
+        let doc = r#"<?xml version="1.0" encoding="UTF-8"?>
+<LexicalResource dtdVersion="16">
+  <GlobalInformation><feat att="label" val="Sample"/></GlobalInformation>
+  <Lexicon>
+    <feat att="language" val="en"/>
+    <LexicalEntry id="cat-n">
+      <feat att="partOfSpeech" val="noun"/>
+      <Lemma><FormRepresentation><feat att="writtenForm" val="cat"/><feat att="phoneticForm" val="kæt"/></FormRepresentation></Lemma>
+      <WordForm><feat att="writtenForm" val="cats"/><feat att="grammaticalNumber" val="plural"/></WordForm>
+      <Sense id="cat-n-1">
+        <Definition><TextRepresentation><feat att="writtenForm" val="A small domesticated mammal."/></TextRepresentation></Definition>
+        <SenseExample><feat att="text" val="The cat sat on the mat."/></SenseExample>
+        <SenseRelation targets="puss-n-1"><feat att="label" val="synonym"/></SenseRelation>
+        <Context/>
+      </Sense>
+      <RelatedForm targets="kitten-n"><feat att="type" val="diminutive"/></RelatedForm>
+    </LexicalEntry>
+    <LexicalEntry id="kitten-n">
+      <Lemma><feat att="writtenForm" val="kitten"/></Lemma>
+    </LexicalEntry>
+  </Lexicon>
+</LexicalResource>"#;
+        let (resource, report) = read_lmf(doc.as_bytes()).unwrap();
+        assert_eq!(resource.title, Some("Sample".to_string()));
+        assert_eq!(resource.lang_code.0, "en");
+        let entry = &resource.entries[0];
+        assert_eq!(entry.headword, "cat");
+        assert_eq!(entry.parts_of_speech, vec!["noun"]);
+        assert_eq!(entry.pronunciations[0].transcriptions[0].text, "kæt");
+        assert_eq!(entry.inflected_forms[0].text, "cats");
+        assert_eq!(entry.inflected_forms[0].tag, Some("plural".to_string()));
+        assert_eq!(entry.senses[0].definitions[0].text, "A small domesticated mammal.");
+        assert_eq!(entry.senses[0].examples[0].text, "The cat sat on the mat.");
+        assert_eq!(resource.relations.len(), 2);
+        assert_eq!(resource.relations[0]._type, "synonym");
+        assert_eq!(resource.relations[0].members[0].ref_, "cat-n-1");
+        assert_eq!(resource.relations[1]._type, "diminutive");
+        assert_eq!(resource.relation_types[1].member_types.iter().map(|m| &m._type).collect::<Vec<_>>(),
+            vec![&MemberTypeType::Entry]);
+        let messages : Vec<&str> = report.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(messages, vec!["<Context> in <Sense> not mapped"]);
+        assert_eq!(report.warnings[0].location, "line 14");
+    }
+}
@@ -0,0 +1,447 @@
+//! Three-way merge of lexicographic resources, for example as a git merge
+//! driver
+//!
+//! The changes made on each side since the common base are combined at the
+//! level of entries, senses, definitions, examples, headword translations,
+//! tags and relations, which are matched as in [`crate::diff`]. A property
+//! or element changed on both sides in different ways is a conflict, as is
+//! an element changed on one side and removed on the other or added on both
+//! sides with different content. Conflicts are reported for each element and
+//! resolved by keeping our version, or the changed version if the other
+//! side removed the element.
+//!
+//! The command line tool works as a git merge driver with this configuration
+//! in `.git/config`
+//!
+//! ```text
+//! [merge "dmlex"]
+//!     name = DMLEX merge
+//!     driver = dmlex-converter --merge %O %A %B
+//! ```
+//!
+//! and a line such as `*.xml merge=dmlex` in `.gitattributes`.
+use crate::diff::{entry_label, pair, relation_label};
+use crate::model::*;
+use crate::validate::Validate;
+use std::fmt;
+use std::hash::Hash;
+
+/// Why an element could not be merged
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictKind {
+    /// A property was changed on both sides
+    Field(String),
+    /// An element without properties of its own was changed on both sides
+    Changed,
+    /// Changed by us and removed by them
+    RemovedByTheirs,
+    /// Removed by us and changed by them
+    RemovedByOurs,
+    /// Added on both sides with different content
+    Added,
+}
+
+/// A conflict in an element, such as `entry abandon-verb, sense abandon-verb-1`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub element: String,
+    pub kind: ConflictKind,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ConflictKind::Field(field) => write!(f, "{}: {} changed on both sides, keeping ours", self.element, field),
+            ConflictKind::Changed => write!(f, "{}: changed on both sides, keeping ours", self.element),
+            ConflictKind::RemovedByTheirs => write!(f, "{}: changed by us and removed by them, keeping ours", self.element),
+            ConflictKind::RemovedByOurs => write!(f, "{}: removed by us and changed by them, keeping theirs", self.element),
+            ConflictKind::Added => write!(f, "{}: added on both sides differently, keeping ours", self.element),
+        }
+    }
+}
+
+/// The merged resource and the conflicts that were resolved by choosing a
+/// side
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeResult {
+    pub resource: LexicographicResource,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeResult {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// An element of a list that is merged by matching its items
+trait Element : Clone + PartialEq {
+    type Key : Hash + Eq;
+
+    /// Whether the remaining items without identifiers are matched in order
+    const IN_ORDER : bool = false;
+
+    fn id(&self) -> Option<String> {
+        None
+    }
+
+    fn key(&self) -> Self::Key;
+
+    fn label(&self) -> String;
+
+    /// Merge an element that was changed differently on both sides
+    fn merge(_base : &Self, ours : &Self, _theirs : &Self, element : &str, conflicts : &mut Vec<Conflict>) -> Self {
+        conflicts.push(Conflict { element: element.to_string(), kind: ConflictKind::Changed });
+        ours.clone()
+    }
+}
+
+/// Merge a property, keeping ours on conflict
+fn merge_value<T : Clone + PartialEq>(base : &T, ours : &T, theirs : &T, element : &str, field : &str, conflicts : &mut Vec<Conflict>) -> T {
+    if ours == base || ours == theirs {
+        theirs.clone()
+    } else {
+        if theirs != base {
+            conflicts.push(Conflict { element: element.to_string(), kind: ConflictKind::Field(field.to_string()) });
+        }
+        ours.clone()
+    }
+}
+
+macro_rules! merge_fields {
+    ($merged:ident, $base:ident, $ours:ident, $theirs:ident, $element:expr, $conflicts:ident; $($field:ident : $name:expr),*) => {
+        $($merged.$field = merge_value(&$base.$field, &$ours.$field, &$theirs.$field, $element, $name, $conflicts);)*
+    };
+}
+
+fn merge_element<T : Element>(base : &T, ours : &T, theirs : &T, element : &str, conflicts : &mut Vec<Conflict>) -> T {
+    if ours == theirs || base == theirs {
+        ours.clone()
+    } else if base == ours {
+        theirs.clone()
+    } else {
+        T::merge(base, ours, theirs, element, conflicts)
+    }
+}
+
+/// The matching items of two lists, in both directions
+fn matching<T : Element>(a : &[&T], b : &[&T]) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+    let mut a_to_b = vec![None; a.len()];
+    let mut b_to_a = vec![None; b.len()];
+    for (i, j) in pair(a, b, |x| x.id(), |x| x.key(), T::IN_ORDER) {
+        if let (Some(i), Some(j)) = (i, j) {
+            a_to_b[i] = Some(j);
+            b_to_a[j] = Some(i);
+        }
+    }
+    (a_to_b, b_to_a)
+}
+
+/// Merge the lists of a parent element. The result follows our order, with
+/// the items that only they have placed after the item before them in their
+/// list.
+fn merge_list<T : Element>(base : &[T], ours : &[T], theirs : &[T], parent : &str, conflicts : &mut Vec<Conflict>) -> Vec<T> {
+    let path = |item : &T| if parent.is_empty() { item.label() } else { format!("{}, {}", parent, item.label()) };
+    let (base, ours, theirs) : (Vec<&T>, Vec<&T>, Vec<&T>) = (base.iter().collect(), ours.iter().collect(), theirs.iter().collect());
+    let (_, ours_base) = matching(&base, &ours);
+    let (base_theirs, theirs_base) = matching(&base, &theirs);
+    let added_ours : Vec<usize> = (0..ours.len()).filter(|&j| ours_base[j].is_none()).collect();
+    let added_theirs : Vec<usize> = (0..theirs.len()).filter(|&k| theirs_base[k].is_none()).collect();
+    let (added_match, _) = matching(&added_ours.iter().map(|&j| ours[j]).collect::<Vec<_>>(),
+        &added_theirs.iter().map(|&k| theirs[k]).collect::<Vec<_>>());
+    let mut theirs_used = vec![false; theirs.len()];
+    let mut merged : Vec<(T, Option<usize>)> = Vec::new();
+    for (j, ours_item) in ours.iter().enumerate() {
+        let element = path(ours_item);
+        match ours_base[j] {
+            Some(b) => match base_theirs[b] {
+                Some(k) => {
+                    theirs_used[k] = true;
+                    merged.push((merge_element(base[b], ours_item, theirs[k], &element, conflicts), Some(k)));
+                },
+                None => if *ours_item != base[b] {
+                    conflicts.push(Conflict { element, kind: ConflictKind::RemovedByTheirs });
+                    merged.push(((*ours_item).clone(), None));
+                },
+            },
+            None => {
+                let added = added_ours.binary_search(&j).ok()
+                    .and_then(|a| added_match[a]).map(|m| added_theirs[m]);
+                if let Some(k) = added {
+                    theirs_used[k] = true;
+                    if *ours_item != theirs[k] {
+                        conflicts.push(Conflict { element, kind: ConflictKind::Added });
+                    }
+                }
+                merged.push(((*ours_item).clone(), added));
+            }
+        }
+    }
+    for (k, theirs_item) in theirs.iter().enumerate() {
+        if theirs_used[k] {
+            continue;
+        }
+        if let Some(b) = theirs_base[k] {
+            if *theirs_item == base[b] {
+                continue;
+            }
+            conflicts.push(Conflict { element: path(theirs_item), kind: ConflictKind::RemovedByOurs });
+        }
+        let at = merged.iter().rposition(|(_, x)| x.is_some_and(|x| x < k)).map_or(0, |p| p + 1);
+        merged.insert(at, ((*theirs_item).clone(), Some(k)));
+    }
+    merged.into_iter().map(|(item, _)| item).collect()
+}
+
+impl Element for Entry {
+    type Key = (String, Option<u32>, Vec<String>);
+
+    fn id(&self) -> Option<String> {
+        self.id.clone()
+    }
+
+    fn key(&self) -> Self::Key {
+        self.signature()
+    }
+
+    fn label(&self) -> String {
+        format!("entry {}", entry_label(self))
+    }
+
+    fn merge(base : &Entry, ours : &Entry, theirs : &Entry, element : &str, conflicts : &mut Vec<Conflict>) -> Entry {
+        let mut merged = ours.clone();
+        merge_fields!(merged, base, ours, theirs, element, conflicts;
+            id: "id", headword: "headword", placeholder_markers: "placeholderMarkers",
+            homograph_number: "homographNumber", parts_of_speech: "partsOfSpeech", labels: "labels",
+            pronunciations: "pronunciations", inflected_forms: "inflectedForms", etymologies: "etymologies");
+        merged.senses = merge_list(&base.senses, &ours.senses, &theirs.senses, element, conflicts);
+        merged
+    }
+}
+
+impl Element for Sense {
+    type Key = (Option<String>, Vec<String>);
+    const IN_ORDER : bool = true;
+
+    fn id(&self) -> Option<String> {
+        self.id.clone()
+    }
+
+    fn key(&self) -> Self::Key {
+        (self.indicator.clone(), self.definitions.iter().map(|d| d.text.clone()).collect())
+    }
+
+    fn label(&self) -> String {
+        match (&self.id, &self.indicator) {
+            (Some(id), _) => format!("sense {}", id),
+            (None, Some(indicator)) => format!("sense ({})", indicator),
+            (None, None) => "sense".to_string(),
+        }
+    }
+
+    fn merge(base : &Sense, ours : &Sense, theirs : &Sense, element : &str, conflicts : &mut Vec<Conflict>) -> Sense {
+        let mut merged = ours.clone();
+        merge_fields!(merged, base, ours, theirs, element, conflicts;
+            id: "id", indicator: "indicator", labels: "labels", headword_explanations: "headwordExplanations");
+        merged.definitions = merge_list(&base.definitions, &ours.definitions, &theirs.definitions, element, conflicts);
+        merged.examples = merge_list(&base.examples, &ours.examples, &theirs.examples, element, conflicts);
+        merged.headword_translations = merge_list(&base.headword_translations, &ours.headword_translations,
+            &theirs.headword_translations, element, conflicts);
+        merged
+    }
+}
+
+impl Element for Definition {
+    type Key = String;
+    const IN_ORDER : bool = true;
+
+    fn key(&self) -> String {
+        self.text.clone()
+    }
+
+    fn label(&self) -> String {
+        format!("definition {:?}", self.text)
+    }
+}
+
+impl Element for Example {
+    type Key = String;
+    const IN_ORDER : bool = true;
+
+    fn key(&self) -> String {
+        self.text.clone()
+    }
+
+    fn label(&self) -> String {
+        format!("example {:?}", self.text)
+    }
+}
+
+impl Element for HeadwordTranslation {
+    type Key = (String, Option<String>);
+    const IN_ORDER : bool = true;
+
+    fn key(&self) -> Self::Key {
+        (self.text.clone(), self.lang_code.as_ref().map(|l| l.0.clone()))
+    }
+
+    fn label(&self) -> String {
+        format!("headwordTranslation {:?}", self.text)
+    }
+}
+
+macro_rules! tag_element {
+    ($tag:ty, $name:expr) => {
+        impl Element for $tag {
+            type Key = String;
+
+            fn key(&self) -> String {
+                self.tag.clone()
+            }
+
+            fn label(&self) -> String {
+                format!("{} {}", $name, self.tag)
+            }
+        }
+    };
+}
+
+tag_element!(DefinitionTypeTag, "definitionTypeTag");
+tag_element!(InflectedFormTag, "inflectedFormTag");
+tag_element!(LabelTag, "labelTag");
+tag_element!(LabelTypeTag, "labelTypeTag");
+tag_element!(PartOfSpeechTag, "partOfSpeechTag");
+tag_element!(SourceIdentityTag, "sourceIdentityTag");
+tag_element!(TranscriptionSchemeTag, "transcriptionSchemeTag");
+
+impl Element for RelationType {
+    type Key = String;
+
+    fn key(&self) -> String {
+        self._type.clone()
+    }
+
+    fn label(&self) -> String {
+        format!("relationType {}", self._type)
+    }
+}
+
+impl Element for Relation {
+    type Key = String;
+
+    fn key(&self) -> String {
+        relation_label(self)
+    }
+
+    fn label(&self) -> String {
+        relation_label(self)
+    }
+}
+
+impl Element for EtymonLanguage {
+    type Key = String;
+
+    fn key(&self) -> String {
+        self.lang_code.0.clone()
+    }
+
+    fn label(&self) -> String {
+        format!("etymonLanguage {}", self.lang_code.0)
+    }
+}
+
+impl Element for EtymonType {
+    type Key = String;
+
+    fn key(&self) -> String {
+        self._type.clone()
+    }
+
+    fn label(&self) -> String {
+        format!("etymonType {}", self._type)
+    }
+}
+
+/// Merge the changes made in `ours` and `theirs` since `base`
+pub fn merge(base : &LexicographicResource, ours : &LexicographicResource, theirs : &LexicographicResource) -> MergeResult {
+    let mut conflicts = Vec::new();
+    let conflicts_ref = &mut conflicts;
+    let mut resource = ours.clone();
+    merge_fields!(resource, base, ours, theirs, "lexicographicResource", conflicts_ref;
+        title: "title", uri: "uri", lang_code: "langCode", translation_languages: "translationLanguages");
+    macro_rules! lists {
+        ($($field:ident),*) => {
+            $(resource.$field = merge_list(&base.$field, &ours.$field, &theirs.$field, "", conflicts_ref);)*
+        };
+    }
+    lists!(entries, definition_type_tags, inflected_form_tags, label_tags, label_type_tags, part_of_speech_tags,
+        source_identity_tags, transcription_scheme_tags, relations, relation_types, etymon_languages, etymon_types);
+    MergeResult { resource, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    fn example(name : &str) -> LexicographicResource {
+        parse(File::open(format!("examples/{}", name)).unwrap(), &Format::XML, &None).unwrap()
+    }
+
+    #[test]
+    fn test_clean_merge() {
+        let base = example("0.xml");
+        let mut ours = base.clone();
+        ours.entries[0].senses[0].definitions[0].text = "to leave a place or a person".to_string();
+        ours.entries.push(Entry { id: Some("abbey".to_string()), headword: "abbey".to_string(), ..Entry::default() });
+        let mut theirs = base.clone();
+        theirs.entries[0].senses[1].examples.push(Example { text: "The plan was abandoned.".to_string(), ..Example::default() });
+        theirs.entries[0].labels.push("formal".to_string());
+        theirs.entries.insert(0, Entry { id: Some("aardvark".to_string()), headword: "aardvark".to_string(), ..Entry::default() });
+        theirs.title = Some("Example Dictionary 2".to_string());
+        let result = merge(&base, &ours, &theirs);
+        assert!(result.is_clean(), "{:?}", result.conflicts);
+        let resource = result.resource;
+        assert_eq!(resource.title.as_deref(), Some("Example Dictionary 2"));
+        assert_eq!(resource.entries.iter().map(|e| e.headword.as_str()).collect::<Vec<_>>(), vec!["aardvark", "abandon", "abbey"]);
+        let abandon = &resource.entries[1];
+        assert_eq!(abandon.labels, vec!["formal"]);
+        assert_eq!(abandon.senses[0].definitions[0].text, "to leave a place or a person");
+        assert_eq!(abandon.senses[1].examples.len(), 2);
+        assert!(crate::diff::diff(&merge(&base, &ours, &base).resource, &ours).is_empty());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let base = example("0.xml");
+        let mut ours = base.clone();
+        ours.entries[0].senses[0].definitions[0].text = "to leave".to_string();
+        ours.entries[0].senses[1].labels.clear();
+        ours.part_of_speech_tags.push(PartOfSpeechTag { tag: "verb".to_string(), description: Some("verb".to_string()), ..PartOfSpeechTag::default() });
+        let mut theirs = base.clone();
+        theirs.entries[0].senses[0].definitions[0].text = "to go away from".to_string();
+        theirs.entries[0].senses.remove(1);
+        theirs.part_of_speech_tags.push(PartOfSpeechTag { tag: "verb".to_string(), description: Some("a verb".to_string()), ..PartOfSpeechTag::default() });
+        let result = merge(&base, &ours, &theirs);
+        assert_eq!(result.conflicts.iter().map(|c| c.to_string()).collect::<Vec<_>>(), vec![
+            "entry abandon-verb, sense abandon-verb-1, definition \"to leave\": changed on both sides, keeping ours",
+            "entry abandon-verb, sense abandon-verb-2: changed by us and removed by them, keeping ours",
+            "partOfSpeechTag verb: added on both sides differently, keeping ours",
+        ]);
+        assert_eq!(result.resource, ours);
+    }
+
+    #[test]
+    fn test_field_conflict() {
+        let base = example("14.xml");
+        let mut ours = base.clone();
+        ours.entries[0].headword = "die See".to_string();
+        let mut theirs = base.clone();
+        theirs.entries[0].headword = "Die See".to_string();
+        theirs.entries.remove(2);
+        let result = merge(&base, &ours, &theirs);
+        assert_eq!(result.conflicts, vec![Conflict { element: "entry die-see".to_string(), kind: ConflictKind::Field("headword".to_string()) }]);
+        assert_eq!(result.resource.entries.len(), 2);
+    }
+}
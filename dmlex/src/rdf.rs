@@ -13,7 +13,7 @@ use sophia::term::{Term, TTerm, TermKind, CopyTerm};
 use sophia::triple::Triple;
 use thiserror::Error;
 
-type Result<T> = std::result::Result<T, RdfError>;
+pub(crate) type Result<T> = std::result::Result<T, RdfError>;
 pub static DMLEX : &str = "https://docs.oasis-open.org/lexidma/dmlex/v1.0/schemas/RDF/dmlex.ttl#";
 pub static ONTOLEX : &str = "http://www.w3.org/ns/lemon/ontolex#";
 pub static LIME : &str = "http://www.w3.org/ns/lemon/lime#";
@@ -238,7 +238,7 @@ impl FromRDF for LexicographicResource {
 impl ToRDF for Entry {
     fn to_rdf<'a, G: MutableGraph, T1: AsRef<str>, T2: AsRef<str>>(&'a self, 
         graph: &mut G, data : &'a Namespace<T1>, dmlex: &Namespace<T2>,
-        _index : usize, ontolex : bool, uri : &Option<String>) ->
+        index : usize, ontolex : bool, uri : &Option<String>) ->
         Result<Term<String>> {
         let (id, uri) = name_elem(uri, "entry", self)?;
         let id = if let Some(i) = &self.id {
@@ -250,6 +250,10 @@ impl ToRDF for Entry {
             &id,
             &rdf::type_,
             &dmlex.get("Entry")?).expect("Error inserting triple");
+        graph.insert(
+            &id,
+            &dmlex.get("listingOrder")?,
+            &((index + 1) as u32).as_literal()).expect("Error inserting triple");
         if ontolex {
             graph.insert(
                 &id,
@@ -362,7 +366,7 @@ impl ToRDF for Entry {
 impl FromRDF for Entry {
     fn from_rdf<G : Graph, T1 : AsRef<str>, T2: AsRef<str>>(id : &Term<String>, 
         g : &G, dmlex: &Namespace<T1>, data: &Namespace<T2>) -> Result<(usize, Self)> where Self : Sized {
-        Ok((0, Entry {
+        Ok((get_zero_one_usize(g, id, &dmlex.get("listingOrder")?)?.unwrap_or(0), Entry {
             id : get_id(id, data),
             headword: get_one_str(g, id, &dmlex.get("headword")?)?,
             placeholder_markers: read_many(g, id, "placeholderMarker", data, dmlex)?,
@@ -1439,7 +1443,7 @@ impl FromRDF for Member {
 impl ToRDF for RelationType {
     fn to_rdf<'a, G: MutableGraph, T1: AsRef<str>, T2: AsRef<str>>(&'a self, 
         graph: &mut G, data : &'a Namespace<T1>, dmlex: &Namespace<T2>,
-        _index : usize, ontolex : bool, uri : &Option<String>) ->
+        index : usize, ontolex : bool, uri : &Option<String>) ->
         Result<Term<String>> {
         let (id, uri) = name_elem(uri, "relationType", self)?;
         graph.insert(
@@ -1490,6 +1494,10 @@ impl ToRDF for RelationType {
                 &dmlex.get("sameAs")?,
                 &Iri::new(same_as)?).expect("Error inserting triple");
         }
+        graph.insert(
+            &id,
+            &dmlex.get("listingOrder")?,
+            &((index + 1) as u32).as_literal()).expect("Error inserting triple");
         Ok(id)
     }
 }
@@ -1506,7 +1514,7 @@ impl FromRDF for RelationType {
                 _ => return Err(RdfError::InvalidScopeRestriction(s))
             }
         };
-        Ok((0, RelationType {
+        Ok((get_zero_one_usize(g, id, &dmlex.get("listingOrder")?)?.unwrap_or(0), RelationType {
             _type: get_one_str(g, id, &dmlex.get("type")?)?,
             description: get_zero_one_str(g, id, &dmlex.get("description")?)?,
             scope_restriction,
@@ -1520,7 +1528,7 @@ impl FromRDF for RelationType {
 impl ToRDF for MemberType {
     fn to_rdf<'a, G: MutableGraph, T1: AsRef<str>, T2: AsRef<str>>(&'a self, 
         graph: &mut G, _data : &'a Namespace<T1>, dmlex: &Namespace<T2>,
-        _index : usize, _ontolex : bool, uri : &Option<String>) ->
+        index : usize, _ontolex : bool, uri : &Option<String>) ->
         Result<Term<String>> {
         let (id, _) = name_elem(uri, "memberType", self)?;
         graph.insert(
@@ -1598,6 +1606,10 @@ impl ToRDF for MemberType {
                 &dmlex.get("sameAs")?,
                 &Iri::new(same_as)?).expect("Error inserting triple");
         }
+        graph.insert(
+            &id,
+            &dmlex.get("listingOrder")?,
+            &((index + 1) as u32).as_literal()).expect("Error inserting triple");
         Ok(id)
     }
 }
@@ -1620,7 +1632,7 @@ impl FromRDF for MemberType {
                 s => return Err(RdfError::InvalidHint(s.to_string()))
             }
         };
-        Ok((0, MemberType {
+        Ok((get_zero_one_usize(g, id, &dmlex.get("listingOrder")?)?.unwrap_or(0), MemberType {
             role: get_zero_one_str(g, id, &dmlex.get("role")?)?,
             description: get_zero_one_str(g, id, &dmlex.get("description")?)?,
             _type,
@@ -2017,7 +2029,7 @@ fn get_id<T: AsRef<str>>(term : &Term<String>, data : &Namespace<T>) -> Option<S
 //    }
 //}
 
-fn get_zero_one_str<G : Graph, S : TTerm + std::fmt::Debug, P : TTerm + std::fmt::Debug>(g : &G, subj : &S, prop : &P) -> Result<Option<String>> {
+pub(crate) fn get_zero_one_str<G : Graph, S : TTerm + std::fmt::Debug, P : TTerm + std::fmt::Debug>(g : &G, subj : &S, prop : &P) -> Result<Option<String>> {
     let mut iter = g.triples_with_sp(subj, prop);
     if let Some(triple) = iter.next() {
         let t = triple.unwrap();
@@ -2038,7 +2050,7 @@ fn get_zero_one_dmlex_uri<G : Graph, S : TTerm + std::fmt::Debug, P : TTerm + st
         let t = triple.unwrap();
         let obj = t.o();
         if obj.kind() == TermKind::Iri {
-            let iri = obj.value_raw().0.to_string();
+            let iri = obj.value().to_string();
             if iri.starts_with(&DMLEX.to_string()) {
                 Ok(Some(iri[DMLEX.to_string().len()..].to_string()))
             } else {
@@ -2061,15 +2073,13 @@ fn get_zero_one_u32<G : Graph, S : TTerm + std::fmt::Debug, P : TTerm + std::fmt
     }
 }
 
-//
-//fn get_zero_one_usize<G : Graph, S : TTerm + std::fmt::Debug, P : TTerm + std::fmt::Debug>(g : &G, subj : &S, prop : &P) -> Result<Option<usize>> {
-//    match get_zero_one_str(g, subj, prop) {
-//        Ok(Some(s)) => Ok(Some(s.parse::<usize>()?)),
-//        Ok(None) => Ok(None),
-//        Err(e) => Err(e),
-//    }
-//}
-//
+pub(crate) fn get_zero_one_usize<G : Graph, S : TTerm + std::fmt::Debug, P : TTerm + std::fmt::Debug>(g : &G, subj : &S, prop : &P) -> Result<Option<usize>> {
+    match get_zero_one_str(g, subj, prop) {
+        Ok(Some(s)) => Ok(Some(s.parse::<usize>()?)),
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
 
 fn get_zero_one_bool<G : Graph, S : TTerm + std::fmt::Debug, P : TTerm + std::fmt::Debug>(g : &G, subj : &S, prop : &P) -> Result<Option<bool>> {
     match get_zero_one_str(g, subj, prop) {
@@ -2100,7 +2110,7 @@ fn get_one_dmlex_uri<G : Graph, S : TTerm + std::fmt::Debug, P : TTerm + std::fm
         let t = triple.unwrap();
         let obj = t.o();
         if obj.kind() ==  TermKind::Iri {
-            let iri = obj.value_raw().0.to_string();
+            let iri = obj.value().to_string();
             if iri.starts_with(&DMLEX.to_string()) {
                 Ok(iri[DMLEX.to_string().len()..].to_string())
             } else {
@@ -2255,6 +2265,8 @@ pub enum RdfError {
     MissingLexicographicResource,
     #[error("No resource of type dmlex:Entry in the graph")]
     MissingEntry,
+    #[error("Invalid shape {0}: {1}")]
+    InvalidShape(String, String),
 }
 
 #[cfg(test)]
@@ -2422,6 +2434,15 @@ mod tests {
         assert_eq!(graph.triples().count(), graph2.triples().count());
     }
 
+    fn test_xml_round_trip_rdf_lexicon(fname : &str) {
+        let lexicon : LexicographicResource = crate::read_xml::read_xml(File::open(fname).unwrap(), "lexicographicResource").unwrap();
+        let mut graph = LightGraph::new();
+        let data = Namespace::new("file:").unwrap();
+        let dmlex = Namespace::new(DMLEX).expect("DMLEX namespace is invalid");
+        lexicon.to_rdf(&mut graph, &data, &dmlex, 0, false, &None).unwrap();
+        let lexicon2 = read_lexicographic_resource(&graph, &data).unwrap();
+        assert_eq!(lexicon, lexicon2);
+    }
 
     #[test]
     fn test_round_trip_rdf_0() {
@@ -2503,16 +2524,25 @@ mod tests {
         test_round_trip_rdf_lexicon("examples/15.rdf");
     }
 
-    // Example truncated for spec
-    //#[test]
-    //fn test_round_trip_rdf_16() {
-    //    test_round_trip_rdf_lexicon("examples/16.rdf");
-    //}
+    #[test]
+    fn test_round_trip_rdf_16() {
+        test_round_trip_rdf_lexicon("examples/16.rdf");
+    }
+
+    #[test]
+    fn test_round_trip_rdf_17() {
+        test_round_trip_rdf_lexicon("examples/17.rdf");
+    }
 
-    //#[test]
-    //fn test_round_trip_rdf_17() {
-    //    test_round_trip_rdf_lexicon("examples/17.rdf");
-    //}
+    #[test]
+    fn test_xml_round_trip_rdf_16() {
+        test_xml_round_trip_rdf_lexicon("examples/16.xml");
+    }
+
+    #[test]
+    fn test_xml_round_trip_rdf_17() {
+        test_xml_round_trip_rdf_lexicon("examples/17.xml");
+    }
 
     #[test]
     fn test_round_trip_rdf_18() {
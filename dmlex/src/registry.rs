@@ -0,0 +1,354 @@
+//! Readers and writers for lexicographic formats, looked up by name
+use crate::model::{Entry, LexicographicResource};
+use crate::report::ConversionReport;
+use crate::apertium::ApertiumFormat;
+use crate::detect::RootKind;
+use crate::html::HtmlFormat;
+use crate::latex::LatexFormat;
+use crate::lift::LiftFormat;
+use crate::sfm::SfmFormat;
+#[cfg(feature = "sqlite")]
+use crate::sqlite::SqliteFormat;
+use crate::tabular::TabularFormat;
+use crate::tei::TeiFormat;
+use crate::wordnet::WordNetFormat;
+use crate::{Format, ParseError, TurtleOptions, WriteError};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+/// Options passed to a reader
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadOptions {
+    pub default_namespace: Option<String>,
+}
+
+/// Options passed to a writer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WriteOptions {
+    pub default_namespace: Option<String>,
+    pub ontolex: bool,
+    pub turtle: TurtleOptions,
+}
+
+/// A format that lexicographic resources can be read from
+pub trait ResourceReader {
+    fn read_resource(&self, input : &mut dyn Read, options : &ReadOptions) -> Result<LexicographicResource, ParseError>;
+
+    /// Read a resource, also returning the constructs that could not be mapped
+    fn read_resource_with_report(&self, input : &mut dyn Read, options : &ReadOptions) -> Result<(LexicographicResource, ConversionReport), ParseError> {
+        Ok((self.read_resource(input, options)?, ConversionReport::new()))
+    }
+
+    /// Read a single entry, not all formats support this
+    fn read_entry(&self, _input : &mut dyn Read, _options : &ReadOptions) -> Result<Entry, ParseError> {
+        Err(ParseError::EntryNotSupported)
+    }
+
+    /// Whether a document holds a resource or a single entry, if the
+    /// format can tell
+    fn detect_root(&self, _content : &[u8]) -> Option<RootKind> {
+        None
+    }
+}
+
+/// A format that lexicographic resources can be written to
+pub trait ResourceWriter {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> Result<(), WriteError>;
+
+    /// Write a resource, also returning the constructs that could not be mapped
+    fn write_resource_with_report(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> Result<ConversionReport, WriteError> {
+        self.write_resource(output, resource, options)?;
+        Ok(ConversionReport::new())
+    }
+
+    /// Write a single entry, not all formats support this
+    fn write_entry(&self, _output : &mut dyn Write, _entry : &Entry, _options : &WriteOptions) -> Result<(), WriteError> {
+        Err(WriteError::EntryNotSupported)
+    }
+}
+
+impl ResourceReader for Format {
+    fn read_resource(&self, input : &mut dyn Read, options : &ReadOptions) -> Result<LexicographicResource, ParseError> {
+        crate::parse(input, self, &options.default_namespace)
+    }
+
+    fn read_resource_with_report(&self, input : &mut dyn Read, options : &ReadOptions) -> Result<(LexicographicResource, ConversionReport), ParseError> {
+        match self {
+            Format::LMF => Ok(crate::lmf::read_lmf(input)?),
+            _ => Ok((self.read_resource(input, options)?, ConversionReport::new())),
+        }
+    }
+
+    fn read_entry(&self, input : &mut dyn Read, options : &ReadOptions) -> Result<Entry, ParseError> {
+        crate::parse_entry(input, self, &options.default_namespace)
+    }
+
+    fn detect_root(&self, content : &[u8]) -> Option<RootKind> {
+        crate::detect::detect_root(content, self)
+    }
+}
+
+impl ResourceWriter for Format {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> Result<(), WriteError> {
+        crate::write_with_options(output, self, resource, &options.default_namespace, options.ontolex, &options.turtle)
+    }
+
+    fn write_resource_with_report(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> Result<ConversionReport, WriteError> {
+        match self {
+            Format::LMF => Ok(crate::lmf::write_lmf(output, resource)?),
+            _ => {
+                self.write_resource(output, resource, options)?;
+                Ok(ConversionReport::new())
+            }
+        }
+    }
+
+    fn write_entry(&self, output : &mut dyn Write, entry : &Entry, options : &WriteOptions) -> Result<(), WriteError> {
+        crate::write_entry_with_options(output, self, entry, &options.default_namespace, options.ontolex, &options.turtle)
+    }
+}
+
+/// A collection of readers and writers keyed by format name. The default
+/// registry contains the DMLEX serializations `xml`, `json` and `rdf` as
+/// well as `lift` for SIL LIFT, `lmf` for LMF, `tei` for TEI Lex-0,
+/// `wordnet` for WN-LMF, `csv` and `tsv` for tables with one row per sense
+/// and, for reading only, `sfm` for Toolbox/MDF files. For writing only,
+/// `html` renders the resource as a single HTML document, `latex` as a
+/// LaTeX document and `dix` exports its translations as an Apertium
+/// bilingual dictionary. With the `sqlite`
+/// feature, `sqlite` reads and writes relational SQLite databases.
+pub struct FormatRegistry {
+    readers: BTreeMap<String, Box<dyn ResourceReader>>,
+    writers: BTreeMap<String, Box<dyn ResourceWriter>>,
+    /// XML document elements that identify a format other than DMLEX XML
+    xml_roots: BTreeMap<String, String>,
+}
+
+impl FormatRegistry {
+    /// Create a registry without any formats
+    pub fn empty() -> FormatRegistry {
+        FormatRegistry {
+            readers: BTreeMap::new(),
+            writers: BTreeMap::new(),
+            xml_roots: BTreeMap::new(),
+        }
+    }
+
+    /// Register a reader, replacing any reader with the same name
+    pub fn register_reader<R : ResourceReader + 'static>(&mut self, name : &str, reader : R) {
+        self.readers.insert(name.to_string(), Box::new(reader));
+    }
+
+    /// Register a writer, replacing any writer with the same name
+    pub fn register_writer<W : ResourceWriter + 'static>(&mut self, name : &str, writer : W) {
+        self.writers.insert(name.to_string(), Box::new(writer));
+    }
+
+    /// Register a format that can be both read and written
+    pub fn register<F : ResourceReader + ResourceWriter + Clone + 'static>(&mut self, name : &str, format : F) {
+        self.register_reader(name, format.clone());
+        self.register_writer(name, format);
+    }
+
+    /// Detect XML documents with the given document element as this format
+    pub fn register_xml_root(&mut self, root : &str, name : &str) {
+        self.xml_roots.insert(root.to_string(), name.to_string());
+    }
+
+    /// Detect the name of the format of a document, see `detect::detect_format`.
+    /// XML documents are recognised by their document element.
+    pub fn detect_format(&self, content : &[u8], file_name : Option<&str>) -> Option<String> {
+        let format = match crate::detect::detect_format(content, file_name) {
+            Some(format) => format,
+            None => return self.detect_other_format(content, file_name),
+        };
+        if format == Format::XML {
+            if let Some(name) = crate::detect::xml_root_name(content).and_then(|r| self.xml_roots.get(&r)) {
+                return Some(name.clone());
+            }
+        }
+        Some(format.name().to_string())
+    }
+
+    /// Detect the formats that are not serializations of DMLEX or XML, that
+    /// is SFM by its first marker, SQLite by its header and tables by their
+    /// file extension
+    fn detect_other_format(&self, content : &[u8], file_name : Option<&str>) -> Option<String> {
+        let name = if crate::sfm::is_sfm(content) {
+            "sfm".to_string()
+        } else if content.starts_with(b"SQLite format 3\0") {
+            "sqlite".to_string()
+        } else {
+            std::path::Path::new(file_name?).extension()?.to_str()?.to_lowercase()
+        };
+        if self.readers.contains_key(&name) {
+            Some(name)
+        } else {
+            None
+        }
+    }
+
+    pub fn reader(&self, name : &str) -> Option<&dyn ResourceReader> {
+        self.readers.get(name).map(|r| r.as_ref())
+    }
+
+    pub fn writer(&self, name : &str) -> Option<&dyn ResourceWriter> {
+        self.writers.get(name).map(|w| w.as_ref())
+    }
+
+    /// Whether a document in a format holds a resource or a single entry,
+    /// if the reader of the format can tell
+    pub fn detect_root(&self, name : &str, content : &[u8]) -> Option<RootKind> {
+        self.reader(name)?.detect_root(content)
+    }
+
+    /// The names of the formats that can be read, in alphabetical order
+    pub fn reader_names(&self) -> Vec<&str> {
+        self.readers.keys().map(|k| k.as_str()).collect()
+    }
+
+    /// The names of the formats that can be written, in alphabetical order
+    pub fn writer_names(&self) -> Vec<&str> {
+        self.writers.keys().map(|k| k.as_str()).collect()
+    }
+
+    pub fn parse(&self, name : &str, input : &mut dyn Read, options : &ReadOptions) -> Result<LexicographicResource, ParseError> {
+        self.reader(name).ok_or_else(|| ParseError::UnknownFormat(name.to_string()))?
+            .read_resource(input, options)
+    }
+
+    pub fn parse_with_report(&self, name : &str, input : &mut dyn Read, options : &ReadOptions) -> Result<(LexicographicResource, ConversionReport), ParseError> {
+        self.reader(name).ok_or_else(|| ParseError::UnknownFormat(name.to_string()))?
+            .read_resource_with_report(input, options)
+    }
+
+    pub fn parse_entry(&self, name : &str, input : &mut dyn Read, options : &ReadOptions) -> Result<Entry, ParseError> {
+        self.reader(name).ok_or_else(|| ParseError::UnknownFormat(name.to_string()))?
+            .read_entry(input, options)
+    }
+
+    pub fn write(&self, name : &str, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> Result<(), WriteError> {
+        self.writer(name).ok_or_else(|| WriteError::UnknownFormat(name.to_string()))?
+            .write_resource(output, resource, options)
+    }
+
+    pub fn write_with_report(&self, name : &str, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> Result<ConversionReport, WriteError> {
+        self.writer(name).ok_or_else(|| WriteError::UnknownFormat(name.to_string()))?
+            .write_resource_with_report(output, resource, options)
+    }
+
+    pub fn write_entry(&self, name : &str, output : &mut dyn Write, entry : &Entry, options : &WriteOptions) -> Result<(), WriteError> {
+        self.writer(name).ok_or_else(|| WriteError::UnknownFormat(name.to_string()))?
+            .write_entry(output, entry, options)
+    }
+}
+
+impl Default for FormatRegistry {
+    fn default() -> FormatRegistry {
+        let mut registry = FormatRegistry::empty();
+        registry.register(Format::XML.name(), Format::XML);
+        registry.register(Format::JSON.name(), Format::JSON);
+        registry.register(Format::RDF.name(), Format::RDF);
+        registry.register(Format::LMF.name(), Format::LMF);
+        registry.register("tei", TeiFormat);
+        registry.register_xml_root("TEI", "tei");
+        registry.register("lift", LiftFormat);
+        registry.register_xml_root("lift", "lift");
+        registry.register_reader("sfm", SfmFormat::default());
+        registry.register("csv", TabularFormat::csv());
+        registry.register("tsv", TabularFormat::tsv());
+        #[cfg(feature = "sqlite")]
+        registry.register("sqlite", SqliteFormat);
+        registry.register("wordnet", WordNetFormat);
+        registry.register_writer("dix", ApertiumFormat::default());
+        registry.register_writer("html", HtmlFormat::default());
+        registry.register_writer("latex", LatexFormat::default());
+        // ISO LMF documents are detected as `Format::LMF`, so only WordNet
+        // LMF documents are left as XML with this document element
+        registry.register_xml_root("LexicalResource", "wordnet");
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    struct HeadwordList;
+
+    impl ResourceWriter for HeadwordList {
+        fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, _options : &WriteOptions) -> Result<(), WriteError> {
+            for entry in resource.entries.iter() {
+                writeln!(output, "{}", entry.headword)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_default_formats() {
+        let registry = FormatRegistry::default();
+        let mut readers = vec!["csv", "json", "lift", "lmf", "rdf", "sfm", "tei", "tsv", "wordnet", "xml"];
+        let mut writers = vec!["csv", "dix", "html", "json", "latex", "lift", "lmf", "rdf", "tei", "tsv", "wordnet", "xml"];
+        if cfg!(feature = "sqlite") {
+            readers.insert(6, "sqlite");
+            writers.insert(8, "sqlite");
+        }
+        assert_eq!(registry.reader_names(), readers);
+        assert_eq!(registry.writer_names(), writers);
+    }
+
+    #[test]
+    fn test_detect_registered_root() {
+        let registry = FormatRegistry::default();
+        let tei = br#"<TEI xmlns="http://www.tei-c.org/ns/1.0"><text><body/></text></TEI>"#;
+        assert_eq!(registry.detect_format(tei, None), Some("tei".to_string()));
+        let lmf = br#"<LexicalResource dtdVersion="16"><Lexicon/></LexicalResource>"#;
+        assert_eq!(registry.detect_format(lmf, None), Some("lmf".to_string()));
+        let wordnet = br#"<LexicalResource><Lexicon id="ewn" label="" language="en" email="" license="" version="1"/></LexicalResource>"#;
+        assert_eq!(registry.detect_format(wordnet, None), Some("wordnet".to_string()));
+        assert_eq!(registry.detect_format(br#"<lift version="0.13"/>"#, None), Some("lift".to_string()));
+        assert_eq!(registry.detect_format(b"headword,definition\n", Some("review.CSV")), Some("csv".to_string()));
+        assert_eq!(registry.detect_format(b"headword,definition\n", Some("review.txt")), None);
+        assert_eq!(registry.detect_format(b"\\_sh v3.0  400  MDF 4.0\n\\lx cat\n", None), Some("sfm".to_string()));
+        assert_eq!(registry.detect_format(&std::fs::read("examples/0.xml").unwrap(), None), Some("xml".to_string()));
+    }
+
+    #[test]
+    fn test_detect_root() {
+        let registry = FormatRegistry::default();
+        let tei = br#"<entry xmlns="http://www.tei-c.org/ns/1.0"><form type="lemma"><orth>cat</orth></form></entry>"#;
+        assert_eq!(registry.detect_root("tei", tei), Some(RootKind::Entry));
+        assert_eq!(registry.detect_root("xml", &std::fs::read("examples/1.xml").unwrap()), Some(RootKind::Entry));
+        assert_eq!(registry.detect_root("xml", &std::fs::read("examples/0.xml").unwrap()), Some(RootKind::LexicographicResource));
+        assert_eq!(registry.detect_root("csv", b"headword,definition\n"), None);
+        assert_eq!(registry.detect_root("unknown", b""), None);
+    }
+
+    #[test]
+    fn test_round_trip_through_registry() {
+        let registry = FormatRegistry::default();
+        let resource = registry.parse("xml", &mut File::open("examples/0.xml").unwrap(), &ReadOptions::default()).unwrap();
+        let mut out = Vec::new();
+        registry.write("json", &mut out, &resource, &WriteOptions::default()).unwrap();
+        let resource2 = registry.parse("json", &mut out.as_slice(), &ReadOptions::default()).unwrap();
+        assert_eq!(resource, resource2);
+    }
+
+    #[test]
+    fn test_register_writer() {
+        let mut registry = FormatRegistry::default();
+        registry.register_writer("headwords", HeadwordList);
+        assert!(registry.writer_names().contains(&"headwords"));
+        assert!(!registry.reader_names().contains(&"headwords"));
+        let resource = registry.parse("xml", &mut File::open("examples/0.xml").unwrap(), &ReadOptions::default()).unwrap();
+        let mut out = Vec::new();
+        registry.write("headwords", &mut out, &resource, &WriteOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "abandon\n");
+        assert!(matches!(registry.write_entry("headwords", &mut Vec::new(), &resource.entries[0], &WriteOptions::default()),
+            Err(WriteError::EntryNotSupported)));
+        assert!(matches!(registry.parse("headwords", &mut "".as_bytes(), &ReadOptions::default()),
+            Err(ParseError::UnknownFormat(_))));
+    }
+}
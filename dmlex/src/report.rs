@@ -0,0 +1,69 @@
+//! Reports of data that could not be mapped when converting between DMLEX
+//! and other formats
+use crate::model::LexicographicResource;
+use std::fmt;
+
+/// A construct that was skipped or only partially converted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionWarning {
+    /// Where the construct was found, e.g. a path or a line number
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConversionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// The list of warnings produced by a conversion
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ConversionReport {
+    pub warnings: Vec<ConversionWarning>,
+}
+
+impl ConversionReport {
+    pub fn new() -> ConversionReport {
+        ConversionReport::default()
+    }
+
+    pub fn warn<L : Into<String>, M : Into<String>>(&mut self, location : L, message : M) {
+        self.warnings.push(ConversionWarning {
+            location: location.into(),
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    pub fn extend(&mut self, other : ConversionReport) {
+        self.warnings.extend(other.warnings);
+    }
+}
+
+/// Report the tag definitions, etymon languages and etymon types of a
+/// resource for formats that have no place for them
+pub(crate) fn note_unmapped_tags(resource : &LexicographicResource, here : &str, report : &mut ConversionReport) {
+    let tag_count = resource.definition_type_tags.len() + resource.inflected_form_tags.len()
+        + resource.label_tags.len() + resource.label_type_tags.len()
+        + resource.part_of_speech_tags.len() + resource.source_identity_tags.len()
+        + resource.transcription_scheme_tags.len();
+    if tag_count > 0 {
+        report.warn(here, format!("{} tag definition(s) not mapped", tag_count));
+    }
+    if !resource.etymon_languages.is_empty() || !resource.etymon_types.is_empty() {
+        report.warn(here, "Etymon languages and types are not mapped");
+    }
+}
+
+impl fmt::Display for ConversionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for warning in self.warnings.iter() {
+            writeln!(f, "{}", warning)?;
+        }
+        Ok(())
+    }
+}
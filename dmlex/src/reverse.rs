@@ -0,0 +1,317 @@
+//! Reversal of a bilingual resource into a resource from one of its
+//! translation languages back to the headword language
+//!
+//! Each headword translation into the chosen language becomes an entry, and
+//! translations with the same text and parts of speech are grouped into one
+//! entry. Each source sense that a translation belongs to becomes a sense of
+//! that entry, which is linked back to the source headword by a headword
+//! translation into the source language. Its indicator, its translations
+//! into other languages and the examples whose translation contains the new
+//! headword are carried over, with the example and its translation swapped.
+//! Definitions, relations and etymologies are in the source language or
+//! refer to it and are left out.
+//!
+//! Entries sharing a headword are numbered as homographs in the order of
+//! their parts of speech. Tags are carried over with their `for` language
+//! swapped: tags for the chosen language now apply to the headwords and tags
+//! for the headwords now apply to the source language translations, unless
+//! they are also used on the new headwords.
+use crate::homograph::{number_homographs, HomographOptions, HomographOrder};
+use crate::model::*;
+use crate::report::ConversionReport;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use thiserror::Error;
+
+type Result<T> = std::result::Result<T, ReverseError>;
+
+/// Reverse a resource into the language `lang`, or into its only translation
+/// language if `lang` is `None`
+pub fn reverse(resource : &LexicographicResource, lang : Option<&str>) -> Result<(LexicographicResource, ConversionReport)> {
+    let mut report = ConversionReport::new();
+    let languages = languages(resource);
+    let target = match lang {
+        Some(lang) if languages.contains(lang) => lang.to_string(),
+        Some(lang) => return Err(ReverseError::UnknownLanguage(lang.to_string())),
+        None if languages.len() == 1 => languages.iter().next().unwrap().clone(),
+        None if languages.is_empty() => return Err(ReverseError::NoTranslations),
+        None => return Err(ReverseError::AmbiguousLanguage(languages.into_iter().collect::<Vec<_>>().join(", "))),
+    };
+    let source = resource.lang_code.0.clone();
+    let default_lang = match resource.translation_languages.as_slice() {
+        [lang] => Some(lang.as_str()),
+        _ => None,
+    };
+    let lang_of = |translation : &HeadwordTranslation| translation.lang_code.as_ref()
+        .map(|l| l.0.clone()).or(default_lang.map(|l| l.to_string()));
+    let mut groups : BTreeMap<(String, String, Vec<String>), Entry> = BTreeMap::new();
+    let mut other_languages = Vec::new();
+    for (i, entry) in resource.entries.iter().enumerate() {
+        let location = entry.id.clone().unwrap_or_else(|| format!("entry {}", i + 1));
+        for sense in entry.senses.iter() {
+            let (translations, others) : (Vec<&HeadwordTranslation>, Vec<&HeadwordTranslation>) = sense.headword_translations.iter()
+                .partition(|t| lang_of(t).as_deref() == Some(target.as_str()));
+            for translation in translations {
+                if translation.text.trim().is_empty() {
+                    report.warn(&location, "Empty translation left out");
+                    continue;
+                }
+                let key = (translation.text.to_lowercase(), translation.text.clone(), translation.parts_of_speech.clone());
+                let reversed = groups.entry(key).or_default();
+                reverse_translation(reversed, translation);
+                let mut back_links = vec![back_link(entry, &source)];
+                for other in others.iter() {
+                    let lang = lang_of(other);
+                    if let Some(lang) = &lang {
+                        if !other_languages.contains(lang) {
+                            other_languages.push(lang.clone());
+                        }
+                    }
+                    back_links.push(HeadwordTranslation { lang_code: lang.map(LangCode), ..(*other).clone() });
+                }
+                reversed.senses.push(Sense {
+                    id: None,
+                    indicator: sense.indicator.clone(),
+                    labels: translation.labels.clone(),
+                    headword_translations: back_links,
+                    examples: sense.examples.iter()
+                        .filter_map(|e| reverse_example(e, &translation.text, &target, &source, default_lang))
+                        .collect(),
+                    ..Sense::default()
+                });
+            }
+        }
+    }
+    if !resource.relations.is_empty() {
+        report.warn("lexicographicResource", "Relations are not carried over");
+    }
+    if resource.entries.iter().any(|e| !e.etymologies.is_empty()) {
+        report.warn("lexicographicResource", "Etymologies are not carried over");
+    }
+    let mut translation_languages = vec![source.clone()];
+    translation_languages.extend(other_languages.into_iter().filter(|l| *l != source && *l != target));
+    let mut reversed = LexicographicResource {
+        lang_code: LangCode(target.clone()),
+        entries: groups.into_values().collect(),
+        translation_languages,
+        ..LexicographicResource::default()
+    };
+    carry_tags(resource, &mut reversed, &target, &source);
+    number_homographs(&mut reversed, &HomographOptions::default().with_order(HomographOrder::PartOfSpeech));
+    let mut used_ids = HashSet::new();
+    for entry in reversed.entries.iter_mut() {
+        let mut id = entry.headword.split_whitespace().collect::<Vec<_>>().join("-");
+        if let Some(number) = entry.homograph_number {
+            id.push_str(&format!("-{}", number));
+        }
+        let mut unique = id.clone();
+        let mut n = 1;
+        while !used_ids.insert(unique.clone()) {
+            n += 1;
+            unique = format!("{}-{}", id, n);
+        }
+        for (j, sense) in entry.senses.iter_mut().enumerate() {
+            sense.id = Some(format!("{}-{}", unique, j + 1));
+        }
+        entry.id = Some(unique);
+    }
+    Ok((reversed, report))
+}
+
+/// The languages a resource has translations into
+fn languages(resource : &LexicographicResource) -> BTreeSet<String> {
+    let mut languages : BTreeSet<String> = resource.translation_languages.iter().cloned().collect();
+    for translation in resource.entries.iter().flat_map(|e| e.senses.iter()).flat_map(|s| s.headword_translations.iter()) {
+        if let Some(lang) = &translation.lang_code {
+            languages.insert(lang.0.clone());
+        }
+    }
+    languages
+}
+
+/// Add the headword properties of a translation to the entry it becomes
+fn reverse_translation(entry : &mut Entry, translation : &HeadwordTranslation) {
+    if entry.headword.is_empty() {
+        entry.headword = translation.text.clone();
+        entry.placeholder_markers = translation.placeholder_markers.clone();
+        entry.parts_of_speech = translation.parts_of_speech.clone();
+    }
+    for pronunciation in translation.pronunciations.iter() {
+        if !entry.pronunciations.contains(pronunciation) {
+            entry.pronunciations.push(pronunciation.clone());
+        }
+    }
+    for form in translation.inflected_forms.iter() {
+        if !entry.inflected_forms.contains(form) {
+            entry.inflected_forms.push(form.clone());
+        }
+    }
+}
+
+/// The translation linking a reversed sense back to a source entry
+fn back_link(entry : &Entry, source : &str) -> HeadwordTranslation {
+    HeadwordTranslation {
+        text: entry.headword.clone(),
+        placeholder_markers: entry.placeholder_markers.clone(),
+        lang_code: Some(LangCode(source.to_string())),
+        parts_of_speech: entry.parts_of_speech.clone(),
+        labels: entry.labels.clone(),
+        pronunciations: entry.pronunciations.clone(),
+        inflected_forms: entry.inflected_forms.clone(),
+    }
+}
+
+/// An example with its translation into the target language as the text and
+/// the original as the translation, if that translation contains the headword
+fn reverse_example(example : &Example, headword : &str, target : &str, source : &str, default_lang : Option<&str>) -> Option<Example> {
+    let headword = headword.to_lowercase();
+    let translation = example.example_translations.iter().find(|t| {
+        t.lang_code.as_ref().map(|l| l.0.as_str()).or(default_lang) == Some(target)
+            && t.text.to_lowercase().contains(&headword)
+    })?;
+    Some(Example {
+        text: translation.text.clone(),
+        collocate_markers: translation.collocate_markers.clone(),
+        headword_markers: translation.headword_markers.clone(),
+        labels: translation.labels.clone(),
+        sound_file: translation.sound_file.clone(),
+        example_translations: vec![ExampleTranslation {
+            text: example.text.clone(),
+            collocate_markers: example.collocate_markers.clone(),
+            headword_markers: example.headword_markers.clone(),
+            lang_code: Some(LangCode(source.to_string())),
+            labels: example.labels.clone(),
+            sound_file: example.sound_file.clone(),
+        }],
+        ..Example::default()
+    })
+}
+
+/// Swap the `for` language of a tag between the source and target languages
+fn swap_for(for_ : &Option<String>, tag : &str, used : &HashSet<&str>, target : &str, source : &str) -> Option<String> {
+    match for_.as_deref() {
+        Some(lang) if lang == target => None,
+        None if !used.contains(tag) => Some(source.to_string()),
+        _ => for_.clone(),
+    }
+}
+
+/// Carry over the tags that still apply to the reversed resource
+fn carry_tags(resource : &LexicographicResource, reversed : &mut LexicographicResource, target : &str, source : &str) {
+    let mut used = HashSet::new();
+    for entry in reversed.entries.iter() {
+        used.extend(entry.parts_of_speech.iter().map(|s| s.as_str()));
+        used.extend(entry.senses.iter().flat_map(|s| s.labels.iter()).map(|s| s.as_str()));
+        used.extend(entry.inflected_forms.iter().filter_map(|f| f.tag.as_deref()));
+        used.extend(entry.inflected_forms.iter().flat_map(|f| f.labels.iter()).map(|s| s.as_str()));
+        for pronunciation in entry.pronunciations.iter() {
+            used.extend(pronunciation.labels.iter().map(|s| s.as_str()));
+            used.extend(pronunciation.transcriptions.iter().filter_map(|t| t.scheme.as_deref()));
+        }
+    }
+    reversed.part_of_speech_tags = resource.part_of_speech_tags.iter().map(|t| PartOfSpeechTag {
+        for_: swap_for(&t.for_, &t.tag, &used, target, source), ..t.clone()
+    }).collect();
+    reversed.inflected_form_tags = resource.inflected_form_tags.iter().map(|t| InflectedFormTag {
+        for_: swap_for(&t.for_, &t.tag, &used, target, source), ..t.clone()
+    }).collect();
+    reversed.label_tags = resource.label_tags.iter().map(|t| LabelTag {
+        for_: swap_for(&t.for_, &t.tag, &used, target, source), ..t.clone()
+    }).collect();
+    reversed.transcription_scheme_tags = resource.transcription_scheme_tags.iter().map(|t| TranscriptionSchemeTag {
+        for_: swap_for(&t.for_, &t.tag, &used, target, source), ..t.clone()
+    }).collect();
+    reversed.label_type_tags = resource.label_type_tags.clone();
+}
+
+#[derive(Error, Debug)]
+pub enum ReverseError {
+    #[error("The resource has no translations")]
+    NoTranslations,
+    #[error("The resource has several translation languages, choose one of: {0}")]
+    AmbiguousLanguage(String),
+    #[error("The resource has no translations into {0}")]
+    UnknownLanguage(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_entry, Format};
+    use std::fs::File;
+
+    #[test]
+    fn test_reverse() {
+        let resource = parse(File::open("examples/14.xml").unwrap(), &Format::XML, &None).unwrap();
+        let (reversed, report) = reverse(&resource, None).unwrap();
+        assert_eq!(report.to_string(), "lexicographicResource: Relations are not carried over\n");
+        assert_eq!(reversed.lang_code.0, "en");
+        assert_eq!(reversed.translation_languages, vec!["de"]);
+        assert_eq!(reversed.entries.iter().map(|e| e.headword.as_str()).collect::<Vec<_>>(), vec!["ocean", "sea"]);
+        let sea = &reversed.entries[1];
+        assert_eq!(sea.id.as_deref(), Some("sea"));
+        assert_eq!(sea.senses.len(), 2);
+        assert_eq!(sea.senses[1].id.as_deref(), Some("sea-2"));
+        let back = &sea.senses[1].headword_translations[0];
+        assert_eq!(back.text, "Meer");
+        assert_eq!(back.lang_code, Some(LangCode("de".to_string())));
+        assert_eq!(back.parts_of_speech, vec!["n-neut"]);
+        assert!(reversed.validate().is_ok());
+    }
+
+    #[test]
+    fn test_homographs_and_tags() {
+        let mut resource = LexicographicResource {
+            lang_code: LangCode("en".to_string()),
+            translation_languages: vec!["de".to_string()],
+            part_of_speech_tags: vec![
+                PartOfSpeechTag { tag: "n".to_string(), ..PartOfSpeechTag::default() },
+                PartOfSpeechTag { tag: "n-masc".to_string(), for_: Some("de".to_string()), ..PartOfSpeechTag::default() },
+            ],
+            ..LexicographicResource::default()
+        };
+        let entry = parse_entry(File::open("examples/8.xml").unwrap(), &Format::XML, &None).unwrap();
+        resource.entries.push(entry);
+        resource.entries.push(Entry {
+            headword: "arrow".to_string(),
+            parts_of_speech: vec!["n".to_string()],
+            senses: vec![Sense {
+                headword_translations: vec![HeadwordTranslation { text: "Pfeil".to_string(), ..HeadwordTranslation::default() }],
+                examples: vec![Example {
+                    text: "He shot an arrow.".to_string(),
+                    example_translations: vec![
+                        ExampleTranslation { text: "Er schoss einen Pfeil.".to_string(), ..ExampleTranslation::default() },
+                    ],
+                    ..Example::default()
+                }],
+                ..Sense::default()
+            }],
+            ..Entry::default()
+        });
+        resource.entries[0].senses[0].headword_translations[0].text = "Doktor".to_string();
+        resource.entries[0].senses[0].headword_translations[0].parts_of_speech = vec!["n".to_string()];
+        let (reversed, report) = reverse(&resource, Some("de")).unwrap();
+        assert!(report.is_empty(), "{}", report);
+        let doktor : Vec<&Entry> = reversed.entries.iter().filter(|e| e.headword == "Doktor").collect();
+        assert_eq!(doktor.len(), 2);
+        assert_eq!(doktor[0].homograph_number, Some(1));
+        assert_eq!(doktor[0].parts_of_speech, vec!["n"]);
+        assert_eq!(doktor[0].id.as_deref(), Some("Doktor-1"));
+        assert_eq!(doktor[1].senses[0].indicator.as_deref(), Some("academic title"));
+        let pfeil = reversed.entries.iter().find(|e| e.headword == "Pfeil").unwrap();
+        assert_eq!(pfeil.senses[0].examples[0].text, "Er schoss einen Pfeil.");
+        assert_eq!(pfeil.senses[0].examples[0].example_translations[0].text, "He shot an arrow.");
+        assert_eq!(reversed.part_of_speech_tags[0].for_, None);
+        assert_eq!(reversed.part_of_speech_tags[1].for_, None);
+        assert!(reversed.validate().is_ok());
+    }
+
+    #[test]
+    fn test_language_choice() {
+        let resource = parse(File::open("examples/14.xml").unwrap(), &Format::XML, &None).unwrap();
+        assert!(matches!(reverse(&resource, Some("fr")), Err(ReverseError::UnknownLanguage(_))));
+        let mut resource = LexicographicResource::default();
+        assert!(matches!(reverse(&resource, None), Err(ReverseError::NoTranslations)));
+        resource.translation_languages = vec!["de".to_string(), "fr".to_string()];
+        assert!(matches!(reverse(&resource, None), Err(ReverseError::AmbiguousLanguage(_))));
+    }
+}
@@ -0,0 +1,498 @@
+//! Import of Toolbox and Multi-Dictionary Formatter (MDF) standard format
+//! marker files
+//!
+//! A file consists of fields that start with a backslash marker such as
+//! `\lx` and continue until the next marker. Each record starts with the
+//! record marker and becomes an entry, and the other markers are mapped
+//! onto DMLEX by an `SfmMapping`. Markers without a mapping are listed in the
+//! `ConversionReport` with their line numbers.
+use crate::model::*;
+use crate::registry::{ReadOptions, ResourceReader};
+use crate::report::ConversionReport;
+use crate::ParseError;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Read};
+use thiserror::Error;
+
+type Result<T> = std::result::Result<T, SfmError>;
+
+/// The DMLEX property a marker is mapped onto
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SfmTarget {
+    /// The headword of the entry, the record marker is mapped onto this
+    Headword,
+    HomographNumber,
+    /// A part of speech of the entry, which is also declared as a tag
+    PartOfSpeech,
+    /// Starts a new sense
+    SenseNumber,
+    Definition,
+    /// A headword translation in the given language
+    HeadwordTranslation(Option<String>),
+    /// Starts a new example in the current sense
+    Example,
+    /// A translation of the last example in the given language
+    ExampleTranslation(Option<String>),
+    Pronunciation,
+    /// A label of the current sense, or of the entry before the first sense
+    Label,
+    Etymology,
+    /// Fields that are known but deliberately not converted
+    Ignore,
+}
+
+/// The mapping from markers (without the backslash) to DMLEX
+#[derive(Debug, Clone, PartialEq)]
+pub struct SfmMapping {
+    /// The marker that starts a record
+    pub record_marker: String,
+    pub markers: HashMap<String, SfmTarget>,
+}
+
+impl SfmMapping {
+    /// A mapping without any markers except the record marker
+    pub fn new(record_marker : &str) -> SfmMapping {
+        let mut markers = HashMap::new();
+        markers.insert(record_marker.to_string(), SfmTarget::Headword);
+        SfmMapping {
+            record_marker: record_marker.to_string(),
+            markers,
+        }
+    }
+
+    /// The MDF markers with English as the analysis language
+    pub fn mdf() -> SfmMapping {
+        SfmMapping::new("lx")
+            .with_marker("hm", SfmTarget::HomographNumber)
+            .with_marker("ps", SfmTarget::PartOfSpeech)
+            .with_marker("sn", SfmTarget::SenseNumber)
+            .with_marker("de", SfmTarget::Definition)
+            .with_marker("ge", SfmTarget::HeadwordTranslation(Some("en".to_string())))
+            .with_marker("xv", SfmTarget::Example)
+            .with_marker("xe", SfmTarget::ExampleTranslation(Some("en".to_string())))
+            .with_marker("ph", SfmTarget::Pronunciation)
+            .with_marker("et", SfmTarget::Etymology)
+            .with_marker("_sh", SfmTarget::Ignore)
+            .with_marker("dt", SfmTarget::Ignore)
+    }
+
+    /// Map a marker, replacing any existing mapping
+    pub fn with_marker(mut self, marker : &str, target : SfmTarget) -> SfmMapping {
+        self.markers.insert(marker.to_string(), target);
+        self
+    }
+
+    /// Remove the mapping of a marker, so that it is reported
+    pub fn without_marker(mut self, marker : &str) -> SfmMapping {
+        self.markers.remove(marker);
+        self
+    }
+
+    /// Add the mappings of a table with a marker and its target on each
+    /// line, separated by a tab. The targets are `headword`, which makes the
+    /// marker the record marker, `homographNumber`, `partOfSpeech`,
+    /// `senseNumber`, `definition`, `headwordTranslation`, `example`,
+    /// `exampleTranslation`, `pronunciation`, `label`, `etymology` and
+    /// `ignore`, and translations may be followed by a space and their
+    /// language. Empty lines and lines starting with `#` are skipped.
+    pub fn read_mapping<R : BufRead>(mut self, input : R) -> Result<SfmMapping> {
+        for (i, line) in input.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (marker, target) = match line.split_once('\t') {
+                Some((marker, target)) => (marker.trim().trim_start_matches('\\'), target.trim()),
+                None => return Err(SfmError::InvalidMapping(i + 1, line)),
+            };
+            let (name, lang) = match target.split_once(' ') {
+                Some((name, lang)) => (name, Some(lang.trim().to_string())),
+                None => (target, None),
+            };
+            let target = match (name, lang) {
+                ("headword", None) => SfmTarget::Headword,
+                ("homographNumber", None) => SfmTarget::HomographNumber,
+                ("partOfSpeech", None) => SfmTarget::PartOfSpeech,
+                ("senseNumber", None) => SfmTarget::SenseNumber,
+                ("definition", None) => SfmTarget::Definition,
+                ("headwordTranslation", lang) => SfmTarget::HeadwordTranslation(lang),
+                ("example", None) => SfmTarget::Example,
+                ("exampleTranslation", lang) => SfmTarget::ExampleTranslation(lang),
+                ("pronunciation", None) => SfmTarget::Pronunciation,
+                ("label", None) => SfmTarget::Label,
+                ("etymology", None) => SfmTarget::Etymology,
+                ("ignore", None) => SfmTarget::Ignore,
+                _ => return Err(SfmError::InvalidMapping(i + 1, line)),
+            };
+            if target == SfmTarget::Headword {
+                self.markers.remove(&self.record_marker);
+                self.record_marker = marker.to_string();
+            }
+            self = self.with_marker(marker, target);
+        }
+        Ok(self)
+    }
+}
+
+impl Default for SfmMapping {
+    fn default() -> SfmMapping {
+        SfmMapping::mdf()
+    }
+}
+
+/// A field of an SFM file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SfmField {
+    pub marker: String,
+    pub value: String,
+    pub line: usize,
+}
+
+/// Split an SFM file into fields. Lines that do not start with a marker
+/// continue the previous field.
+pub fn read_fields(text : &str) -> Vec<SfmField> {
+    let mut fields : Vec<SfmField> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(rest) = line.strip_prefix('\\') {
+            let (marker, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            fields.push(SfmField {
+                marker: marker.to_string(),
+                value: value.trim().to_string(),
+                line: index + 1,
+            });
+        } else if let Some(field) = fields.last_mut() {
+            let line = line.trim();
+            if !line.is_empty() {
+                if !field.value.is_empty() {
+                    field.value.push(' ');
+                }
+                field.value.push_str(line);
+            }
+        }
+    }
+    fields
+}
+
+/// Whether a document looks like an SFM file, that is its first line starts
+/// with a marker, such as `\_sh` or the record marker of any mapping,
+/// followed by a space or the end of the line. TeX commands such as
+/// `\documentclass{book}` are not markers.
+pub fn is_sfm(content : &[u8]) -> bool {
+    let text = String::from_utf8_lossy(&content[..content.len().min(256)]);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let Some(rest) = text.strip_prefix('\\') else {
+        return false;
+    };
+    let marker_len = rest.find(|c : char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+    marker_len > 0 && rest[marker_len..].chars().next().is_none_or(char::is_whitespace)
+}
+
+/// Read an SFM file as a resource in the given language
+pub fn read_sfm<R : Read>(mut input : R, mapping : &SfmMapping, lang_code : &str) -> Result<(LexicographicResource, ConversionReport)> {
+    let mut content = Vec::new();
+    input.read_to_end(&mut content)?;
+    let text = String::from_utf8_lossy(&content);
+    let mut report = ConversionReport::new();
+    let mut resource = LexicographicResource {
+        lang_code: LangCode(lang_code.to_string()),
+        ..LexicographicResource::default()
+    };
+    let mut entry : Option<Entry> = None;
+    for field in read_fields(&text) {
+        let here = format!("line {}", field.line);
+        let target = match mapping.markers.get(&field.marker) {
+            Some(target) => target,
+            None => {
+                report.warn(&here, format!("\\{} not mapped", field.marker));
+                continue;
+            }
+        };
+        if field.marker == mapping.record_marker {
+            if let Some(entry) = entry.take() {
+                resource.entries.push(entry);
+            }
+            entry = Some(Entry {
+                headword: field.value.clone(),
+                ..Entry::default()
+            });
+            continue;
+        }
+        if *target == SfmTarget::Ignore {
+            continue;
+        }
+        let entry = match entry.as_mut() {
+            Some(entry) => entry,
+            None => {
+                report.warn(&here, format!("\\{} before the first \\{} not mapped", field.marker, mapping.record_marker));
+                continue;
+            }
+        };
+        if let Err(message) = apply(entry, target, &field.value) {
+            report.warn(&here, format!("\\{} {}", field.marker, message));
+        }
+    }
+    if let Some(entry) = entry.take() {
+        resource.entries.push(entry);
+    }
+    assign_ids(&mut resource.entries);
+    for entry in resource.entries.iter() {
+        for pos in entry.parts_of_speech.iter() {
+            if !resource.part_of_speech_tags.iter().any(|t| &t.tag == pos) {
+                resource.part_of_speech_tags.push(PartOfSpeechTag {
+                    tag: pos.clone(),
+                    ..PartOfSpeechTag::default()
+                });
+            }
+        }
+    }
+    // The translation languages in the order they first appear
+    for sense in resource.entries.iter().flat_map(|e| e.senses.iter()) {
+        let langs = sense.headword_translations.iter().map(|t| &t.lang_code)
+            .chain(sense.examples.iter().flat_map(|e| e.example_translations.iter().map(|t| &t.lang_code)));
+        for LangCode(lang) in langs.flatten() {
+            if !resource.translation_languages.contains(lang) {
+                resource.translation_languages.push(lang.clone());
+            }
+        }
+    }
+    Ok((resource, report))
+}
+
+/// The last sense of the entry, starting one if there is none
+fn current_sense(entry : &mut Entry) -> &mut Sense {
+    if entry.senses.is_empty() {
+        entry.senses.push(Sense::default());
+    }
+    entry.senses.last_mut().unwrap()
+}
+
+/// Apply a field to an entry, returning why it could not be applied
+fn apply(entry : &mut Entry, target : &SfmTarget, value : &str) -> std::result::Result<(), String> {
+    match target {
+        SfmTarget::Headword | SfmTarget::Ignore => (),
+        SfmTarget::HomographNumber => {
+            entry.homograph_number = Some(value.parse()
+                .map_err(|_| format!("homograph number {} is not a number", value))?);
+        },
+        SfmTarget::PartOfSpeech => {
+            if !entry.parts_of_speech.iter().any(|p| p == value) {
+                entry.parts_of_speech.push(value.to_string());
+            }
+        },
+        SfmTarget::SenseNumber => {
+            // A sense number right at the start of an entry numbers the
+            // sense that was started implicitly, if it is still empty
+            if entry.senses.last() != Some(&Sense::default()) {
+                entry.senses.push(Sense::default());
+            }
+        },
+        SfmTarget::Definition => current_sense(entry).definitions.push(Definition {
+            text: value.to_string(),
+            ..Definition::default()
+        }),
+        SfmTarget::HeadwordTranslation(lang) => current_sense(entry).headword_translations.push(HeadwordTranslation {
+            text: value.to_string(),
+            lang_code: lang.clone().map(LangCode),
+            ..HeadwordTranslation::default()
+        }),
+        SfmTarget::Example => current_sense(entry).examples.push(Example {
+            text: value.to_string(),
+            ..Example::default()
+        }),
+        SfmTarget::ExampleTranslation(lang) => {
+            let example = current_sense(entry).examples.last_mut()
+                .ok_or_else(|| "without a preceding example not mapped".to_string())?;
+            example.example_translations.push(ExampleTranslation {
+                text: value.to_string(),
+                lang_code: lang.clone().map(LangCode),
+                ..ExampleTranslation::default()
+            });
+        },
+        SfmTarget::Pronunciation => entry.pronunciations.push(Pronunciation {
+            transcriptions: vec![Transcription {
+                text: value.to_string(),
+                scheme: None,
+            }],
+            ..Pronunciation::default()
+        }),
+        SfmTarget::Label => match entry.senses.last_mut() {
+            Some(sense) => sense.labels.push(value.to_string()),
+            None => entry.labels.push(value.to_string()),
+        },
+        SfmTarget::Etymology => entry.etymologies.push(Etymology {
+            description: Some(value.to_string()),
+            etymons: Vec::new(),
+        }),
+    }
+    Ok(())
+}
+
+/// Give each entry an id made from its headword and homograph number and
+/// each sense an id made from the entry id and its number, adding a number
+/// to those already used
+fn assign_ids(entries : &mut [Entry]) {
+    let mut used = HashSet::new();
+    let mut fresh = |base : String| {
+        let mut id = base.clone();
+        let mut n = 1;
+        while used.contains(&id) {
+            n += 1;
+            id = format!("{}-{}", base, n);
+        }
+        used.insert(id.clone());
+        id
+    };
+    for entry in entries.iter_mut() {
+        let mut base = entry.headword.split_whitespace().collect::<Vec<_>>().join("-");
+        if let Some(n) = entry.homograph_number {
+            base = format!("{}-{}", base, n);
+        }
+        let id = fresh(base);
+        for (i, sense) in entry.senses.iter_mut().enumerate() {
+            sense.id = Some(fresh(format!("{}-s{}", id, i + 1)));
+        }
+        entry.id = Some(id);
+    }
+}
+
+/// The SFM format for use in a `FormatRegistry`. SFM files can only be read.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SfmFormat {
+    pub mapping: SfmMapping,
+    /// The language of the headwords, `und` if not given
+    pub lang_code: Option<String>,
+}
+
+impl ResourceReader for SfmFormat {
+    fn read_resource(&self, input : &mut dyn Read, options : &ReadOptions) -> std::result::Result<LexicographicResource, ParseError> {
+        Ok(self.read_resource_with_report(input, options)?.0)
+    }
+
+    fn read_resource_with_report(&self, input : &mut dyn Read, _options : &ReadOptions) -> std::result::Result<(LexicographicResource, ConversionReport), ParseError> {
+        Ok(read_sfm(input, &self.mapping, self.lang_code.as_deref().unwrap_or("und"))?)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SfmError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Line {0} of the marker mapping is not a marker and a target separated by a tab: {1}")]
+    InvalidMapping(usize, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SAMPLE : &str = "\\_sh v3.0  400  MDF 4.0
+
+\\lx haus
+\\hm 1
+\\ph haus
+\\ps n
+\\sn 1
+\\ge house
+\\de a building where people
+  live
+\\xv Mi go long haus.
+\\xe I go home.
+\\sn 2
+\\ge home
+\\nt check this
+\\et German Haus
+\\dt 12/Jan/2005
+
+\\lx kaikai
+\\ps v
+\\ge eat
+\\ps n
+\\sn 2
+\\ge food
+\\xe food translation
+";
+
+    #[test]
+    fn test_read_fields() {
+        let fields = read_fields(SAMPLE);
+        assert_eq!(fields[0].marker, "_sh");
+        let de = fields.iter().find(|f| f.marker == "de").unwrap();
+        assert_eq!(de.value, "a building where people live");
+        assert_eq!(de.line, 9);
+    }
+
+    #[test]
+    fn test_read_mdf() {
+        let (resource, report) = read_sfm(SAMPLE.as_bytes(), &SfmMapping::mdf(), "tpi").unwrap();
+        assert_eq!(resource.lang_code.0, "tpi");
+        assert_eq!(resource.entries.len(), 2);
+        let haus = &resource.entries[0];
+        assert_eq!(haus.id, Some("haus-1".to_string()));
+        assert_eq!(haus.homograph_number, Some(1));
+        assert_eq!(haus.pronunciations[0].transcriptions[0].text, "haus");
+        assert_eq!(haus.senses.len(), 2);
+        assert_eq!(haus.senses[0].id, Some("haus-1-s1".to_string()));
+        assert_eq!(haus.senses[0].definitions[0].text, "a building where people live");
+        assert_eq!(haus.senses[0].examples[0].example_translations[0].text, "I go home.");
+        assert_eq!(haus.senses[1].headword_translations[0].lang_code, Some(LangCode("en".to_string())));
+        assert_eq!(haus.etymologies[0].description, Some("German Haus".to_string()));
+        let kaikai = &resource.entries[1];
+        assert_eq!(kaikai.parts_of_speech, vec!["v", "n"]);
+        assert_eq!(kaikai.senses.len(), 2);
+        let tags : Vec<&str> = resource.part_of_speech_tags.iter().map(|t| t.tag.as_str()).collect();
+        assert_eq!(tags, vec!["n", "v"]);
+        assert_eq!(resource.translation_languages, vec!["en"]);
+        let warnings : Vec<String> = report.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(warnings, vec![
+            "line 15: \\nt not mapped",
+            "line 25: \\xe without a preceding example not mapped",
+        ]);
+    }
+
+    #[test]
+    fn test_custom_mapping() {
+        let mapping = SfmMapping::mdf()
+            .with_marker("nt", SfmTarget::Ignore)
+            .with_marker("ge", SfmTarget::HeadwordTranslation(Some("de".to_string())))
+            .without_marker("et");
+        let (resource, report) = read_sfm(SAMPLE.as_bytes(), &mapping, "tpi").unwrap();
+        assert_eq!(resource.entries[0].senses[0].headword_translations[0].lang_code, Some(LangCode("de".to_string())));
+        assert!(resource.entries[0].etymologies.is_empty());
+        assert_eq!(report.warnings[0].to_string(), "line 16: \\et not mapped");
+        assert_eq!(resource.translation_languages, vec!["de", "en"]);
+    }
+
+    #[test]
+    fn test_read_mapping() {
+        let table = "# MDF with other languages\n\nge\theadwordTranslation tr\n\\xe\texampleTranslation af\net\tignore\n";
+        let mapping = SfmMapping::mdf().read_mapping(table.as_bytes()).unwrap();
+        assert_eq!(mapping.markers.get("xe"), Some(&SfmTarget::ExampleTranslation(Some("af".to_string()))));
+        let (resource, _) = read_sfm(SAMPLE.as_bytes(), &mapping, "tpi").unwrap();
+        assert!(resource.entries[0].etymologies.is_empty());
+        assert_eq!(resource.translation_languages, vec!["tr", "af"]);
+        let mapping = SfmMapping::mdf().read_mapping("hw\theadword\n".as_bytes()).unwrap();
+        assert_eq!(mapping.record_marker, "hw");
+        assert!(!mapping.markers.contains_key("lx"));
+        assert!(matches!(SfmMapping::mdf().read_mapping("ge gloss".as_bytes()), Err(SfmError::InvalidMapping(1, _))));
+        assert!(matches!(SfmMapping::mdf().read_mapping("\n\nge\tgloss".as_bytes()), Err(SfmError::InvalidMapping(3, _))));
+    }
+
+    #[test]
+    fn test_homograph_ids() {
+        let (resource, _) = read_sfm("\\lx haus\n\\ps n\n\\ge house\n\n\\lx haus\n\\ps v\n\\ge live\n\n\\lx haus\n\\hm 2\n\\ge building\n".as_bytes(),
+            &SfmMapping::mdf(), "tpi").unwrap();
+        assert_eq!(resource.entries.iter().map(|e| e.id.as_deref().unwrap()).collect::<Vec<_>>(), vec!["haus", "haus-2", "haus-2-2"]);
+        assert_eq!(resource.entries[2].senses[0].id.as_deref(), Some("haus-2-2-s1"));
+        assert!(resource.validate().is_ok());
+    }
+
+    #[test]
+    fn test_is_sfm() {
+        assert!(is_sfm(SAMPLE.as_bytes()));
+        assert!(is_sfm(b"\\lx cat\n"));
+        assert!(is_sfm(b"\\hw cat\n\\gl Katze\n"));
+        assert!(!is_sfm(b"\\documentclass{book}\n"));
+        assert!(!is_sfm(b"<lift/>"));
+    }
+}
@@ -0,0 +1,349 @@
+//! Validation of DMLEX RDF against SHACL shapes
+//!
+//! Only the subset of SHACL used by the bundled shapes is supported: class
+//! targets, property shapes with a simple predicate path and the `minCount`,
+//! `maxCount`, `nodeKind`, `in`, `pattern` and `node` constraints.
+use crate::rdf::{get_zero_one_str, get_zero_one_usize, RdfError, Result, DMLEX};
+use regex::Regex;
+use sophia::graph::Graph;
+use sophia::graph::inmem::LightGraph;
+use sophia::ns::Namespace;
+use sophia::term::ns::rdf;
+use sophia::term::{CopyTerm, TTerm, Term, TermKind};
+use sophia::triple::Triple;
+use sophia::triple::stream::TripleSource;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// The SHACL shapes for the DMLEX vocabulary as read by this crate
+pub static SHAPES : &str = include_str!("../shapes/dmlex-shapes.ttl");
+pub static SH : &str = "http://www.w3.org/ns/shacl#";
+
+/// A single node that does not conform to a shape
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The node that was validated
+    pub focus_node: String,
+    /// The property of the focus node, if the violation concerns a property
+    pub path: Option<String>,
+    /// The shape that was violated
+    pub shape: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{} {} ({}): {}", self.focus_node, path, self.shape, self.message)
+        } else {
+            write!(f, "{} ({}): {}", self.focus_node, self.shape, self.message)
+        }
+    }
+}
+
+/// The result of validating a graph, listing every violation found
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValidationReport {
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub fn conforms(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} shape violation(s)", self.violations.len())?;
+        for violation in &self.violations {
+            write!(f, "\n  {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the bundled DMLEX shapes
+pub fn shapes_graph() -> LightGraph {
+    sophia::parser::turtle::parse_str(SHAPES).collect_triples()
+        .expect("DMLEX shapes are invalid")
+}
+
+/// The bundled DMLEX shapes, which are only read once
+fn bundled_shapes() -> &'static HashMap<Term<String>, NodeShape> {
+    static SHAPES_CELL : OnceLock<HashMap<Term<String>, NodeShape>> = OnceLock::new();
+    SHAPES_CELL.get_or_init(|| read_shapes(&shapes_graph()).expect("DMLEX shapes are invalid"))
+}
+
+/// Validate a graph against the bundled DMLEX shapes
+pub fn validate<G : Graph>(g : &G) -> Result<ValidationReport> {
+    Ok(check(g, bundled_shapes()))
+}
+
+/// Validate a graph against the node shapes in a shapes graph
+pub fn validate_with_shapes<G : Graph, S : Graph>(g : &G, shapes : &S) -> Result<ValidationReport> {
+    Ok(check(g, &read_shapes(shapes)?))
+}
+
+fn check<G : Graph>(g : &G, shapes : &HashMap<Term<String>, NodeShape>) -> ValidationReport {
+    let mut queue = VecDeque::new();
+    for (id, shape) in shapes.iter() {
+        for class in shape.targets.iter() {
+            for triple in g.triples_with_po(&rdf::type_, class) {
+                queue.push_back((Term::copy(triple.unwrap().s()), id.clone()));
+            }
+        }
+    }
+    let mut visited = HashSet::new();
+    let mut violations = Vec::new();
+    while let Some((focus, shape_id)) = queue.pop_front() {
+        if !visited.insert((focus.clone(), shape_id.clone())) {
+            continue;
+        }
+        let shape = &shapes[&shape_id];
+        for property in shape.properties.iter() {
+            let mut violation = |message : String| violations.push(Violation {
+                focus_node: term_str(&focus),
+                path: Some(term_str(&property.path)),
+                shape: term_str(&shape_id),
+                message,
+            });
+            let values : Vec<Term<String>> = g.triples_with_sp(&focus, &property.path)
+                .map(|triple| Term::copy(triple.unwrap().o()))
+                .collect();
+            if let Some(min_count) = property.min_count {
+                if values.len() < min_count {
+                    violation(format!("Expected at least {} value(s) but found {}", min_count, values.len()));
+                }
+            }
+            if let Some(max_count) = property.max_count {
+                if values.len() > max_count {
+                    violation(format!("Expected at most {} value(s) but found {}", max_count, values.len()));
+                }
+            }
+            for value in values.iter() {
+                if let Some(node_kind) = &property.node_kind {
+                    if !node_kind.matches(value) {
+                        violation(format!("Value {} is not of kind {:?}", term_str(value), node_kind));
+                    }
+                }
+                if let Some(allowed) = &property.allowed {
+                    if !allowed.contains(value) {
+                        violation(format!("Value {} is not one of {}", term_str(value),
+                            allowed.iter().map(term_str).collect::<Vec<_>>().join(", ")));
+                    }
+                }
+                if let Some(pattern) = &property.pattern {
+                    if value.kind() == TermKind::BlankNode || !pattern.is_match(&value.value()) {
+                        violation(format!("Value {} does not match pattern {}", term_str(value), pattern));
+                    }
+                }
+                if let Some(node) = &property.node {
+                    if value.kind() != TermKind::Literal {
+                        queue.push_back((value.clone(), node.clone()));
+                    }
+                }
+            }
+        }
+    }
+    ValidationReport { violations }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NodeKind {
+    Iri,
+    Literal,
+    BlankNode,
+    BlankNodeOrIri,
+    BlankNodeOrLiteral,
+    IriOrLiteral,
+}
+
+impl NodeKind {
+    fn from_local(name : &str) -> Option<NodeKind> {
+        match name {
+            "IRI" => Some(NodeKind::Iri),
+            "Literal" => Some(NodeKind::Literal),
+            "BlankNode" => Some(NodeKind::BlankNode),
+            "BlankNodeOrIRI" => Some(NodeKind::BlankNodeOrIri),
+            "BlankNodeOrLiteral" => Some(NodeKind::BlankNodeOrLiteral),
+            "IRIOrLiteral" => Some(NodeKind::IriOrLiteral),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, term : &Term<String>) -> bool {
+        let kind = term.kind();
+        match self {
+            NodeKind::Iri => kind == TermKind::Iri,
+            NodeKind::Literal => kind == TermKind::Literal,
+            NodeKind::BlankNode => kind == TermKind::BlankNode,
+            NodeKind::BlankNodeOrIri => kind == TermKind::BlankNode || kind == TermKind::Iri,
+            NodeKind::BlankNodeOrLiteral => kind == TermKind::BlankNode || kind == TermKind::Literal,
+            NodeKind::IriOrLiteral => kind == TermKind::Iri || kind == TermKind::Literal,
+        }
+    }
+}
+
+struct NodeShape {
+    targets: Vec<Term<String>>,
+    properties: Vec<PropertyShape>,
+}
+
+struct PropertyShape {
+    path: Term<String>,
+    min_count: Option<usize>,
+    max_count: Option<usize>,
+    node_kind: Option<NodeKind>,
+    allowed: Option<Vec<Term<String>>>,
+    pattern: Option<Regex>,
+    node: Option<Term<String>>,
+}
+
+fn read_shapes<S : Graph>(g : &S) -> Result<HashMap<Term<String>, NodeShape>> {
+    let sh = Namespace::new(SH).expect("SHACL namespace is invalid");
+    let mut shapes = HashMap::new();
+    for triple in g.triples_with_po(&rdf::type_, &sh.get("NodeShape")?) {
+        let id = Term::copy(triple.unwrap().s());
+        let targets = objects(g, &id, &sh.get("targetClass")?);
+        let mut properties = Vec::new();
+        for prop_id in objects(g, &id, &sh.get("property")?) {
+            properties.push(read_property_shape(g, &prop_id, &sh)?);
+        }
+        shapes.insert(id, NodeShape { targets, properties });
+    }
+    for (id, shape) in shapes.iter() {
+        for property in shape.properties.iter() {
+            if let Some(node) = &property.node {
+                if !shapes.contains_key(node) {
+                    return Err(RdfError::InvalidShape(term_str(id),
+                        format!("sh:node refers to unknown shape {}", term_str(node))));
+                }
+            }
+        }
+    }
+    Ok(shapes)
+}
+
+fn read_property_shape<S : Graph>(g : &S, id : &Term<String>, sh : &Namespace<&str>) -> Result<PropertyShape> {
+    let path = match objects(g, id, &sh.get("path")?).pop() {
+        Some(path) if path.kind() == TermKind::Iri => path,
+        _ => return Err(RdfError::InvalidShape(term_str(id), "sh:path must be a single IRI".to_string())),
+    };
+    let node_kind = match objects(g, id, &sh.get("nodeKind")?).pop() {
+        Some(kind) => Some(kind.value().strip_prefix(SH)
+            .and_then(NodeKind::from_local)
+            .ok_or_else(|| RdfError::InvalidShape(term_str(id), format!("Unsupported node kind {}", term_str(&kind))))?),
+        None => None,
+    };
+    let allowed = match objects(g, id, &sh.get("in")?).pop() {
+        Some(list) => Some(read_list(g, id, list)?),
+        None => None,
+    };
+    let pattern = match get_zero_one_str(g, id, &sh.get("pattern")?)? {
+        Some(pattern) => Some(Regex::new(&pattern)
+            .map_err(|e| RdfError::InvalidShape(term_str(id), e.to_string()))?),
+        None => None,
+    };
+    Ok(PropertyShape {
+        path,
+        min_count: get_zero_one_usize(g, id, &sh.get("minCount")?)?,
+        max_count: get_zero_one_usize(g, id, &sh.get("maxCount")?)?,
+        node_kind,
+        allowed,
+        pattern,
+        node: objects(g, id, &sh.get("node")?).pop(),
+    })
+}
+
+fn read_list<S : Graph>(g : &S, id : &Term<String>, mut node : Term<String>) -> Result<Vec<Term<String>>> {
+    let mut items = Vec::new();
+    while node != rdf::nil {
+        match (objects(g, &node, &rdf::first).pop(), objects(g, &node, &rdf::rest).pop()) {
+            (Some(first), Some(rest)) => {
+                items.push(first);
+                node = rest;
+            },
+            _ => return Err(RdfError::InvalidShape(term_str(id), "sh:in is not a well-formed list".to_string())),
+        }
+    }
+    Ok(items)
+}
+
+fn objects<G : Graph, P : TTerm + ?Sized>(g : &G, subj : &Term<String>, prop : &P) -> Vec<Term<String>> {
+    g.triples_with_sp(subj, prop)
+        .map(|triple| Term::copy(triple.unwrap().o()))
+        .collect()
+}
+
+fn term_str(term : &Term<String>) -> String {
+    match term.kind() {
+        TermKind::Iri => {
+            let iri = term.value().to_string();
+            if let Some(local) = iri.strip_prefix(DMLEX) {
+                format!("dmlex:{}", local)
+            } else if let Some(local) = iri.strip_prefix(SH) {
+                format!("sh:{}", local)
+            } else {
+                format!("<{}>", iri)
+            }
+        },
+        TermKind::BlankNode => format!("_:{}", term.value()),
+        TermKind::Literal => format!("{:?}", term.value().to_string()),
+        TermKind::Variable => format!("?{}", term.value()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    fn read_graph(fname : &str) -> LightGraph {
+        let mut input = BufReader::new(File::open(fname).unwrap());
+        sophia::parser::turtle::parse_bufread(&mut input).collect_triples().unwrap()
+    }
+
+    #[test]
+    fn test_shapes_are_valid() {
+        let shapes = read_shapes(&shapes_graph()).unwrap();
+        assert!(shapes.len() > 30);
+    }
+
+    #[test]
+    fn test_examples_conform() {
+        for i in 0..25 {
+            let fname = format!("examples/{}.rdf", i);
+            let report = validate(&read_graph(&fname)).unwrap();
+            assert!(report.conforms(), "{}: {}", fname, report);
+        }
+    }
+
+    #[test]
+    fn test_all_violations_reported() {
+        let g : LightGraph = sophia::parser::turtle::parse_str(r#"
+            @prefix dmlex: <https://docs.oasis-open.org/lexidma/dmlex/v1.0/schemas/RDF/dmlex.ttl#> .
+            @prefix ex: <http://example.com/> .
+            ex:lr a dmlex:LexicographicResource ;
+                dmlex:entry ex:cat .
+            ex:cat a dmlex:Entry ;
+                dmlex:headword "cat", "kitten" ;
+                dmlex:sense [ dmlex:listingOrder "first" ] .
+            ex:rt dmlex:type "synonyms" .
+            ex:lr dmlex:relationType [
+                dmlex:type "synonyms" ;
+                dmlex:memberType [ dmlex:type dmlex:word ] ] .
+        "#).collect_triples().unwrap();
+        let report = validate(&g).unwrap();
+        let messages : Vec<(String, Option<String>)> = report.violations.iter()
+            .map(|v| (v.shape.clone(), v.path.clone()))
+            .collect();
+        assert_eq!(report.violations.len(), 4, "{}", report);
+        assert!(messages.contains(&("<https://github.com/jmccrae/dmlex-converter/shapes#LexicographicResource>".to_string(), Some("dmlex:langCode".to_string()))));
+        assert!(messages.contains(&("<https://github.com/jmccrae/dmlex-converter/shapes#Entry>".to_string(), Some("dmlex:headword".to_string()))));
+        assert!(messages.contains(&("<https://github.com/jmccrae/dmlex-converter/shapes#Sense>".to_string(), Some("dmlex:listingOrder".to_string()))));
+        assert!(messages.contains(&("<https://github.com/jmccrae/dmlex-converter/shapes#MemberType>".to_string(), Some("dmlex:type".to_string()))));
+    }
+}
@@ -0,0 +1,205 @@
+//! Storage of a resource as one file per entry, as used by editorial systems
+//!
+//! A resource is split into a directory with a header file `resource.<ext>`
+//! holding the resource without its entries, that is its properties, tags,
+//! relation types and relations, and an `entries` directory with a file for
+//! each entry written with the entry writer of the format. The files are
+//! named after the identifier of the entry if it is safe as a file name and
+//! otherwise after the fragment path of the entry, which is derived from its
+//! headword, homograph number and parts of speech.
+//!
+//! Assembling reads the header and the entry files back. As the order of the
+//! entries is not stored, they are sorted alphabetically for the language of
+//! the resource and then by homograph number, parts of speech and identifier.
+use crate::collation::Collation;
+use crate::model::*;
+use crate::registry::{FormatRegistry, ReadOptions, WriteOptions};
+use crate::validate::Validate;
+use crate::{ParseError, WriteError};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+type Result<T> = std::result::Result<T, SplitError>;
+
+/// The name of the header file, without the extension
+pub const HEADER : &str = "resource";
+
+/// The directory of the entry files
+pub const ENTRIES_DIR : &str = "entries";
+
+/// The file name of an entry, without the extension
+fn file_name(entry : &Entry) -> String {
+    match &entry.id {
+        Some(id) if !id.is_empty() && !id.starts_with('.')
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') => id.clone(),
+        _ => entry.frag_path(),
+    }
+}
+
+/// Split a resource into a header and entry files in `format` in a
+/// directory. Files of that format in the entries directory that do not
+/// belong to an entry of the resource are removed, so that splitting again
+/// after a change leaves no stale entries behind.
+pub fn split<P : AsRef<Path>>(dir : P, resource : &LexicographicResource, registry : &FormatRegistry, format : &str,
+        options : &WriteOptions) -> Result<()> {
+    if registry.writer(format).is_none() {
+        return Err(SplitError::UnknownFormat(format.to_string()));
+    }
+    let entries_dir = dir.as_ref().join(ENTRIES_DIR);
+    // Each entry is written to memory before its file is created, so that
+    // nothing is written in a format that cannot write single entries
+    let mut used = HashSet::new();
+    for entry in resource.entries.iter() {
+        let mut content = Vec::new();
+        registry.write_entry(format, &mut content, entry, options)?;
+        if used.is_empty() {
+            std::fs::create_dir_all(&entries_dir)?;
+        }
+        // Names are compared without case for case-insensitive file systems
+        let name = file_name(entry);
+        let mut unique = name.clone();
+        let mut n = 1;
+        while !used.insert(unique.to_lowercase()) {
+            n += 1;
+            unique = format!("{}-{}", name, n);
+        }
+        std::fs::write(entries_dir.join(format!("{}.{}", unique, format)), content)?;
+    }
+    if used.is_empty() {
+        std::fs::create_dir_all(&entries_dir)?;
+    }
+    let header = LexicographicResource { entries: Vec::new(), ..resource.clone() };
+    let mut output = BufWriter::new(File::create(dir.as_ref().join(format!("{}.{}", HEADER, format)))?);
+    registry.write(format, &mut output, &header, options)?;
+    for path in entry_files(&entries_dir, format)? {
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_lowercase();
+        if !used.contains(&stem) {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// The files of a format in the entries directory
+fn entry_files(entries_dir : &Path, format : &str) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for file in std::fs::read_dir(entries_dir)? {
+        let path = file?.path();
+        if path.is_file() && path.extension().and_then(|e| e.to_str()) == Some(format) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The format of the header file of a split resource, if it is one the
+/// registry can read
+pub fn header_format<P : AsRef<Path>>(dir : P, registry : &FormatRegistry) -> Option<String> {
+    registry.reader_names().into_iter()
+        .find(|format| dir.as_ref().join(format!("{}.{}", HEADER, format)).is_file())
+        .map(|format| format.to_string())
+}
+
+/// Assemble a resource from a directory written by `split`, in `format` or
+/// in the format of the header file if it is not given
+pub fn assemble<P : AsRef<Path>>(dir : P, registry : &FormatRegistry, format : Option<&str>, options : &ReadOptions) -> Result<LexicographicResource> {
+    let format = match format {
+        Some(format) => format.to_string(),
+        None => header_format(&dir, registry).ok_or_else(|| SplitError::NoHeader(dir.as_ref().to_path_buf()))?,
+    };
+    if registry.reader(&format).is_none() {
+        return Err(SplitError::UnknownFormat(format));
+    }
+    let header = dir.as_ref().join(format!("{}.{}", HEADER, format));
+    let mut resource = registry.parse(&format, &mut File::open(&header)?, options)?;
+    let entries_dir = dir.as_ref().join(ENTRIES_DIR);
+    if entries_dir.is_dir() {
+        for path in entry_files(&entries_dir, &format)? {
+            let entry = registry.parse_entry(&format, &mut File::open(&path)?, options)
+                .map_err(|e| SplitError::Entry(path.clone(), e))?;
+            resource.entries.push(entry);
+        }
+    }
+    let collation = Collation::for_resource(&resource);
+    resource.entries.sort_by_cached_key(|e| (collation.key(&e.headword), e.homograph_number,
+        e.parts_of_speech.clone(), e.id.clone()));
+    Ok(resource)
+}
+
+#[derive(Error, Debug)]
+pub enum SplitError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Parse error: {0}")]
+    Parse(#[from] ParseError),
+    #[error("Could not parse entry {0}: {1}")]
+    Entry(PathBuf, ParseError),
+    #[error("Write error: {0}")]
+    Write(#[from] WriteError),
+    #[error("Unknown format {0}")]
+    UnknownFormat(String),
+    #[error("No {HEADER} file found in {0}")]
+    NoHeader(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+
+    fn temp_dir(name : &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dmlex-split-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let registry = FormatRegistry::default();
+        let resource = parse(File::open("examples/14.xml").unwrap(), &Format::XML, &None).unwrap();
+        for format in ["xml", "json"] {
+            let dir = temp_dir(format);
+            split(&dir, &resource, &registry, format, &WriteOptions::default()).unwrap();
+            assert!(dir.join(format!("entries/die-see.{}", format)).is_file());
+            let assembled = assemble(&dir, &registry, None, &ReadOptions::default()).unwrap();
+            assert_eq!(assembled.entries.iter().map(|e| e.headword.as_str()).collect::<Vec<_>>(), vec!["Meer", "Ozean", "See"]);
+            assert_eq!(assembled.relations, resource.relations);
+            assert_eq!(assembled.relation_types, resource.relation_types);
+            assert_eq!(assembled.uri, resource.uri);
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_file_names() {
+        let registry = FormatRegistry::default();
+        let dir = temp_dir("names");
+        let mut resource = LexicographicResource {
+            lang_code: LangCode("en".to_string()),
+            entries: vec![
+                Entry { headword: "a/b".to_string(), ..Entry::default() },
+                Entry { id: Some("x".to_string()), headword: "x".to_string(), ..Entry::default() },
+                Entry { id: Some("X".to_string()), headword: "X".to_string(), ..Entry::default() },
+            ],
+            ..LexicographicResource::default()
+        };
+        split(&dir, &resource, &registry, "json", &WriteOptions::default()).unwrap();
+        let mut names : Vec<String> = std::fs::read_dir(dir.join(ENTRIES_DIR)).unwrap()
+            .map(|f| f.unwrap().file_name().to_string_lossy().to_string()).collect();
+        names.sort();
+        assert_eq!(names, vec!["X-2.json", "a%2Fb.json", "x.json"]);
+        resource.entries.truncate(2);
+        split(&dir, &resource, &registry, "json", &WriteOptions::default()).unwrap();
+        assert_eq!(std::fs::read_dir(dir.join(ENTRIES_DIR)).unwrap().count(), 2);
+        assert!(matches!(split(&dir, &resource, &registry, "dix", &WriteOptions::default()),
+            Err(SplitError::Write(WriteError::EntryNotSupported))));
+        assert!(!dir.join(format!("{}.dix", HEADER)).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(split(&dir, &resource, &registry, "dix", &WriteOptions::default()).is_err());
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,992 @@
+//! Export and import of resources as relational SQLite databases
+//!
+//! Every object of the model is a row in its own table, linked to the object
+//! that contains it by a foreign key and ordered by a `listing_order` column.
+//! Objects that can occur in several places, such as labels, pronunciations
+//! or markers, have one nullable foreign key column for each kind of owner,
+//! exactly one of which is set. The tags of the resource share the `tag`
+//! table and are told apart by the `kind` column. A database may contain
+//! several resources, `read_sqlite` reads the first one.
+use crate::model::*;
+use crate::registry::{ReadOptions, ResourceReader, ResourceWriter, WriteOptions};
+use crate::{ParseError, WriteError};
+use rusqlite::{params, Connection, Row};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use thiserror::Error;
+
+type Result<T> = std::result::Result<T, SqliteError>;
+
+/// The first bytes of every SQLite database file
+pub const SQLITE_HEADER : &[u8] = b"SQLite format 3\0";
+
+/// The statements that create the tables of the schema
+pub const SCHEMA : &str = "
+CREATE TABLE IF NOT EXISTS resource (
+    id INTEGER PRIMARY KEY,
+    title TEXT,
+    uri TEXT,
+    lang_code TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS translation_language (
+    id INTEGER PRIMARY KEY,
+    resource_id INTEGER NOT NULL REFERENCES resource(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    lang_code TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS entry (
+    id INTEGER PRIMARY KEY,
+    resource_id INTEGER NOT NULL REFERENCES resource(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    xml_id TEXT,
+    headword TEXT NOT NULL,
+    homograph_number INTEGER
+);
+CREATE TABLE IF NOT EXISTS sense (
+    id INTEGER PRIMARY KEY,
+    entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    xml_id TEXT,
+    indicator TEXT
+);
+CREATE TABLE IF NOT EXISTS definition (
+    id INTEGER PRIMARY KEY,
+    sense_id INTEGER NOT NULL REFERENCES sense(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    definition_type TEXT
+);
+CREATE TABLE IF NOT EXISTS example (
+    id INTEGER PRIMARY KEY,
+    sense_id INTEGER NOT NULL REFERENCES sense(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    source_identity TEXT,
+    source_elaboration TEXT,
+    sound_file TEXT
+);
+CREATE TABLE IF NOT EXISTS example_translation (
+    id INTEGER PRIMARY KEY,
+    example_id INTEGER NOT NULL REFERENCES example(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    lang_code TEXT,
+    sound_file TEXT
+);
+CREATE TABLE IF NOT EXISTS headword_translation (
+    id INTEGER PRIMARY KEY,
+    sense_id INTEGER NOT NULL REFERENCES sense(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    lang_code TEXT
+);
+CREATE TABLE IF NOT EXISTS headword_explanation (
+    id INTEGER PRIMARY KEY,
+    sense_id INTEGER NOT NULL REFERENCES sense(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    lang_code TEXT
+);
+CREATE TABLE IF NOT EXISTS inflected_form (
+    id INTEGER PRIMARY KEY,
+    entry_id INTEGER REFERENCES entry(id) ON DELETE CASCADE,
+    headword_translation_id INTEGER REFERENCES headword_translation(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    tag TEXT
+);
+CREATE TABLE IF NOT EXISTS pronunciation (
+    id INTEGER PRIMARY KEY,
+    entry_id INTEGER REFERENCES entry(id) ON DELETE CASCADE,
+    inflected_form_id INTEGER REFERENCES inflected_form(id) ON DELETE CASCADE,
+    headword_translation_id INTEGER REFERENCES headword_translation(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    sound_file TEXT
+);
+CREATE TABLE IF NOT EXISTS transcription (
+    id INTEGER PRIMARY KEY,
+    pronunciation_id INTEGER NOT NULL REFERENCES pronunciation(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    scheme TEXT
+);
+CREATE TABLE IF NOT EXISTS placeholder_marker (
+    id INTEGER PRIMARY KEY,
+    entry_id INTEGER REFERENCES entry(id) ON DELETE CASCADE,
+    headword_translation_id INTEGER REFERENCES headword_translation(id) ON DELETE CASCADE,
+    headword_explanation_id INTEGER REFERENCES headword_explanation(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    start_index INTEGER NOT NULL,
+    end_index INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS headword_marker (
+    id INTEGER PRIMARY KEY,
+    definition_id INTEGER REFERENCES definition(id) ON DELETE CASCADE,
+    example_id INTEGER REFERENCES example(id) ON DELETE CASCADE,
+    example_translation_id INTEGER REFERENCES example_translation(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    start_index INTEGER NOT NULL,
+    end_index INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS collocate_marker (
+    id INTEGER PRIMARY KEY,
+    definition_id INTEGER REFERENCES definition(id) ON DELETE CASCADE,
+    example_id INTEGER REFERENCES example(id) ON DELETE CASCADE,
+    example_translation_id INTEGER REFERENCES example_translation(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    start_index INTEGER NOT NULL,
+    end_index INTEGER NOT NULL,
+    xml_id TEXT,
+    lemma TEXT
+);
+CREATE TABLE IF NOT EXISTS etymology (
+    id INTEGER PRIMARY KEY,
+    entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    description TEXT
+);
+CREATE TABLE IF NOT EXISTS etymon (
+    id INTEGER PRIMARY KEY,
+    etymology_id INTEGER NOT NULL REFERENCES etymology(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    when_ TEXT,
+    type TEXT,
+    note TEXT,
+    translation TEXT
+);
+CREATE TABLE IF NOT EXISTS etymon_unit (
+    id INTEGER PRIMARY KEY,
+    etymon_id INTEGER NOT NULL REFERENCES etymon(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    lang_code TEXT NOT NULL,
+    text TEXT NOT NULL,
+    reconstructed INTEGER,
+    translation TEXT
+);
+CREATE TABLE IF NOT EXISTS part_of_speech (
+    id INTEGER PRIMARY KEY,
+    entry_id INTEGER REFERENCES entry(id) ON DELETE CASCADE,
+    headword_translation_id INTEGER REFERENCES headword_translation(id) ON DELETE CASCADE,
+    etymon_unit_id INTEGER REFERENCES etymon_unit(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    tag TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS label (
+    id INTEGER PRIMARY KEY,
+    entry_id INTEGER REFERENCES entry(id) ON DELETE CASCADE,
+    sense_id INTEGER REFERENCES sense(id) ON DELETE CASCADE,
+    inflected_form_id INTEGER REFERENCES inflected_form(id) ON DELETE CASCADE,
+    pronunciation_id INTEGER REFERENCES pronunciation(id) ON DELETE CASCADE,
+    example_id INTEGER REFERENCES example(id) ON DELETE CASCADE,
+    example_translation_id INTEGER REFERENCES example_translation(id) ON DELETE CASCADE,
+    headword_translation_id INTEGER REFERENCES headword_translation(id) ON DELETE CASCADE,
+    collocate_marker_id INTEGER REFERENCES collocate_marker(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    tag TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS tag (
+    id INTEGER PRIMARY KEY,
+    resource_id INTEGER NOT NULL REFERENCES resource(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    listing_order INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    description TEXT,
+    type_tag TEXT,
+    for_ TEXT
+);
+CREATE TABLE IF NOT EXISTS relation_type (
+    id INTEGER PRIMARY KEY,
+    resource_id INTEGER NOT NULL REFERENCES resource(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    description TEXT,
+    scope_restriction TEXT
+);
+CREATE TABLE IF NOT EXISTS member_type (
+    id INTEGER PRIMARY KEY,
+    relation_type_id INTEGER NOT NULL REFERENCES relation_type(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    role TEXT,
+    description TEXT,
+    type TEXT NOT NULL,
+    min INTEGER,
+    max INTEGER,
+    hint TEXT
+);
+CREATE TABLE IF NOT EXISTS same_as (
+    id INTEGER PRIMARY KEY,
+    tag_id INTEGER REFERENCES tag(id) ON DELETE CASCADE,
+    relation_type_id INTEGER REFERENCES relation_type(id) ON DELETE CASCADE,
+    member_type_id INTEGER REFERENCES member_type(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    uri TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS relation (
+    id INTEGER PRIMARY KEY,
+    resource_id INTEGER NOT NULL REFERENCES resource(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    description TEXT
+);
+CREATE TABLE IF NOT EXISTS member (
+    id INTEGER PRIMARY KEY,
+    relation_id INTEGER NOT NULL REFERENCES relation(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    ref TEXT NOT NULL,
+    role TEXT,
+    obverse_listing_order INTEGER
+);
+CREATE TABLE IF NOT EXISTS etymon_language (
+    id INTEGER PRIMARY KEY,
+    resource_id INTEGER NOT NULL REFERENCES resource(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    lang_code TEXT NOT NULL,
+    display_name TEXT
+);
+CREATE TABLE IF NOT EXISTS etymon_type (
+    id INTEGER PRIMARY KEY,
+    resource_id INTEGER NOT NULL REFERENCES resource(id) ON DELETE CASCADE,
+    listing_order INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    description TEXT
+);
+";
+
+/// The row that owns a label, pronunciation, marker or other object that can
+/// occur in several places
+#[derive(Debug, Clone, Copy)]
+enum Owner {
+    Entry(i64),
+    Sense(i64),
+    InflectedForm(i64),
+    Pronunciation(i64),
+    Definition(i64),
+    Example(i64),
+    ExampleTranslation(i64),
+    HeadwordTranslation(i64),
+    HeadwordExplanation(i64),
+    CollocateMarker(i64),
+    EtymonUnit(i64),
+    Tag(i64),
+    RelationType(i64),
+    MemberType(i64),
+}
+
+impl Owner {
+    /// The foreign key column that refers to the owner
+    fn column(self) -> &'static str {
+        match self {
+            Owner::Entry(_) => "entry_id",
+            Owner::Sense(_) => "sense_id",
+            Owner::InflectedForm(_) => "inflected_form_id",
+            Owner::Pronunciation(_) => "pronunciation_id",
+            Owner::Definition(_) => "definition_id",
+            Owner::Example(_) => "example_id",
+            Owner::ExampleTranslation(_) => "example_translation_id",
+            Owner::HeadwordTranslation(_) => "headword_translation_id",
+            Owner::HeadwordExplanation(_) => "headword_explanation_id",
+            Owner::CollocateMarker(_) => "collocate_marker_id",
+            Owner::EtymonUnit(_) => "etymon_unit_id",
+            Owner::Tag(_) => "tag_id",
+            Owner::RelationType(_) => "relation_type_id",
+            Owner::MemberType(_) => "member_type_id",
+        }
+    }
+
+    fn id(self) -> i64 {
+        match self {
+            Owner::Entry(id) | Owner::Sense(id) | Owner::InflectedForm(id)
+                | Owner::Pronunciation(id) | Owner::Definition(id) | Owner::Example(id)
+                | Owner::ExampleTranslation(id) | Owner::HeadwordTranslation(id)
+                | Owner::HeadwordExplanation(id) | Owner::CollocateMarker(id)
+                | Owner::EtymonUnit(id) | Owner::Tag(id) | Owner::RelationType(id)
+                | Owner::MemberType(id) => id,
+        }
+    }
+}
+
+/// Create the tables of the schema in a database, if they do not exist yet
+pub fn create_schema(conn : &Connection) -> Result<()> {
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(())
+}
+
+/// Write a resource to a database, creating the schema if needed. The
+/// resource is added to any resources already in the database and the id of
+/// its row in the `resource` table is returned.
+pub fn write_sqlite(conn : &Connection, resource : &LexicographicResource) -> Result<i64> {
+    create_schema(conn)?;
+    let tx = conn.unchecked_transaction()?;
+    let id = SqliteWriter { conn: &tx }.write_resource(resource)?;
+    tx.commit()?;
+    Ok(id)
+}
+
+/// Read the first resource in a database
+pub fn read_sqlite(conn : &Connection) -> Result<LexicographicResource> {
+    let id = conn.query_row("SELECT id FROM resource ORDER BY id LIMIT 1", [], |row| row.get(0))
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => SqliteError::NoResource,
+            e => SqliteError::Sqlite(e),
+        })?;
+    read_sqlite_resource(conn, id)
+}
+
+/// Read the resource with the given id in the `resource` table
+pub fn read_sqlite_resource(conn : &Connection, id : i64) -> Result<LexicographicResource> {
+    SqliteReader { conn }.read_resource(id)
+}
+
+/// Write a resource to a new database file, replacing any existing file
+pub fn write_sqlite_file<P : AsRef<Path>>(path : P, resource : &LexicographicResource) -> Result<()> {
+    if path.as_ref().exists() {
+        std::fs::remove_file(&path)?;
+    }
+    let conn = Connection::open(path)?;
+    write_sqlite(&conn, resource)?;
+    Ok(())
+}
+
+/// Read the first resource in a database file
+pub fn read_sqlite_file<P : AsRef<Path>>(path : P) -> Result<LexicographicResource> {
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    read_sqlite(&conn)
+}
+
+/// Whether the content is an SQLite database
+pub fn is_sqlite(content : &[u8]) -> bool {
+    content.starts_with(SQLITE_HEADER)
+}
+
+struct SqliteWriter<'a> {
+    conn : &'a Connection,
+}
+
+impl<'a> SqliteWriter<'a> {
+    fn insert<P : rusqlite::Params>(&self, sql : &str, params : P) -> Result<i64> {
+        self.conn.prepare_cached(sql)?.execute(params)?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    fn write_resource(&self, resource : &LexicographicResource) -> Result<i64> {
+        let id = self.insert("INSERT INTO resource (title, uri, lang_code) VALUES (?1, ?2, ?3)",
+            params![resource.title, resource.uri, resource.lang_code.0])?;
+        for (i, lang) in resource.translation_languages.iter().enumerate() {
+            self.insert("INSERT INTO translation_language (resource_id, listing_order, lang_code) VALUES (?1, ?2, ?3)",
+                params![id, i, lang])?;
+        }
+        for (i, entry) in resource.entries.iter().enumerate() {
+            self.write_entry(id, i, entry)?;
+        }
+        for (i, tag) in resource.definition_type_tags.iter().enumerate() {
+            self.write_tag(id, "definitionType", i, &tag.tag, &tag.description, &None, &None, &tag.same_as)?;
+        }
+        for (i, tag) in resource.inflected_form_tags.iter().enumerate() {
+            self.write_tag(id, "inflectedForm", i, &tag.tag, &tag.description, &None, &tag.for_, &tag.same_as)?;
+        }
+        for (i, tag) in resource.label_tags.iter().enumerate() {
+            self.write_tag(id, "label", i, &tag.tag, &tag.description, &tag.type_tag, &tag.for_, &tag.same_as)?;
+        }
+        for (i, tag) in resource.label_type_tags.iter().enumerate() {
+            self.write_tag(id, "labelType", i, &tag.tag, &tag.description, &None, &None, &tag.same_as)?;
+        }
+        for (i, tag) in resource.part_of_speech_tags.iter().enumerate() {
+            self.write_tag(id, "partOfSpeech", i, &tag.tag, &tag.description, &None, &tag.for_, &tag.same_as)?;
+        }
+        for (i, tag) in resource.source_identity_tags.iter().enumerate() {
+            self.write_tag(id, "sourceIdentity", i, &tag.tag, &tag.description, &None, &None, &tag.same_as)?;
+        }
+        for (i, tag) in resource.transcription_scheme_tags.iter().enumerate() {
+            self.write_tag(id, "transcriptionScheme", i, &tag.tag, &tag.description, &None, &tag.for_, &[])?;
+        }
+        for (i, relation) in resource.relations.iter().enumerate() {
+            let relation_id = self.insert("INSERT INTO relation (resource_id, listing_order, type, description) VALUES (?1, ?2, ?3, ?4)",
+                params![id, i, relation._type, relation.description])?;
+            for (j, member) in relation.members.iter().enumerate() {
+                self.insert("INSERT INTO member (relation_id, listing_order, ref, role, obverse_listing_order) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![relation_id, j, member.ref_, member.role, member.obverse_listing_order])?;
+            }
+        }
+        for (i, relation_type) in resource.relation_types.iter().enumerate() {
+            self.write_relation_type(id, i, relation_type)?;
+        }
+        for (i, language) in resource.etymon_languages.iter().enumerate() {
+            self.insert("INSERT INTO etymon_language (resource_id, listing_order, lang_code, display_name) VALUES (?1, ?2, ?3, ?4)",
+                params![id, i, language.lang_code.0, language.display_name])?;
+        }
+        for (i, etymon_type) in resource.etymon_types.iter().enumerate() {
+            self.insert("INSERT INTO etymon_type (resource_id, listing_order, type, description) VALUES (?1, ?2, ?3, ?4)",
+                params![id, i, etymon_type._type, etymon_type.description])?;
+        }
+        Ok(id)
+    }
+
+    fn write_entry(&self, resource_id : i64, order : usize, entry : &Entry) -> Result<()> {
+        let id = self.insert("INSERT INTO entry (resource_id, listing_order, xml_id, headword, homograph_number) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![resource_id, order, entry.id, entry.headword, entry.homograph_number])?;
+        let owner = Owner::Entry(id);
+        self.write_placeholder_markers(owner, &entry.placeholder_markers)?;
+        self.write_parts_of_speech(owner, &entry.parts_of_speech)?;
+        self.write_labels(owner, &entry.labels)?;
+        self.write_pronunciations(owner, &entry.pronunciations)?;
+        self.write_inflected_forms(owner, &entry.inflected_forms)?;
+        for (i, sense) in entry.senses.iter().enumerate() {
+            self.write_sense(id, i, sense)?;
+        }
+        for (i, etymology) in entry.etymologies.iter().enumerate() {
+            self.write_etymology(id, i, etymology)?;
+        }
+        Ok(())
+    }
+
+    fn write_sense(&self, entry_id : i64, order : usize, sense : &Sense) -> Result<()> {
+        let id = self.insert("INSERT INTO sense (entry_id, listing_order, xml_id, indicator) VALUES (?1, ?2, ?3, ?4)",
+            params![entry_id, order, sense.id, sense.indicator])?;
+        self.write_labels(Owner::Sense(id), &sense.labels)?;
+        for (i, definition) in sense.definitions.iter().enumerate() {
+            let definition_id = self.insert("INSERT INTO definition (sense_id, listing_order, text, definition_type) VALUES (?1, ?2, ?3, ?4)",
+                params![id, i, definition.text, definition.definition_type])?;
+            let owner = Owner::Definition(definition_id);
+            self.write_headword_markers(owner, &definition.headword_markers)?;
+            self.write_collocate_markers(owner, &definition.collocate_markers)?;
+        }
+        for (i, example) in sense.examples.iter().enumerate() {
+            self.write_example(id, i, example)?;
+        }
+        for (i, explanation) in sense.headword_explanations.iter().enumerate() {
+            let explanation_id = self.insert("INSERT INTO headword_explanation (sense_id, listing_order, text, lang_code) VALUES (?1, ?2, ?3, ?4)",
+                params![id, i, explanation.text, explanation.lang_code.as_ref().map(|l| &l.0)])?;
+            self.write_placeholder_markers(Owner::HeadwordExplanation(explanation_id), &explanation.placeholder_markers)?;
+        }
+        for (i, translation) in sense.headword_translations.iter().enumerate() {
+            let translation_id = self.insert("INSERT INTO headword_translation (sense_id, listing_order, text, lang_code) VALUES (?1, ?2, ?3, ?4)",
+                params![id, i, translation.text, translation.lang_code.as_ref().map(|l| &l.0)])?;
+            let owner = Owner::HeadwordTranslation(translation_id);
+            self.write_placeholder_markers(owner, &translation.placeholder_markers)?;
+            self.write_parts_of_speech(owner, &translation.parts_of_speech)?;
+            self.write_labels(owner, &translation.labels)?;
+            self.write_pronunciations(owner, &translation.pronunciations)?;
+            self.write_inflected_forms(owner, &translation.inflected_forms)?;
+        }
+        Ok(())
+    }
+
+    fn write_example(&self, sense_id : i64, order : usize, example : &Example) -> Result<()> {
+        let id = self.insert("INSERT INTO example (sense_id, listing_order, text, source_identity, source_elaboration, sound_file) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![sense_id, order, example.text, example.source_identity, example.source_elaboration, example.sound_file])?;
+        let owner = Owner::Example(id);
+        self.write_headword_markers(owner, &example.headword_markers)?;
+        self.write_collocate_markers(owner, &example.collocate_markers)?;
+        self.write_labels(owner, &example.labels)?;
+        for (i, translation) in example.example_translations.iter().enumerate() {
+            let translation_id = self.insert("INSERT INTO example_translation (example_id, listing_order, text, lang_code, sound_file) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![id, i, translation.text, translation.lang_code.as_ref().map(|l| &l.0), translation.sound_file])?;
+            let owner = Owner::ExampleTranslation(translation_id);
+            self.write_headword_markers(owner, &translation.headword_markers)?;
+            self.write_collocate_markers(owner, &translation.collocate_markers)?;
+            self.write_labels(owner, &translation.labels)?;
+        }
+        Ok(())
+    }
+
+    fn write_etymology(&self, entry_id : i64, order : usize, etymology : &Etymology) -> Result<()> {
+        let id = self.insert("INSERT INTO etymology (entry_id, listing_order, description) VALUES (?1, ?2, ?3)",
+            params![entry_id, order, etymology.description])?;
+        for (i, etymon) in etymology.etymons.iter().enumerate() {
+            let etymon_id = self.insert("INSERT INTO etymon (etymology_id, listing_order, when_, type, note, translation) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![id, i, etymon.when, etymon._type, etymon.note, etymon.translation])?;
+            for (j, unit) in etymon.etymon_units.iter().enumerate() {
+                let unit_id = self.insert("INSERT INTO etymon_unit (etymon_id, listing_order, lang_code, text, reconstructed, translation) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![etymon_id, j, unit.lang_code.0, unit.text, unit.reconstructed, unit.translation])?;
+                self.write_parts_of_speech(Owner::EtymonUnit(unit_id), &unit.parts_of_speech)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_inflected_forms(&self, owner : Owner, forms : &[InflectedForm]) -> Result<()> {
+        for (i, form) in forms.iter().enumerate() {
+            let id = self.insert(&format!("INSERT INTO inflected_form ({}, listing_order, text, tag) VALUES (?1, ?2, ?3, ?4)", owner.column()),
+                params![owner.id(), i, form.text, form.tag])?;
+            self.write_labels(Owner::InflectedForm(id), &form.labels)?;
+            self.write_pronunciations(Owner::InflectedForm(id), &form.pronunciations)?;
+        }
+        Ok(())
+    }
+
+    fn write_pronunciations(&self, owner : Owner, pronunciations : &[Pronunciation]) -> Result<()> {
+        for (i, pronunciation) in pronunciations.iter().enumerate() {
+            let id = self.insert(&format!("INSERT INTO pronunciation ({}, listing_order, sound_file) VALUES (?1, ?2, ?3)", owner.column()),
+                params![owner.id(), i, pronunciation.sound_file])?;
+            for (j, transcription) in pronunciation.transcriptions.iter().enumerate() {
+                self.insert("INSERT INTO transcription (pronunciation_id, listing_order, text, scheme) VALUES (?1, ?2, ?3, ?4)",
+                    params![id, j, transcription.text, transcription.scheme])?;
+            }
+            self.write_labels(Owner::Pronunciation(id), &pronunciation.labels)?;
+        }
+        Ok(())
+    }
+
+    fn write_strings(&self, table : &str, column : &str, owner : Owner, values : &[String]) -> Result<()> {
+        for (i, value) in values.iter().enumerate() {
+            self.insert(&format!("INSERT INTO {} ({}, listing_order, {}) VALUES (?1, ?2, ?3)", table, owner.column(), column),
+                params![owner.id(), i, value])?;
+        }
+        Ok(())
+    }
+
+    fn write_labels(&self, owner : Owner, labels : &[String]) -> Result<()> {
+        self.write_strings("label", "tag", owner, labels)
+    }
+
+    fn write_parts_of_speech(&self, owner : Owner, parts_of_speech : &[String]) -> Result<()> {
+        self.write_strings("part_of_speech", "tag", owner, parts_of_speech)
+    }
+
+    fn write_same_as(&self, owner : Owner, same_as : &[String]) -> Result<()> {
+        self.write_strings("same_as", "uri", owner, same_as)
+    }
+
+    fn write_markers(&self, table : &str, owner : Owner, markers : &[Marker]) -> Result<()> {
+        for (i, marker) in markers.iter().enumerate() {
+            self.insert(&format!("INSERT INTO {} ({}, listing_order, start_index, end_index) VALUES (?1, ?2, ?3, ?4)", table, owner.column()),
+                params![owner.id(), i, marker.start_index, marker.end_index])?;
+        }
+        Ok(())
+    }
+
+    fn write_placeholder_markers(&self, owner : Owner, markers : &[Marker]) -> Result<()> {
+        self.write_markers("placeholder_marker", owner, markers)
+    }
+
+    fn write_headword_markers(&self, owner : Owner, markers : &[Marker]) -> Result<()> {
+        self.write_markers("headword_marker", owner, markers)
+    }
+
+    fn write_collocate_markers(&self, owner : Owner, markers : &[CollocateMarker]) -> Result<()> {
+        for (i, marker) in markers.iter().enumerate() {
+            let id = self.insert(&format!("INSERT INTO collocate_marker ({}, listing_order, start_index, end_index, xml_id, lemma) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", owner.column()),
+                params![owner.id(), i, marker.start_index, marker.end_index, marker.id, marker.lemma])?;
+            self.write_labels(Owner::CollocateMarker(id), &marker.labels)?;
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn write_tag(&self, resource_id : i64, kind : &str, order : usize, tag : &str, description : &Option<String>,
+        type_tag : &Option<String>, for_ : &Option<String>, same_as : &[String]) -> Result<()> {
+        let id = self.insert("INSERT INTO tag (resource_id, kind, listing_order, tag, description, type_tag, for_) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![resource_id, kind, order, tag, description, type_tag, for_])?;
+        self.write_same_as(Owner::Tag(id), same_as)
+    }
+
+    fn write_relation_type(&self, resource_id : i64, order : usize, relation_type : &RelationType) -> Result<()> {
+        let scope_restriction = relation_type.scope_restriction.as_ref().map(|s| match s {
+            ScopeRestriction::SameEntry => "sameEntry",
+            ScopeRestriction::SameResource => "sameResource",
+            ScopeRestriction::Any => "any",
+        });
+        let id = self.insert("INSERT INTO relation_type (resource_id, listing_order, type, description, scope_restriction) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![resource_id, order, relation_type._type, relation_type.description, scope_restriction])?;
+        for (i, member_type) in relation_type.member_types.iter().enumerate() {
+            let _type = match member_type._type {
+                MemberTypeType::Sense => "sense",
+                MemberTypeType::Entry => "entry",
+                MemberTypeType::Collocate => "collocate",
+            };
+            let hint = member_type.hint.as_ref().map(|h| match h {
+                Hint::Embed => "embed",
+                Hint::Navigate => "navigate",
+                Hint::None => "none",
+            });
+            let member_type_id = self.insert("INSERT INTO member_type (relation_type_id, listing_order, role, description, type, min, max, hint) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![id, i, member_type.role, member_type.description, _type, member_type.min, member_type.max, hint])?;
+            self.write_same_as(Owner::MemberType(member_type_id), &member_type.same_as)?;
+        }
+        self.write_same_as(Owner::RelationType(id), &relation_type.same_as)
+    }
+}
+
+struct SqliteReader<'a> {
+    conn : &'a Connection,
+}
+
+impl<'a> SqliteReader<'a> {
+    /// Query the rows that belong to a parent row, in listing order
+    fn query<T, F : FnMut(&Row) -> rusqlite::Result<T>>(&self, sql : &str, parent : i64, f : F) -> Result<Vec<T>> {
+        let mut stmt = self.conn.prepare_cached(sql)?;
+        let rows = stmt.query_map([parent], f)?.collect::<rusqlite::Result<Vec<T>>>()?;
+        Ok(rows)
+    }
+
+    fn read_resource(&self, id : i64) -> Result<LexicographicResource> {
+        let mut resource = self.conn.query_row("SELECT title, uri, lang_code FROM resource WHERE id = ?1", [id], |row| {
+            Ok(LexicographicResource {
+                title: row.get(0)?,
+                uri: row.get(1)?,
+                lang_code: LangCode(row.get(2)?),
+                ..LexicographicResource::default()
+            })
+        })?;
+        resource.translation_languages = self.query("SELECT lang_code FROM translation_language WHERE resource_id = ?1 ORDER BY listing_order",
+            id, |row| row.get(0))?;
+        for (entry_id, mut entry) in self.query("SELECT id, xml_id, headword, homograph_number FROM entry WHERE resource_id = ?1 ORDER BY listing_order",
+            id, |row| Ok((row.get(0)?, Entry {
+                id: row.get(1)?,
+                headword: row.get(2)?,
+                homograph_number: row.get(3)?,
+                ..Entry::default()
+            })))? {
+            self.read_entry(entry_id, &mut entry)?;
+            resource.entries.push(entry);
+        }
+        for (tag_id, kind, tag, description, type_tag, for_) in self.query("SELECT id, kind, tag, description, type_tag, for_ FROM tag WHERE resource_id = ?1 ORDER BY kind, listing_order",
+            id, |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?, row.get::<_, Option<String>>(4)?, row.get::<_, Option<String>>(5)?)))? {
+            let same_as = self.read_same_as(Owner::Tag(tag_id))?;
+            match kind.as_str() {
+                "definitionType" => resource.definition_type_tags.push(DefinitionTypeTag { tag, description, same_as }),
+                "inflectedForm" => resource.inflected_form_tags.push(InflectedFormTag { tag, description, same_as, for_ }),
+                "label" => resource.label_tags.push(LabelTag { tag, description, type_tag, same_as, for_ }),
+                "labelType" => resource.label_type_tags.push(LabelTypeTag { tag, description, same_as }),
+                "partOfSpeech" => resource.part_of_speech_tags.push(PartOfSpeechTag { tag, description, same_as, for_ }),
+                "sourceIdentity" => resource.source_identity_tags.push(SourceIdentityTag { tag, description, same_as }),
+                "transcriptionScheme" => resource.transcription_scheme_tags.push(TranscriptionSchemeTag { tag, description, for_ }),
+                _ => return Err(SqliteError::InvalidValue("tag.kind".to_string(), kind)),
+            }
+        }
+        for (relation_id, mut relation) in self.query("SELECT id, type, description FROM relation WHERE resource_id = ?1 ORDER BY listing_order",
+            id, |row| Ok((row.get(0)?, Relation { _type: row.get(1)?, description: row.get(2)?, members: Vec::new() })))? {
+            relation.members = self.query("SELECT ref, role, obverse_listing_order FROM member WHERE relation_id = ?1 ORDER BY listing_order",
+                relation_id, |row| Ok(Member { ref_: row.get(0)?, role: row.get(1)?, obverse_listing_order: row.get(2)? }))?;
+            resource.relations.push(relation);
+        }
+        for (relation_type_id, scope_restriction, mut relation_type) in self.query("SELECT id, scope_restriction, type, description FROM relation_type WHERE resource_id = ?1 ORDER BY listing_order",
+            id, |row| Ok((row.get(0)?, row.get::<_, Option<String>>(1)?, RelationType {
+                _type: row.get(2)?,
+                description: row.get(3)?,
+                ..RelationType::default()
+            })))? {
+            relation_type.scope_restriction = match scope_restriction.as_deref() {
+                None => None,
+                Some("sameEntry") => Some(ScopeRestriction::SameEntry),
+                Some("sameResource") => Some(ScopeRestriction::SameResource),
+                Some("any") => Some(ScopeRestriction::Any),
+                Some(s) => return Err(SqliteError::InvalidValue("relation_type.scope_restriction".to_string(), s.to_string())),
+            };
+            relation_type.member_types = self.read_member_types(relation_type_id)?;
+            relation_type.same_as = self.read_same_as(Owner::RelationType(relation_type_id))?;
+            resource.relation_types.push(relation_type);
+        }
+        resource.etymon_languages = self.query("SELECT lang_code, display_name FROM etymon_language WHERE resource_id = ?1 ORDER BY listing_order",
+            id, |row| Ok(EtymonLanguage { lang_code: LangCode(row.get(0)?), display_name: row.get(1)? }))?;
+        resource.etymon_types = self.query("SELECT type, description FROM etymon_type WHERE resource_id = ?1 ORDER BY listing_order",
+            id, |row| Ok(EtymonType { _type: row.get(0)?, description: row.get(1)? }))?;
+        Ok(resource)
+    }
+
+    fn read_member_types(&self, relation_type_id : i64) -> Result<Vec<MemberType>> {
+        let mut member_types = Vec::new();
+        for (id, _type, hint, mut member_type) in self.query("SELECT id, type, hint, role, description, min, max FROM member_type WHERE relation_type_id = ?1 ORDER BY listing_order",
+            relation_type_id, |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?, MemberType {
+                role: row.get(3)?,
+                description: row.get(4)?,
+                min: row.get(5)?,
+                max: row.get(6)?,
+                ..MemberType::default()
+            })))? {
+            member_type._type = match _type.as_str() {
+                "sense" => MemberTypeType::Sense,
+                "entry" => MemberTypeType::Entry,
+                "collocate" => MemberTypeType::Collocate,
+                _ => return Err(SqliteError::InvalidValue("member_type.type".to_string(), _type)),
+            };
+            member_type.hint = match hint.as_deref() {
+                None => None,
+                Some("embed") => Some(Hint::Embed),
+                Some("navigate") => Some(Hint::Navigate),
+                Some("none") => Some(Hint::None),
+                Some(s) => return Err(SqliteError::InvalidValue("member_type.hint".to_string(), s.to_string())),
+            };
+            member_type.same_as = self.read_same_as(Owner::MemberType(id))?;
+            member_types.push(member_type);
+        }
+        Ok(member_types)
+    }
+
+    fn read_entry(&self, id : i64, entry : &mut Entry) -> Result<()> {
+        let owner = Owner::Entry(id);
+        entry.placeholder_markers = self.read_placeholder_markers(owner)?;
+        entry.parts_of_speech = self.read_parts_of_speech(owner)?;
+        entry.labels = self.read_labels(owner)?;
+        entry.pronunciations = self.read_pronunciations(owner)?;
+        entry.inflected_forms = self.read_inflected_forms(owner)?;
+        for (sense_id, mut sense) in self.query("SELECT id, xml_id, indicator FROM sense WHERE entry_id = ?1 ORDER BY listing_order",
+            id, |row| Ok((row.get(0)?, Sense { id: row.get(1)?, indicator: row.get(2)?, ..Sense::default() })))? {
+            self.read_sense(sense_id, &mut sense)?;
+            entry.senses.push(sense);
+        }
+        for (etymology_id, mut etymology) in self.query("SELECT id, description FROM etymology WHERE entry_id = ?1 ORDER BY listing_order",
+            id, |row| Ok((row.get(0)?, Etymology { description: row.get(1)?, etymons: Vec::new() })))? {
+            for (etymon_id, mut etymon) in self.query("SELECT id, when_, type, note, translation FROM etymon WHERE etymology_id = ?1 ORDER BY listing_order",
+                etymology_id, |row| Ok((row.get(0)?, Etymon {
+                    when: row.get(1)?,
+                    _type: row.get(2)?,
+                    note: row.get(3)?,
+                    translation: row.get(4)?,
+                    etymon_units: Vec::new(),
+                })))? {
+                for (unit_id, mut unit) in self.query("SELECT id, lang_code, text, reconstructed, translation FROM etymon_unit WHERE etymon_id = ?1 ORDER BY listing_order",
+                    etymon_id, |row| Ok((row.get(0)?, EtymonUnit {
+                        lang_code: LangCode(row.get(1)?),
+                        text: row.get(2)?,
+                        reconstructed: row.get(3)?,
+                        translation: row.get(4)?,
+                        parts_of_speech: Vec::new(),
+                    })))? {
+                    unit.parts_of_speech = self.read_parts_of_speech(Owner::EtymonUnit(unit_id))?;
+                    etymon.etymon_units.push(unit);
+                }
+                etymology.etymons.push(etymon);
+            }
+            entry.etymologies.push(etymology);
+        }
+        Ok(())
+    }
+
+    fn read_sense(&self, id : i64, sense : &mut Sense) -> Result<()> {
+        sense.labels = self.read_labels(Owner::Sense(id))?;
+        for (definition_id, mut definition) in self.query("SELECT id, text, definition_type FROM definition WHERE sense_id = ?1 ORDER BY listing_order",
+            id, |row| Ok((row.get(0)?, Definition { text: row.get(1)?, definition_type: row.get(2)?, ..Definition::default() })))? {
+            let owner = Owner::Definition(definition_id);
+            definition.headword_markers = self.read_headword_markers(owner)?;
+            definition.collocate_markers = self.read_collocate_markers(owner)?;
+            sense.definitions.push(definition);
+        }
+        for (example_id, mut example) in self.query("SELECT id, text, source_identity, source_elaboration, sound_file FROM example WHERE sense_id = ?1 ORDER BY listing_order",
+            id, |row| Ok((row.get(0)?, Example {
+                text: row.get(1)?,
+                source_identity: row.get(2)?,
+                source_elaboration: row.get(3)?,
+                sound_file: row.get(4)?,
+                ..Example::default()
+            })))? {
+            let owner = Owner::Example(example_id);
+            example.headword_markers = self.read_headword_markers(owner)?;
+            example.collocate_markers = self.read_collocate_markers(owner)?;
+            example.labels = self.read_labels(owner)?;
+            for (translation_id, mut translation) in self.query("SELECT id, text, lang_code, sound_file FROM example_translation WHERE example_id = ?1 ORDER BY listing_order",
+                example_id, |row| Ok((row.get(0)?, ExampleTranslation {
+                    text: row.get(1)?,
+                    lang_code: row.get::<_, Option<String>>(2)?.map(LangCode),
+                    sound_file: row.get(3)?,
+                    ..ExampleTranslation::default()
+                })))? {
+                let owner = Owner::ExampleTranslation(translation_id);
+                translation.headword_markers = self.read_headword_markers(owner)?;
+                translation.collocate_markers = self.read_collocate_markers(owner)?;
+                translation.labels = self.read_labels(owner)?;
+                example.example_translations.push(translation);
+            }
+            sense.examples.push(example);
+        }
+        for (explanation_id, mut explanation) in self.query("SELECT id, text, lang_code FROM headword_explanation WHERE sense_id = ?1 ORDER BY listing_order",
+            id, |row| Ok((row.get(0)?, HeadwordExplanation {
+                text: row.get(1)?,
+                lang_code: row.get::<_, Option<String>>(2)?.map(LangCode),
+                ..HeadwordExplanation::default()
+            })))? {
+            explanation.placeholder_markers = self.read_placeholder_markers(Owner::HeadwordExplanation(explanation_id))?;
+            sense.headword_explanations.push(explanation);
+        }
+        for (translation_id, mut translation) in self.query("SELECT id, text, lang_code FROM headword_translation WHERE sense_id = ?1 ORDER BY listing_order",
+            id, |row| Ok((row.get(0)?, HeadwordTranslation {
+                text: row.get(1)?,
+                lang_code: row.get::<_, Option<String>>(2)?.map(LangCode),
+                ..HeadwordTranslation::default()
+            })))? {
+            let owner = Owner::HeadwordTranslation(translation_id);
+            translation.placeholder_markers = self.read_placeholder_markers(owner)?;
+            translation.parts_of_speech = self.read_parts_of_speech(owner)?;
+            translation.labels = self.read_labels(owner)?;
+            translation.pronunciations = self.read_pronunciations(owner)?;
+            translation.inflected_forms = self.read_inflected_forms(owner)?;
+            sense.headword_translations.push(translation);
+        }
+        Ok(())
+    }
+
+    fn read_inflected_forms(&self, owner : Owner) -> Result<Vec<InflectedForm>> {
+        let mut forms = Vec::new();
+        for (id, mut form) in self.query(&format!("SELECT id, text, tag FROM inflected_form WHERE {} = ?1 ORDER BY listing_order", owner.column()),
+            owner.id(), |row| Ok((row.get(0)?, InflectedForm { text: row.get(1)?, tag: row.get(2)?, ..InflectedForm::default() })))? {
+            form.labels = self.read_labels(Owner::InflectedForm(id))?;
+            form.pronunciations = self.read_pronunciations(Owner::InflectedForm(id))?;
+            forms.push(form);
+        }
+        Ok(forms)
+    }
+
+    fn read_pronunciations(&self, owner : Owner) -> Result<Vec<Pronunciation>> {
+        let mut pronunciations = Vec::new();
+        for (id, mut pronunciation) in self.query(&format!("SELECT id, sound_file FROM pronunciation WHERE {} = ?1 ORDER BY listing_order", owner.column()),
+            owner.id(), |row| Ok((row.get(0)?, Pronunciation { sound_file: row.get(1)?, ..Pronunciation::default() })))? {
+            pronunciation.transcriptions = self.query("SELECT text, scheme FROM transcription WHERE pronunciation_id = ?1 ORDER BY listing_order",
+                id, |row| Ok(Transcription { text: row.get(0)?, scheme: row.get(1)? }))?;
+            pronunciation.labels = self.read_labels(Owner::Pronunciation(id))?;
+            pronunciations.push(pronunciation);
+        }
+        Ok(pronunciations)
+    }
+
+    fn read_strings(&self, table : &str, column : &str, owner : Owner) -> Result<Vec<String>> {
+        self.query(&format!("SELECT {} FROM {} WHERE {} = ?1 ORDER BY listing_order", column, table, owner.column()),
+            owner.id(), |row| row.get(0))
+    }
+
+    fn read_labels(&self, owner : Owner) -> Result<Vec<String>> {
+        self.read_strings("label", "tag", owner)
+    }
+
+    fn read_parts_of_speech(&self, owner : Owner) -> Result<Vec<String>> {
+        self.read_strings("part_of_speech", "tag", owner)
+    }
+
+    fn read_same_as(&self, owner : Owner) -> Result<Vec<String>> {
+        self.read_strings("same_as", "uri", owner)
+    }
+
+    fn read_markers(&self, table : &str, owner : Owner) -> Result<Vec<Marker>> {
+        self.query(&format!("SELECT start_index, end_index FROM {} WHERE {} = ?1 ORDER BY listing_order", table, owner.column()),
+            owner.id(), |row| Ok(Marker { start_index: row.get(0)?, end_index: row.get(1)? }))
+    }
+
+    fn read_placeholder_markers(&self, owner : Owner) -> Result<Vec<Marker>> {
+        self.read_markers("placeholder_marker", owner)
+    }
+
+    fn read_headword_markers(&self, owner : Owner) -> Result<Vec<Marker>> {
+        self.read_markers("headword_marker", owner)
+    }
+
+    fn read_collocate_markers(&self, owner : Owner) -> Result<Vec<CollocateMarker>> {
+        let mut markers = Vec::new();
+        for (id, mut marker) in self.query(&format!("SELECT id, start_index, end_index, xml_id, lemma FROM collocate_marker WHERE {} = ?1 ORDER BY listing_order", owner.column()),
+            owner.id(), |row| Ok((row.get(0)?, CollocateMarker {
+                start_index: row.get(1)?,
+                end_index: row.get(2)?,
+                id: row.get(3)?,
+                lemma: row.get(4)?,
+                labels: Vec::new(),
+            })))? {
+            marker.labels = self.read_labels(Owner::CollocateMarker(id))?;
+            markers.push(marker);
+        }
+        Ok(markers)
+    }
+}
+
+/// A database file in the temporary directory that is removed when dropped,
+/// used to read and write databases as byte streams
+struct TempDatabase {
+    path : PathBuf,
+}
+
+impl TempDatabase {
+    fn new() -> TempDatabase {
+        static COUNTER : AtomicUsize = AtomicUsize::new(0);
+        let name = format!("dmlex-{}-{}.sqlite", std::process::id(), COUNTER.fetch_add(1, Ordering::SeqCst));
+        TempDatabase { path: std::env::temp_dir().join(name) }
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// SQLite databases as a format of the registry. Databases are copied to a
+/// temporary file, as SQLite cannot read them from a stream.
+#[derive(Debug, Clone, Default)]
+pub struct SqliteFormat;
+
+impl ResourceReader for SqliteFormat {
+    fn read_resource(&self, input : &mut dyn Read, _options : &ReadOptions) -> std::result::Result<LexicographicResource, ParseError> {
+        let temp = TempDatabase::new();
+        let mut content = Vec::new();
+        input.read_to_end(&mut content).map_err(SqliteError::from)?;
+        std::fs::write(&temp.path, content).map_err(SqliteError::from)?;
+        Ok(read_sqlite_file(&temp.path)?)
+    }
+}
+
+impl ResourceWriter for SqliteFormat {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, _options : &WriteOptions) -> std::result::Result<(), WriteError> {
+        let temp = TempDatabase::new();
+        write_sqlite_file(&temp.path, resource)?;
+        output.write_all(&std::fs::read(&temp.path)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SqliteError {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The database contains no resource")]
+    NoResource,
+    #[error("Invalid value in {0}: {1}")]
+    InvalidValue(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::FormatRegistry;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    #[test]
+    fn test_round_trip_all_props() {
+        let resource = parse(File::open("examples/all_props.xml").unwrap(), &Format::XML, &None).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        write_sqlite(&conn, &resource).unwrap();
+        assert_eq!(resource, read_sqlite(&conn).unwrap());
+    }
+
+    #[test]
+    fn test_schema_rows() {
+        let resource = parse(File::open("examples/0.xml").unwrap(), &Format::XML, &None).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        let id = write_sqlite(&conn, &resource).unwrap();
+        let headword : String = conn.query_row("SELECT headword FROM entry WHERE resource_id = ?1", [id], |row| row.get(0)).unwrap();
+        assert_eq!(headword, "abandon");
+        let examples : Vec<String> = conn.prepare("SELECT example.text FROM example JOIN sense ON example.sense_id = sense.id \
+            ORDER BY sense.listing_order, example.listing_order").unwrap()
+            .query_map([], |row| row.get(0)).unwrap().collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(examples[0], resource.entries[0].senses[0].examples[0].text);
+        // Deleting the resource deletes everything in it
+        conn.execute("DELETE FROM resource", []).unwrap();
+        let senses : i64 = conn.query_row("SELECT COUNT(*) FROM sense", [], |row| row.get(0)).unwrap();
+        assert_eq!(senses, 0);
+        assert!(matches!(read_sqlite(&conn), Err(SqliteError::NoResource)));
+    }
+
+    #[test]
+    fn test_round_trip_through_registry() {
+        let registry = FormatRegistry::default();
+        let mut resource = parse(File::open("examples/14.xml").unwrap(), &Format::XML, &None).unwrap();
+        resource.entries.extend(parse(File::open("examples/15.xml").unwrap(), &Format::XML, &None).unwrap().entries);
+        let mut out = Vec::new();
+        registry.write("sqlite", &mut out, &resource, &WriteOptions::default()).unwrap();
+        assert!(is_sqlite(&out));
+        assert_eq!(registry.detect_format(&out, None), Some("sqlite".to_string()));
+        let resource2 = registry.parse("sqlite", &mut out.as_slice(), &ReadOptions::default()).unwrap();
+        assert_eq!(resource, resource2);
+    }
+}
@@ -0,0 +1,161 @@
+//! Export of resources in the StarDict format used by GoldenDict and other
+//! offline dictionary readers
+//!
+//! A dictionary consists of an `.ifo` file describing it, a `.dict` file with
+//! the articles as plain text, an `.idx` file with the offset and size of the
+//! article of each headword and a `.syn` file that maps the inflected forms
+//! to the headwords they belong to. Homographs are separate articles.
+//!
+//! The articles are stored in the `.dict` file in the alphabetical order of
+//! the language of the resource, while the `.idx` and `.syn` files are in the
+//! order StarDict needs to look words up.
+use crate::article;
+use crate::collation::Collation;
+use crate::model::*;
+use crate::report::ConversionReport;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// The files of a StarDict dictionary
+#[derive(Debug, Clone, PartialEq)]
+pub struct StarDictFiles {
+    pub ifo: String,
+    pub idx: Vec<u8>,
+    pub dict: Vec<u8>,
+    /// The synonyms file, which is only written if there are inflected forms
+    pub syn: Option<Vec<u8>>,
+}
+
+/// The order of the `.idx` and `.syn` files: ASCII case-insensitive and then
+/// byte by byte
+pub fn stardict_cmp(a : &str, b : &str) -> Ordering {
+    let lower = |s : &str| s.bytes().map(|b| b.to_ascii_lowercase()).collect::<Vec<u8>>();
+    lower(a).cmp(&lower(b)).then_with(|| a.cmp(b))
+}
+
+/// Render a resource as StarDict files
+pub fn render_stardict(resource : &LexicographicResource) -> (StarDictFiles, ConversionReport) {
+    let mut report = ConversionReport::new();
+    article::note_lost(resource, &mut report);
+    let mut dict = Vec::new();
+    let mut words = Vec::new();
+    let collation = Collation::for_resource(resource);
+    let mut entries : Vec<&Entry> = resource.entries.iter().collect();
+    entries.sort_by_cached_key(|e| (collation.key(&e.headword), e.homograph_number));
+    for entry in entries {
+        let text = article::plain_text(entry, false);
+        words.push((entry, dict.len(), text.len()));
+        dict.extend_from_slice(text.as_bytes());
+    }
+    words.sort_by(|a, b| stardict_cmp(&a.0.headword, &b.0.headword)
+        .then_with(|| a.0.homograph_number.cmp(&b.0.homograph_number)));
+    let mut idx = Vec::new();
+    let mut synonyms = Vec::new();
+    for (i, (entry, offset, size)) in words.iter().enumerate() {
+        idx.extend_from_slice(entry.headword.as_bytes());
+        idx.push(0);
+        idx.extend_from_slice(&(*offset as u32).to_be_bytes());
+        idx.extend_from_slice(&(*size as u32).to_be_bytes());
+        for form in article::lookup_forms(entry).into_iter().skip(1) {
+            synonyms.push((form, i));
+        }
+    }
+    synonyms.sort_by(|a, b| stardict_cmp(a.0, b.0).then_with(|| a.1.cmp(&b.1)));
+    let syn = if synonyms.is_empty() {
+        None
+    } else {
+        let mut syn = Vec::new();
+        for (form, i) in synonyms.iter() {
+            syn.extend_from_slice(form.as_bytes());
+            syn.push(0);
+            syn.extend_from_slice(&(*i as u32).to_be_bytes());
+        }
+        Some(syn)
+    };
+    let mut ifo = String::from("StarDict's dict ifo file\nversion=2.4.2\n");
+    ifo.push_str(&format!("bookname={}\n", one_line(resource.title.as_deref().unwrap_or("Dictionary"))));
+    ifo.push_str(&format!("wordcount={}\n", words.len()));
+    if !synonyms.is_empty() {
+        ifo.push_str(&format!("synwordcount={}\n", synonyms.len()));
+    }
+    ifo.push_str(&format!("idxfilesize={}\n", idx.len()));
+    if let Some(uri) = &resource.uri {
+        ifo.push_str(&format!("website={}\n", one_line(uri)));
+    }
+    ifo.push_str("sametypesequence=m\n");
+    (StarDictFiles { ifo, idx, dict, syn }, report)
+}
+
+/// Write a resource as StarDict files named after `base`, so that `out/en`
+/// gives `out/en.ifo`, `out/en.idx`, `out/en.dict` and `out/en.syn`
+pub fn write_stardict<P : AsRef<Path>>(base : P, resource : &LexicographicResource) -> std::io::Result<ConversionReport> {
+    let (files, report) = render_stardict(resource);
+    std::fs::write(with_suffix(base.as_ref(), "ifo"), files.ifo)?;
+    std::fs::write(with_suffix(base.as_ref(), "idx"), files.idx)?;
+    std::fs::write(with_suffix(base.as_ref(), "dict"), files.dict)?;
+    if let Some(syn) = files.syn {
+        std::fs::write(with_suffix(base.as_ref(), "syn"), syn)?;
+    }
+    Ok(report)
+}
+
+/// The path with an extension added, keeping any dots in the file name
+pub(crate) fn with_suffix(base : &Path, extension : &str) -> PathBuf {
+    let mut path = base.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// The `.ifo` file has one option per line
+fn one_line(text : &str) -> String {
+    text.replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    /// Read the words of an `.idx` file with their offset and size
+    fn read_idx(idx : &[u8]) -> Vec<(String, u32, u32)> {
+        let mut words = Vec::new();
+        let mut rest = idx;
+        while let Some(end) = rest.iter().position(|&b| b == 0) {
+            let word = String::from_utf8(rest[..end].to_vec()).unwrap();
+            let offset = u32::from_be_bytes(rest[end + 1..end + 5].try_into().unwrap());
+            let size = u32::from_be_bytes(rest[end + 5..end + 9].try_into().unwrap());
+            words.push((word, offset, size));
+            rest = &rest[end + 9..];
+        }
+        words
+    }
+
+    #[test]
+    fn test_stardict() {
+        let mut resource = parse(File::open("examples/0.xml").unwrap(), &Format::XML, &None).unwrap();
+        resource.entries.extend(parse(File::open("examples/5.xml").unwrap(), &Format::XML, &None).unwrap().entries);
+        resource.entries.push(Entry { headword: "Zebra".to_string(), ..Entry::default() });
+        let (files, report) = render_stardict(&resource);
+        assert!(report.is_empty(), "{}", report);
+        let words = read_idx(&files.idx);
+        assert_eq!(words.iter().map(|w| w.0.as_str()).collect::<Vec<_>>(), vec!["abandon", "folúsghlantóir", "Zebra"]);
+        assert!(files.ifo.contains(&format!("idxfilesize={}\n", files.idx.len())));
+        assert!(files.ifo.contains("bookname=Example Dictionary\nwordcount=3\nsynwordcount=2\n"));
+        let (_, offset, size) = &words[0];
+        let article = std::str::from_utf8(&files.dict[*offset as usize..(*offset + *size) as usize]).unwrap();
+        assert_eq!(article, "verb\n1. to suddenly leave a place or a person\n   • I'm sorry I abandoned you like that.\n   • Abandon ship!\n\
+            2. [mostly-passive] to stop supporting an idea\n   • That theory has been abandoned.");
+        let syn = files.syn.unwrap();
+        assert_eq!(&syn[..syn.iter().position(|&b| b == 0).unwrap()], "folúsghlantóirí".as_bytes());
+        assert_eq!(&syn[syn.len() - 4..], &1u32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_order() {
+        let mut words = vec!["b", "a", "B", "A", "_x"];
+        words.sort_by(|a, b| stardict_cmp(a, b));
+        assert_eq!(words, vec!["_x", "A", "a", "B", "b"]);
+    }
+}
@@ -0,0 +1,217 @@
+//! Extraction of a subset of the entries of a resource, such as a sample of
+//! the nouns or the entries for a list of headwords
+//!
+//! The relations whose members are all still in the subset are kept, with
+//! the relation types they use. Tags of all kinds, etymon languages and
+//! etymon types that the remaining entries no longer use are removed, as are
+//! label type tags for removed label tags.
+use crate::model::*;
+use std::collections::HashSet;
+use std::io::BufRead;
+
+/// The tags used by a list of entries
+#[derive(Default)]
+struct UsedTags<'a> {
+    parts_of_speech: HashSet<&'a str>,
+    labels: HashSet<&'a str>,
+    inflected_forms: HashSet<&'a str>,
+    definition_types: HashSet<&'a str>,
+    source_identities: HashSet<&'a str>,
+    transcription_schemes: HashSet<&'a str>,
+    etymon_types: HashSet<&'a str>,
+    etymon_languages: HashSet<&'a str>,
+}
+
+impl<'a> UsedTags<'a> {
+    fn of(entries : &'a [Entry]) -> UsedTags<'a> {
+        let mut used = UsedTags::default();
+        for entry in entries.iter() {
+            used.parts_of_speech.extend(entry.parts_of_speech.iter().map(|s| s.as_str()));
+            used.labels.extend(entry.labels.iter().map(|s| s.as_str()));
+            used.pronunciations(&entry.pronunciations);
+            used.inflected_forms(&entry.inflected_forms);
+            for sense in entry.senses.iter() {
+                used.labels.extend(sense.labels.iter().map(|s| s.as_str()));
+                for definition in sense.definitions.iter() {
+                    used.definition_types.extend(definition.definition_type.as_deref());
+                    used.collocates(&definition.collocate_markers);
+                }
+                for example in sense.examples.iter() {
+                    used.labels.extend(example.labels.iter().map(|s| s.as_str()));
+                    used.source_identities.extend(example.source_identity.as_deref());
+                    used.collocates(&example.collocate_markers);
+                    for translation in example.example_translations.iter() {
+                        used.labels.extend(translation.labels.iter().map(|s| s.as_str()));
+                        used.collocates(&translation.collocate_markers);
+                    }
+                }
+                for translation in sense.headword_translations.iter() {
+                    used.parts_of_speech.extend(translation.parts_of_speech.iter().map(|s| s.as_str()));
+                    used.labels.extend(translation.labels.iter().map(|s| s.as_str()));
+                    used.pronunciations(&translation.pronunciations);
+                    used.inflected_forms(&translation.inflected_forms);
+                }
+            }
+            for etymon in entry.etymologies.iter().flat_map(|e| e.etymons.iter()) {
+                used.etymon_types.extend(etymon._type.as_deref());
+                for unit in etymon.etymon_units.iter() {
+                    used.etymon_languages.insert(&unit.lang_code.0);
+                    used.parts_of_speech.extend(unit.parts_of_speech.iter().map(|s| s.as_str()));
+                }
+            }
+        }
+        used
+    }
+
+    fn pronunciations(&mut self, pronunciations : &'a [Pronunciation]) {
+        for pronunciation in pronunciations.iter() {
+            self.labels.extend(pronunciation.labels.iter().map(|s| s.as_str()));
+            self.transcription_schemes.extend(pronunciation.transcriptions.iter().filter_map(|t| t.scheme.as_deref()));
+        }
+    }
+
+    fn inflected_forms(&mut self, forms : &'a [InflectedForm]) {
+        for form in forms.iter() {
+            self.inflected_forms.extend(form.tag.as_deref());
+            self.labels.extend(form.labels.iter().map(|s| s.as_str()));
+            self.pronunciations(&form.pronunciations);
+        }
+    }
+
+    fn collocates(&mut self, collocates : &'a [CollocateMarker]) {
+        for collocate in collocates.iter() {
+            self.labels.extend(collocate.labels.iter().map(|s| s.as_str()));
+        }
+    }
+}
+
+/// The identifiers that relation members can refer to
+fn ids(entries : &[Entry]) -> HashSet<&str> {
+    let mut ids = HashSet::new();
+    for entry in entries.iter() {
+        ids.extend(entry.id.as_deref());
+        for sense in entry.senses.iter() {
+            ids.extend(sense.id.as_deref());
+            let collocates = sense.definitions.iter().flat_map(|d| d.collocate_markers.iter())
+                .chain(sense.examples.iter().flat_map(|e| e.collocate_markers.iter()
+                    .chain(e.example_translations.iter().flat_map(|t| t.collocate_markers.iter()))));
+            ids.extend(collocates.filter_map(|c| c.id.as_deref()));
+        }
+    }
+    ids
+}
+
+/// Extract the entries of a resource for which `predicate` holds, with the
+/// relations and tags they still need
+pub fn extract<F : Fn(&Entry) -> bool>(resource : &LexicographicResource, predicate : F) -> LexicographicResource {
+    let entries : Vec<Entry> = resource.entries.iter().filter(|e| predicate(e)).cloned().collect();
+    let ids = ids(&entries);
+    let relations : Vec<Relation> = resource.relations.iter()
+        .filter(|r| r.members.iter().all(|m| ids.contains(m.ref_.as_str())))
+        .cloned().collect();
+    let relation_types : HashSet<&str> = relations.iter().map(|r| r._type.as_str()).collect();
+    let used = UsedTags::of(&entries);
+    let label_tags : Vec<LabelTag> = resource.label_tags.iter()
+        .filter(|t| used.labels.contains(t.tag.as_str())).cloned().collect();
+    let label_types : HashSet<&str> = label_tags.iter().filter_map(|t| t.type_tag.as_deref()).collect();
+    LexicographicResource {
+        title: resource.title.clone(),
+        uri: resource.uri.clone(),
+        lang_code: resource.lang_code.clone(),
+        translation_languages: resource.translation_languages.clone(),
+        definition_type_tags: resource.definition_type_tags.iter()
+            .filter(|t| used.definition_types.contains(t.tag.as_str())).cloned().collect(),
+        inflected_form_tags: resource.inflected_form_tags.iter()
+            .filter(|t| used.inflected_forms.contains(t.tag.as_str())).cloned().collect(),
+        label_type_tags: resource.label_type_tags.iter()
+            .filter(|t| label_types.contains(t.tag.as_str())).cloned().collect(),
+        label_tags,
+        part_of_speech_tags: resource.part_of_speech_tags.iter()
+            .filter(|t| used.parts_of_speech.contains(t.tag.as_str())).cloned().collect(),
+        source_identity_tags: resource.source_identity_tags.iter()
+            .filter(|t| used.source_identities.contains(t.tag.as_str())).cloned().collect(),
+        transcription_scheme_tags: resource.transcription_scheme_tags.iter()
+            .filter(|t| used.transcription_schemes.contains(t.tag.as_str())).cloned().collect(),
+        relation_types: resource.relation_types.iter()
+            .filter(|t| relation_types.contains(t._type.as_str())).cloned().collect(),
+        etymon_types: resource.etymon_types.iter()
+            .filter(|t| used.etymon_types.contains(t._type.as_str())).cloned().collect(),
+        etymon_languages: resource.etymon_languages.iter()
+            .filter(|t| used.etymon_languages.contains(t.lang_code.0.as_str())).cloned().collect(),
+        entries,
+        relations,
+    }
+}
+
+/// A predicate for the entries with one of the parts of speech
+pub fn has_part_of_speech(tags : &[String]) -> impl Fn(&Entry) -> bool + '_ {
+    move |entry| entry.parts_of_speech.iter().any(|p| tags.contains(p))
+}
+
+/// Read a list of headwords, one per line, ignoring blank lines
+pub fn read_headwords<R : BufRead>(input : R) -> std::io::Result<HashSet<String>> {
+    let mut headwords = HashSet::new();
+    for line in input.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            headwords.insert(line.trim().to_string());
+        }
+    }
+    Ok(headwords)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    fn example(name : &str) -> LexicographicResource {
+        parse(File::open(format!("examples/{}", name)).unwrap(), &Format::XML, &None).unwrap()
+    }
+
+    #[test]
+    fn test_relations() {
+        let resource = example("14.xml");
+        let headwords = read_headwords("See\n\nMeer\n".as_bytes()).unwrap();
+        let subset = extract(&resource, |e| headwords.contains(&e.headword));
+        assert_eq!(subset.entries.len(), 2);
+        assert!(subset.relations.is_empty());
+        assert!(subset.relation_types.is_empty());
+        let subset = extract(&resource, |_| true);
+        assert_eq!(subset, resource);
+    }
+
+    #[test]
+    fn test_tags() {
+        let resource = example("all_props.xml");
+        assert!(!resource.part_of_speech_tags.is_empty() && !resource.label_tags.is_empty());
+        let subset = extract(&resource, |_| false);
+        assert!(subset.entries.is_empty());
+        assert!(subset.part_of_speech_tags.is_empty());
+        assert!(subset.label_tags.is_empty());
+        assert!(subset.label_type_tags.is_empty());
+        assert!(subset.relations.is_empty());
+        let all = extract(&resource, |_| true);
+        assert_eq!(all.part_of_speech_tags.iter().map(|t| t.tag.as_str()).collect::<Vec<_>>(), vec!["noun"]);
+        assert_eq!(all.label_tags.iter().map(|t| t.tag.as_str()).collect::<Vec<_>>(), vec!["informal"]);
+        assert_eq!(all.definition_type_tags.iter().map(|t| t.tag.as_str()).collect::<Vec<_>>(), vec!["normal"]);
+        assert_eq!(all.inflected_form_tags.iter().map(|t| t.tag.as_str()).collect::<Vec<_>>(), vec!["plural"]);
+        assert_eq!(all.source_identity_tags.iter().map(|t| t.tag.as_str()).collect::<Vec<_>>(), vec!["sourceIdentity"]);
+        assert_eq!(all.transcription_scheme_tags.iter().map(|t| t.tag.as_str()).collect::<Vec<_>>(), vec!["ipa"]);
+        assert!(all.label_type_tags.is_empty());
+        assert!(all.validate().is_ok());
+    }
+
+    #[test]
+    fn test_parts_of_speech() {
+        let mut resource = example("14.xml");
+        resource.part_of_speech_tags = ["n-fem", "n-neut", "n-masc"].iter()
+            .map(|t| PartOfSpeechTag { tag: t.to_string(), ..PartOfSpeechTag::default() }).collect();
+        let tags = vec!["n-fem".to_string(), "n-neut".to_string()];
+        let subset = extract(&resource, has_part_of_speech(&tags));
+        assert_eq!(subset.entries.iter().map(|e| e.headword.as_str()).collect::<Vec<_>>(), vec!["See", "Meer"]);
+        assert_eq!(subset.part_of_speech_tags.iter().map(|t| t.tag.as_str()).collect::<Vec<_>>(), vec!["n-fem", "n-neut"]);
+        assert!(subset.validate().is_ok());
+    }
+}
@@ -0,0 +1,654 @@
+//! Export and import of resources as CSV or TSV tables with one row per sense
+//!
+//! The entry columns are repeated on every row of the entry, and an entry
+//! without senses has a single row with empty sense columns. Properties with
+//! several values, such as parts of speech or examples, are joined with the
+//! value delimiter, and a value delimiter or backslash inside a value is
+//! escaped with a backslash. Example translations are aligned with the examples, so
+//! the n-th value is the translation of the n-th example. Rows are grouped
+//! into entries by their entry id, which, together with the sense id, lets
+//! edited tables be imported back onto the same entries and senses. Entries
+//! and senses without an id are exported with the id they would get on
+//! import, and a row without an entry id continues the entry of the row
+//! before it if it has the same headword, homograph number and parts of
+//! speech.
+use crate::model::*;
+use crate::registry::{ReadOptions, ResourceReader, ResourceWriter, WriteOptions};
+use crate::report::ConversionReport;
+use crate::{ParseError, WriteError};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use thiserror::Error;
+
+type Result<T> = std::result::Result<T, TabularError>;
+
+/// A column of the table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    EntryId,
+    Headword,
+    HomographNumber,
+    PartsOfSpeech,
+    EntryLabels,
+    SenseId,
+    Indicator,
+    SenseLabels,
+    Definitions,
+    Examples,
+    /// Headword translations, in the given language or without a language
+    Translations(Option<String>),
+    /// Translations of the examples, in the given language or without a
+    /// language
+    ExampleTranslations(Option<String>),
+}
+
+impl Column {
+    /// The name of the column in the header row
+    pub fn header(&self) -> String {
+        match self {
+            Column::EntryId => "entry_id".to_string(),
+            Column::Headword => "headword".to_string(),
+            Column::HomographNumber => "homograph_number".to_string(),
+            Column::PartsOfSpeech => "part_of_speech".to_string(),
+            Column::EntryLabels => "entry_labels".to_string(),
+            Column::SenseId => "sense_id".to_string(),
+            Column::Indicator => "indicator".to_string(),
+            Column::SenseLabels => "sense_labels".to_string(),
+            Column::Definitions => "definition".to_string(),
+            Column::Examples => "examples".to_string(),
+            Column::Translations(None) => "translations".to_string(),
+            Column::Translations(Some(lang)) => format!("translations_{}", lang),
+            Column::ExampleTranslations(None) => "example_translations".to_string(),
+            Column::ExampleTranslations(Some(lang)) => format!("example_translations_{}", lang),
+        }
+    }
+
+    pub fn from_header(header : &str) -> Option<Column> {
+        match header.trim() {
+            "entry_id" => Some(Column::EntryId),
+            "headword" => Some(Column::Headword),
+            "homograph_number" => Some(Column::HomographNumber),
+            "part_of_speech" => Some(Column::PartsOfSpeech),
+            "entry_labels" => Some(Column::EntryLabels),
+            "sense_id" => Some(Column::SenseId),
+            "indicator" => Some(Column::Indicator),
+            "sense_labels" => Some(Column::SenseLabels),
+            "definition" => Some(Column::Definitions),
+            "examples" => Some(Column::Examples),
+            "translations" => Some(Column::Translations(None)),
+            "example_translations" => Some(Column::ExampleTranslations(None)),
+            header => {
+                if let Some(lang) = header.strip_prefix("example_translations_") {
+                    Some(Column::ExampleTranslations(Some(lang.to_string())))
+                } else {
+                    header.strip_prefix("translations_")
+                        .map(|lang| Column::Translations(Some(lang.to_string())))
+                }
+            }
+        }
+    }
+
+    fn is_sense_column(&self) -> bool {
+        !matches!(self, Column::EntryId | Column::Headword | Column::HomographNumber
+            | Column::PartsOfSpeech | Column::EntryLabels)
+    }
+}
+
+/// The layout of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabularOptions {
+    /// The field delimiter, a comma for CSV and a tab for TSV
+    pub delimiter: u8,
+    /// The delimiter between the values of a column with several values
+    pub value_delimiter: String,
+    /// The columns to write, all columns if empty
+    pub columns: Vec<Column>,
+    /// The language of the headwords when importing, `und` if not given
+    pub lang_code: Option<String>,
+}
+
+impl TabularOptions {
+    pub fn csv() -> TabularOptions {
+        TabularOptions {
+            delimiter: b',',
+            value_delimiter: "|".to_string(),
+            columns: Vec::new(),
+            lang_code: None,
+        }
+    }
+
+    pub fn tsv() -> TabularOptions {
+        TabularOptions {
+            delimiter: b'\t',
+            ..TabularOptions::csv()
+        }
+    }
+
+    pub fn with_columns(mut self, columns : Vec<Column>) -> TabularOptions {
+        self.columns = columns;
+        self
+    }
+
+    pub fn with_value_delimiter(mut self, value_delimiter : &str) -> TabularOptions {
+        self.value_delimiter = value_delimiter.to_string();
+        self
+    }
+
+    pub fn with_lang_code(mut self, lang_code : &str) -> TabularOptions {
+        self.lang_code = Some(lang_code.to_string());
+        self
+    }
+
+    /// The columns written for a resource: the selected columns, or all
+    /// columns with translation columns for each translation language
+    pub fn columns_for(&self, resource : &LexicographicResource) -> Vec<Column> {
+        if !self.columns.is_empty() {
+            return self.columns.clone();
+        }
+        let mut columns = vec![Column::EntryId, Column::Headword, Column::HomographNumber,
+            Column::PartsOfSpeech, Column::EntryLabels, Column::SenseId, Column::Indicator,
+            Column::SenseLabels, Column::Definitions, Column::Examples];
+        let langs = translation_langs(resource);
+        for lang in langs.iter() {
+            columns.push(Column::Translations(lang.clone()));
+        }
+        for lang in langs.iter() {
+            columns.push(Column::ExampleTranslations(lang.clone()));
+        }
+        columns
+    }
+}
+
+impl Default for TabularOptions {
+    fn default() -> TabularOptions {
+        TabularOptions::csv()
+    }
+}
+
+/// The languages of the translations, with `None` for translations without
+/// a language, in the order of the translation languages of the resource
+fn translation_langs(resource : &LexicographicResource) -> Vec<Option<String>> {
+    let mut langs : Vec<Option<String>> = resource.translation_languages.iter().map(|l| Some(l.clone())).collect();
+    for sense in resource.entries.iter().flat_map(|e| e.senses.iter()) {
+        let used = sense.headword_translations.iter().map(|t| &t.lang_code)
+            .chain(sense.examples.iter().flat_map(|e| e.example_translations.iter()).map(|t| &t.lang_code));
+        for lang in used {
+            let lang = lang.as_ref().map(|l| l.0.clone());
+            if !langs.contains(&lang) {
+                langs.push(lang);
+            }
+        }
+    }
+    langs
+}
+
+/// Write a resource as a table
+pub fn write_table<W : Write>(output : W, resource : &LexicographicResource, options : &TabularOptions) -> Result<ConversionReport> {
+    let columns = options.columns_for(resource);
+    // Entries and senses without an id are written with the id they get when
+    // the table is read, so that their rows stay together
+    let with_ids;
+    let entries = if resource.entries.iter().any(|e| e.id.is_none() || e.senses.iter().any(|s| s.id.is_none())) {
+        let mut entries = resource.entries.clone();
+        assign_missing_ids(&mut entries);
+        with_ids = entries;
+        &with_ids
+    } else {
+        &resource.entries
+    };
+    let mut writer = csv::WriterBuilder::new().delimiter(options.delimiter).from_writer(output);
+    writer.write_record(columns.iter().map(|c| c.header()))?;
+    let mut report = ConversionReport::new();
+    let mut lost = HashSet::new();
+    for entry in entries.iter() {
+        note_lost(entry, &mut lost);
+        if entry.senses.is_empty() {
+            writer.write_record(columns.iter().map(|c| cell(c, entry, None, options)))?;
+        }
+        for sense in entry.senses.iter() {
+            writer.write_record(columns.iter().map(|c| cell(c, entry, Some(sense), options)))?;
+        }
+    }
+    writer.flush()?;
+    let mut lost : Vec<&str> = lost.into_iter().collect();
+    lost.sort();
+    for what in lost {
+        report.warn("lexicographicResource", format!("{} are not exported", what));
+    }
+    Ok(report)
+}
+
+/// Read a resource from a table with a header row
+pub fn read_table<R : Read>(input : R, options : &TabularOptions) -> Result<(LexicographicResource, ConversionReport)> {
+    let mut reader = csv::ReaderBuilder::new().delimiter(options.delimiter).flexible(true).from_reader(input);
+    let mut report = ConversionReport::new();
+    let headers = reader.headers()?.clone();
+    let columns : Vec<Option<Column>> = headers.iter().map(Column::from_header).collect();
+    for (header, column) in headers.iter().zip(columns.iter()) {
+        if column.is_none() {
+            report.warn("line 1", format!("Column {} not mapped", header));
+        }
+    }
+    // The index of the entry of each entry id, as the rows of an entry need
+    // not be adjacent
+    let mut entry_indices : HashMap<String, usize> = HashMap::new();
+    if !columns.contains(&Some(Column::Headword)) {
+        return Err(TabularError::MissingColumn(Column::Headword.header()));
+    }
+    let mut resource = LexicographicResource {
+        lang_code: LangCode(options.lang_code.clone().unwrap_or_else(|| "und".to_string())),
+        ..LexicographicResource::default()
+    };
+    for column in columns.iter().flatten() {
+        if let Column::Translations(Some(lang)) | Column::ExampleTranslations(Some(lang)) = column {
+            if !resource.translation_languages.contains(lang) {
+                resource.translation_languages.push(lang.clone());
+            }
+        }
+    }
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        let line = record.position().map(|p| p.line()).unwrap_or(index as u64 + 2);
+        let value = |column : &Column| columns.iter().position(|c| c.as_ref() == Some(column))
+            .and_then(|i| record.get(i))
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty());
+        let split = |column : &Column| value(column).map(|v| split_values(v, &options.value_delimiter)).unwrap_or_default();
+        let entry_id = value(&Column::EntryId).map(|s| s.to_string());
+        // A row without an entry id continues the previous entry if it has
+        // the same headword, homograph number and parts of speech
+        let known = match &entry_id {
+            Some(id) => entry_indices.get(id).copied(),
+            None => resource.entries.last()
+                .filter(|last| last.headword == value(&Column::Headword).unwrap_or_default()
+                    && last.homograph_number == value(&Column::HomographNumber).and_then(|n| n.parse().ok())
+                    && last.parts_of_speech == split(&Column::PartsOfSpeech))
+                .map(|_| resource.entries.len() - 1),
+        };
+        let entry_index = match known {
+            Some(i) => i,
+            None => {
+                let homograph_number = match value(&Column::HomographNumber) {
+                    Some(n) => match n.parse() {
+                        Ok(n) => Some(n),
+                        Err(_) => {
+                            report.warn(format!("line {}", line), format!("Homograph number {} is not a number", n));
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                if let Some(id) = &entry_id {
+                    entry_indices.insert(id.clone(), resource.entries.len());
+                }
+                resource.entries.push(Entry {
+                    id: entry_id,
+                    headword: value(&Column::Headword).unwrap_or_default().to_string(),
+                    homograph_number,
+                    parts_of_speech: split(&Column::PartsOfSpeech),
+                    labels: split(&Column::EntryLabels),
+                    ..Entry::default()
+                });
+                resource.entries.len() - 1
+            }
+        };
+        let has_sense = columns.iter().flatten().any(|c| c.is_sense_column() && value(c).is_some());
+        if !has_sense {
+            continue;
+        }
+        let mut sense = Sense {
+            id: value(&Column::SenseId).map(|s| s.to_string()),
+            indicator: value(&Column::Indicator).map(|s| s.to_string()),
+            labels: split(&Column::SenseLabels),
+            definitions: split(&Column::Definitions).into_iter().map(|text| Definition {
+                text,
+                ..Definition::default()
+            }).collect(),
+            examples: split(&Column::Examples).into_iter().map(|text| Example {
+                text,
+                ..Example::default()
+            }).collect(),
+            ..Sense::default()
+        };
+        for column in columns.iter().flatten() {
+            match column {
+                Column::Translations(lang) => {
+                    sense.headword_translations.extend(split(column).into_iter().map(|text| HeadwordTranslation {
+                        text,
+                        lang_code: lang.clone().map(LangCode),
+                        ..HeadwordTranslation::default()
+                    }));
+                },
+                Column::ExampleTranslations(lang) => {
+                    let values = value(column).map(|v| split_escaped(v, &options.value_delimiter)
+                        .into_iter().map(|s| s.trim().to_string()).collect::<Vec<_>>()).unwrap_or_default();
+                    if values.len() > sense.examples.len() {
+                        report.warn(format!("line {}", line), format!("More values in {} than examples", column.header()));
+                    }
+                    for (example, text) in sense.examples.iter_mut().zip(values) {
+                        if !text.is_empty() {
+                            example.example_translations.push(ExampleTranslation {
+                                text,
+                                lang_code: lang.clone().map(LangCode),
+                                ..ExampleTranslation::default()
+                            });
+                        }
+                    }
+                },
+                _ => (),
+            }
+        }
+        resource.entries[entry_index].senses.push(sense);
+    }
+    assign_missing_ids(&mut resource.entries);
+    Ok((resource, report))
+}
+
+fn split_values(value : &str, delimiter : &str) -> Vec<String> {
+    split_escaped(value, delimiter).iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Split a cell at the value delimiters that are not escaped by a backslash
+/// and unescape the values
+fn split_escaped(value : &str, delimiter : &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut rest = value;
+    while let Some(c) = rest.chars().next() {
+        if let Some(escaped) = rest.strip_prefix('\\') {
+            if let Some(after) = escaped.strip_prefix(delimiter).filter(|_| !delimiter.is_empty()) {
+                current.push_str(delimiter);
+                rest = after;
+            } else if let Some(c) = escaped.chars().next() {
+                current.push(c);
+                rest = &escaped[c.len_utf8()..];
+            } else {
+                current.push('\\');
+                rest = escaped;
+            }
+        } else if let Some(after) = rest.strip_prefix(delimiter).filter(|_| !delimiter.is_empty()) {
+            values.push(std::mem::take(&mut current));
+            rest = after;
+        } else {
+            current.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    values.push(current);
+    values
+}
+
+/// Escape the backslashes and value delimiters of a value
+fn escape_value(value : &str, delimiter : &str) -> String {
+    let value = value.replace('\\', "\\\\");
+    if delimiter.is_empty() {
+        value
+    } else {
+        value.replace(delimiter, &format!("\\{}", delimiter))
+    }
+}
+
+fn cell(column : &Column, entry : &Entry, sense : Option<&Sense>, options : &TabularOptions) -> String {
+    let join = |values : Vec<&str>| values.iter()
+        .map(|v| escape_value(v, &options.value_delimiter))
+        .collect::<Vec<_>>()
+        .join(&options.value_delimiter);
+    let lang_matches = |lang_code : &Option<LangCode>, lang : &Option<String>| lang_code.as_ref().map(|l| &l.0) == lang.as_ref();
+    match column {
+        Column::EntryId => entry.id.clone().unwrap_or_default(),
+        Column::Headword => entry.headword.clone(),
+        Column::HomographNumber => entry.homograph_number.map(|n| n.to_string()).unwrap_or_default(),
+        Column::PartsOfSpeech => join(entry.parts_of_speech.iter().map(|s| s.as_str()).collect()),
+        Column::EntryLabels => join(entry.labels.iter().map(|s| s.as_str()).collect()),
+        Column::SenseId => sense.and_then(|s| s.id.clone()).unwrap_or_default(),
+        Column::Indicator => sense.and_then(|s| s.indicator.clone()).unwrap_or_default(),
+        Column::SenseLabels => sense.map(|s| join(s.labels.iter().map(|l| l.as_str()).collect())).unwrap_or_default(),
+        Column::Definitions => sense.map(|s| join(s.definitions.iter().map(|d| d.text.as_str()).collect())).unwrap_or_default(),
+        Column::Examples => sense.map(|s| join(s.examples.iter().map(|e| e.text.as_str()).collect())).unwrap_or_default(),
+        Column::Translations(lang) => sense.map(|s| join(s.headword_translations.iter()
+            .filter(|t| lang_matches(&t.lang_code, lang))
+            .map(|t| t.text.as_str()).collect())).unwrap_or_default(),
+        Column::ExampleTranslations(lang) => sense.map(|s| {
+            let values : Vec<&str> = s.examples.iter().map(|e| e.example_translations.iter()
+                .find(|t| lang_matches(&t.lang_code, lang))
+                .map(|t| t.text.as_str()).unwrap_or("")).collect();
+            if values.iter().all(|v| v.is_empty()) { String::new() } else { join(values) }
+        }).unwrap_or_default(),
+    }
+}
+
+/// Record the kinds of content of an entry that have no column
+fn note_lost(entry : &Entry, lost : &mut HashSet<&'static str>) {
+    if !entry.pronunciations.is_empty() {
+        lost.insert("Pronunciations");
+    }
+    if !entry.inflected_forms.is_empty() {
+        lost.insert("Inflected forms");
+    }
+    if !entry.etymologies.is_empty() {
+        lost.insert("Etymologies");
+    }
+    if !entry.placeholder_markers.is_empty() {
+        lost.insert("Placeholder markers");
+    }
+    for sense in entry.senses.iter() {
+        if !sense.headword_explanations.is_empty() {
+            lost.insert("Headword explanations");
+        }
+        if sense.definitions.iter().any(|d| d.definition_type.is_some()
+            || !d.headword_markers.is_empty() || !d.collocate_markers.is_empty())
+            || sense.examples.iter().any(|e| !e.headword_markers.is_empty() || !e.collocate_markers.is_empty()) {
+            lost.insert("Definition types and markers");
+        }
+        if sense.examples.iter().any(|e| e.source_identity.is_some() || e.source_elaboration.is_some()
+            || !e.labels.is_empty() || e.sound_file.is_some()) {
+            lost.insert("Example sources, labels and sound files");
+        }
+        if sense.headword_translations.iter().any(|t| !t.parts_of_speech.is_empty() || !t.labels.is_empty()
+            || !t.pronunciations.is_empty() || !t.inflected_forms.is_empty()) {
+            lost.insert("Properties of headword translations other than the text");
+        }
+    }
+}
+
+/// Give entries and senses that were added in the table an id that is not
+/// used yet
+fn assign_missing_ids(entries : &mut [Entry]) {
+    let mut used : HashSet<String> = entries.iter()
+        .flat_map(|e| e.id.iter().chain(e.senses.iter().filter_map(|s| s.id.as_ref())))
+        .cloned()
+        .collect();
+    let mut fresh = |base : String| {
+        let mut id = base.clone();
+        let mut n = 1;
+        while used.contains(&id) {
+            n += 1;
+            id = format!("{}-{}", base, n);
+        }
+        used.insert(id.clone());
+        id
+    };
+    for entry in entries.iter_mut() {
+        if entry.id.is_none() {
+            entry.id = Some(fresh(entry.headword.split_whitespace().collect::<Vec<_>>().join("-")));
+        }
+        let entry_id = entry.id.clone().unwrap_or_default();
+        for (i, sense) in entry.senses.iter_mut().enumerate() {
+            if sense.id.is_none() {
+                sense.id = Some(fresh(format!("{}-{}", entry_id, i + 1)));
+            }
+        }
+    }
+}
+
+/// A table format for use in a `FormatRegistry`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TabularFormat {
+    pub options: TabularOptions,
+}
+
+impl TabularFormat {
+    pub fn csv() -> TabularFormat {
+        TabularFormat { options: TabularOptions::csv() }
+    }
+
+    pub fn tsv() -> TabularFormat {
+        TabularFormat { options: TabularOptions::tsv() }
+    }
+}
+
+impl ResourceReader for TabularFormat {
+    fn read_resource(&self, input : &mut dyn Read, options : &ReadOptions) -> std::result::Result<LexicographicResource, ParseError> {
+        Ok(self.read_resource_with_report(input, options)?.0)
+    }
+
+    fn read_resource_with_report(&self, input : &mut dyn Read, _options : &ReadOptions) -> std::result::Result<(LexicographicResource, ConversionReport), ParseError> {
+        Ok(read_table(input, &self.options)?)
+    }
+}
+
+impl ResourceWriter for TabularFormat {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> std::result::Result<(), WriteError> {
+        self.write_resource_with_report(output, resource, options)?;
+        Ok(())
+    }
+
+    fn write_resource_with_report(&self, output : &mut dyn Write, resource : &LexicographicResource, _options : &WriteOptions) -> std::result::Result<ConversionReport, WriteError> {
+        Ok(write_table(output, resource, &self.options)?)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TabularError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The table has no {0} column")]
+    MissingColumn(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_entry, Format};
+    use std::fs::File;
+
+    #[test]
+    fn test_round_trip_csv() {
+        let resource = parse(File::open("examples/0.xml").unwrap(), &Format::XML, &None).unwrap();
+        let mut out = Vec::new();
+        let report = write_table(&mut out, &resource, &TabularOptions::csv()).unwrap();
+        assert_eq!(report.warnings[0].message, "Example sources, labels and sound files are not exported");
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert_eq!(text.lines().next().unwrap(),
+            "entry_id,headword,homograph_number,part_of_speech,entry_labels,sense_id,indicator,sense_labels,definition,examples");
+        assert_eq!(text.lines().nth(1).unwrap(),
+            "abandon-verb,abandon,,verb,,abandon-verb-1,,,to suddenly leave a place or a person,I'm sorry I abandoned you like that.|Abandon ship!");
+        let (resource2, read_report) = read_table(out.as_slice(), &TabularOptions::csv().with_lang_code("en")).unwrap();
+        assert!(read_report.is_empty(), "{}", read_report);
+        let mut expected = resource.clone();
+        // Example labels have no column
+        expected.entries[0].senses[0].examples[1].labels.clear();
+        assert_eq!(expected.entries, resource2.entries);
+    }
+
+    #[test]
+    fn test_round_trip_tsv_translations() {
+        let entry = parse_entry(File::open("examples/11.xml").unwrap(), &Format::XML, &None).unwrap();
+        let mut entry2 = parse_entry(File::open("examples/2.xml").unwrap(), &Format::XML, &None).unwrap();
+        entry2.pronunciations.clear();
+        let resource = LexicographicResource {
+            lang_code: LangCode("ga".to_string()),
+            entries: vec![entry, entry2],
+            translation_languages: vec!["en".to_string(), "de".to_string(), "cs".to_string()],
+            ..LexicographicResource::default()
+        };
+        let options = TabularOptions::tsv().with_value_delimiter("; ").with_lang_code("ga");
+        let mut out = Vec::new();
+        write_table(&mut out, &resource, &options).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert!(text.contains("autumn; fall\tHerbst\tpodzim"), "{}", text);
+        let (resource2, _) = read_table(out.as_slice(), &options).unwrap();
+        assert_eq!(resource, resource2);
+    }
+
+    #[test]
+    fn test_import_edited_table() {
+        let table = "entry_id,headword,sense_id,definition,examples,example_translations_fr,colour\n\
+            cat,cat,cat-1,a small animal,The cat sat.|A cat!,Le chat s'assit.,black\n\
+            cat,cat,,a jazz fan,,,\n\
+            ,dog,,,,,\n";
+        let (resource, report) = read_table(table.as_bytes(), &TabularOptions::csv()).unwrap();
+        assert_eq!(resource.translation_languages, vec!["fr"]);
+        assert_eq!(resource.entries.len(), 2);
+        let cat = &resource.entries[0];
+        assert_eq!(cat.senses.len(), 2);
+        assert_eq!(cat.senses[1].id, Some("cat-2".to_string()));
+        assert_eq!(cat.senses[0].examples[0].example_translations[0].text, "Le chat s'assit.");
+        assert!(cat.senses[0].examples[1].example_translations.is_empty());
+        assert_eq!(resource.entries[1].id, Some("dog".to_string()));
+        assert!(resource.entries[1].senses.is_empty());
+        assert_eq!(report.warnings[0].message, "Column colour not mapped");
+    }
+
+    #[test]
+    fn test_round_trip_without_ids() {
+        let mut resource = parse(File::open("examples/0.xml").unwrap(), &Format::XML, &None).unwrap();
+        resource.entries[0].senses[0].examples[1].labels.clear();
+        resource.entries[0].id = None;
+        for sense in resource.entries[0].senses.iter_mut() {
+            sense.id = None;
+        }
+        let mut out = Vec::new();
+        write_table(&mut out, &resource, &TabularOptions::csv()).unwrap();
+        let (resource2, _) = read_table(out.as_slice(), &TabularOptions::csv().with_lang_code("en")).unwrap();
+        assign_missing_ids(&mut resource.entries);
+        assert_eq!(resource.entries, resource2.entries);
+        assert_eq!(resource2.entries[0].id, Some("abandon".to_string()));
+        let table = "headword,part_of_speech,definition\ncat,noun,a small animal\ncat,noun,a jazz fan\ncat,verb,to vomit\n";
+        let (resource, _) = read_table(table.as_bytes(), &TabularOptions::csv()).unwrap();
+        assert_eq!(resource.entries.iter().map(|e| e.senses.len()).collect::<Vec<_>>(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_rows_grouped_by_entry_id() {
+        let table = "entry_id,headword,sense_id,definition\n\
+            cat,cat,cat-1,a small animal\n\
+            dog,dog,dog-1,a loyal animal\n\
+            cat,cat,cat-2,a jazz fan\n";
+        let (resource, _) = read_table(table.as_bytes(), &TabularOptions::csv()).unwrap();
+        assert_eq!(resource.entries.len(), 2);
+        assert_eq!(resource.entries[0].senses.iter().map(|s| s.id.clone().unwrap()).collect::<Vec<_>>(), vec!["cat-1", "cat-2"]);
+        assert_eq!(resource.entries[1].senses.len(), 1);
+    }
+
+    #[test]
+    fn test_escaped_value_delimiter() {
+        let mut resource = parse(File::open("examples/0.xml").unwrap(), &Format::XML, &None).unwrap();
+        resource.entries[0].senses[0].definitions[0].text = "either|or, with a \\ backslash\\".to_string();
+        resource.entries[0].senses[0].examples[1].labels.clear();
+        resource.entries[0].senses[0].examples[0].text = "A|B".to_string();
+        let mut out = Vec::new();
+        write_table(&mut out, &resource, &TabularOptions::csv()).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert!(text.contains(",\"either\\|or, with a \\\\ backslash\\\\\",A\\|B|Abandon ship!"), "{}", text);
+        let (resource2, _) = read_table(out.as_slice(), &TabularOptions::csv().with_lang_code("en")).unwrap();
+        assert_eq!(resource.entries, resource2.entries);
+        assert_eq!(split_escaped("a\\|b|c\\\\|d\\", "|"), vec!["a|b", "c\\", "d\\"]);
+    }
+
+    #[test]
+    fn test_column_selection() {
+        let resource = parse(File::open("examples/0.xml").unwrap(), &Format::XML, &None).unwrap();
+        let options = TabularOptions::csv().with_columns(vec![Column::Headword, Column::Definitions]);
+        let mut out = Vec::new();
+        write_table(&mut out, &resource, &options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+            "headword,definition\nabandon,to suddenly leave a place or a person\nabandon,to stop supporting an idea\n");
+    }
+}
@@ -0,0 +1,658 @@
+//! Import and export of TEI Lex-0 dictionaries
+//!
+//! Only the core of TEI Lex-0 is mapped: entries with their lemma and
+//! inflected forms, parts of speech, usage labels, senses with definitions,
+//! examples and translation equivalents, and etymologies. Anything else is
+//! listed in the `ConversionReport` returned with the result.
+use crate::detect::{xml_root_name, RootKind};
+use crate::model::*;
+use crate::registry::{ReadOptions, ResourceReader, ResourceWriter, WriteOptions};
+use crate::report::{note_unmapped_tags, ConversionReport};
+use crate::xml_tree::{location, read_tree, unmapped, write_tree, Element, Node};
+use crate::{ParseError, WriteError};
+use std::io::{Read, Write};
+use thiserror::Error;
+
+pub static TEI_NS : &str = "http://www.tei-c.org/ns/1.0";
+
+type Result<T> = std::result::Result<T, TeiError>;
+
+/// Read a TEI Lex-0 document
+pub fn read_tei<R : Read>(input : R) -> Result<(LexicographicResource, ConversionReport)> {
+    let root = read_tree(input)?;
+    if root.name != "TEI" {
+        return Err(TeiError::UnexpectedRoot(root.name));
+    }
+    let mut report = ConversionReport::new();
+    let mut resource = LexicographicResource::default();
+    if let Some(header) = root.child("teiHeader") {
+        let file_desc = header.child("fileDesc");
+        resource.title = file_desc
+            .and_then(|f| f.child("titleStmt"))
+            .and_then(|t| t.child_text("title"))
+            .filter(|t| !t.is_empty());
+        resource.uri = file_desc
+            .and_then(|f| f.child("publicationStmt"))
+            .and_then(|p| p.elements_named("idno").find(|i| i.attr("type") == Some("URI")))
+            .map(|i| i.text());
+    }
+    let text = root.child("text");
+    let body = text.and_then(|t| t.child("body"));
+    let lang = root.attr("xml:lang")
+        .or_else(|| text.and_then(|t| t.attr("xml:lang")))
+        .or_else(|| body.and_then(|b| b.attr("xml:lang")));
+    if let Some(body) = body {
+        read_body(body, &mut resource.entries, &mut report)?;
+    } else {
+        report.warn(location(&root), "TEI document has no <text>/<body>");
+    }
+    resource.lang_code = match lang {
+        Some(lang) => LangCode(lang.to_string()),
+        None => {
+            report.warn(location(&root), "No xml:lang given for the dictionary, using 'und'");
+            LangCode("und".to_string())
+        }
+    };
+    Ok((resource, report))
+}
+
+/// Read a single TEI Lex-0 `<entry>`
+pub fn read_tei_entry<R : Read>(input : R) -> Result<(Entry, ConversionReport)> {
+    let root = read_tree(input)?;
+    if root.name != "entry" {
+        return Err(TeiError::UnexpectedRoot(root.name));
+    }
+    let mut report = ConversionReport::new();
+    let entry = read_entry(&root, &mut report)?;
+    Ok((entry, report))
+}
+
+/// Write a resource as a TEI Lex-0 document
+pub fn write_tei<W : Write>(output : W, resource : &LexicographicResource) -> Result<ConversionReport> {
+    let mut report = ConversionReport::new();
+    let mut publication = Element::new("publicationStmt")
+        .with_child(Element::new("p").with_text("Converted from DMLEX"));
+    if let Some(uri) = &resource.uri {
+        publication.push(Element::new("idno").with_attr("type", "URI").with_text(uri));
+    }
+    let header = Element::new("teiHeader")
+        .with_child(Element::new("fileDesc")
+            .with_child(Element::new("titleStmt")
+                .with_child(Element::new("title")
+                    .with_text(resource.title.as_deref().unwrap_or(""))))
+            .with_child(publication)
+            .with_child(Element::new("sourceDesc")
+                .with_child(Element::new("p").with_text("Converted from DMLEX"))));
+    let mut body = Element::new("body");
+    for entry in resource.entries.iter() {
+        body.push(write_entry(entry, resource, &mut report));
+    }
+    let root = Element::new("TEI")
+        .with_attr("xml:lang", &resource.lang_code.0)
+        .with_child(header)
+        .with_child(Element::new("text").with_child(body));
+    let here = "lexicographicResource";
+    if !resource.translation_languages.is_empty() {
+        report.warn(here, "Translation languages are not mapped");
+    }
+    if !resource.relations.is_empty() {
+        report.warn(here, format!("{} relation(s) not mapped", resource.relations.len()));
+    }
+    if !resource.relation_types.is_empty() {
+        report.warn(here, format!("{} relation type(s) not mapped", resource.relation_types.len()));
+    }
+    note_unmapped_tags(resource, here, &mut report);
+    write_tree(output, &root, Some(TEI_NS))?;
+    Ok(report)
+}
+
+/// Write a single entry as a TEI Lex-0 `<entry>`
+pub fn write_tei_entry<W : Write>(output : W, entry : &Entry) -> Result<ConversionReport> {
+    let mut report = ConversionReport::new();
+    let elem = write_entry(entry, &LexicographicResource::default(), &mut report);
+    write_tree(output, &elem, Some(TEI_NS))?;
+    Ok(report)
+}
+
+/// The TEI Lex-0 format for use in a `FormatRegistry`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TeiFormat;
+
+impl ResourceReader for TeiFormat {
+    fn read_resource(&self, input : &mut dyn Read, options : &ReadOptions) -> std::result::Result<LexicographicResource, ParseError> {
+        Ok(self.read_resource_with_report(input, options)?.0)
+    }
+
+    fn read_resource_with_report(&self, input : &mut dyn Read, _options : &ReadOptions) -> std::result::Result<(LexicographicResource, ConversionReport), ParseError> {
+        Ok(read_tei(input)?)
+    }
+
+    fn read_entry(&self, input : &mut dyn Read, _options : &ReadOptions) -> std::result::Result<Entry, ParseError> {
+        Ok(read_tei_entry(input)?.0)
+    }
+
+    fn detect_root(&self, content : &[u8]) -> Option<RootKind> {
+        match xml_root_name(content)?.as_str() {
+            "TEI" => Some(RootKind::LexicographicResource),
+            "entry" => Some(RootKind::Entry),
+            _ => None,
+        }
+    }
+}
+
+impl ResourceWriter for TeiFormat {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> std::result::Result<(), WriteError> {
+        self.write_resource_with_report(output, resource, options)?;
+        Ok(())
+    }
+
+    fn write_resource_with_report(&self, output : &mut dyn Write, resource : &LexicographicResource, _options : &WriteOptions) -> std::result::Result<ConversionReport, WriteError> {
+        Ok(write_tei(output, resource)?)
+    }
+
+    fn write_entry(&self, output : &mut dyn Write, entry : &Entry, _options : &WriteOptions) -> std::result::Result<(), WriteError> {
+        write_tei_entry(output, entry)?;
+        Ok(())
+    }
+}
+
+fn read_body(body : &Element, entries : &mut Vec<Entry>, report : &mut ConversionReport) -> Result<()> {
+    for child in body.elements() {
+        match child.name.as_str() {
+            "entry" => entries.push(read_entry(child, report)?),
+            "div" => read_body(child, entries, report)?,
+            "head" => (),
+            _ => unmapped(report, child, body),
+        }
+    }
+    Ok(())
+}
+
+fn read_entry(elem : &Element, report : &mut ConversionReport) -> Result<Entry> {
+    let mut entry = Entry {
+        id: elem.attr("xml:id").map(|s| s.to_string()),
+        ..Entry::default()
+    };
+    if let Some(n) = elem.attr("n") {
+        match n.parse() {
+            Ok(n) => entry.homograph_number = Some(n),
+            Err(_) => report.warn(location(elem), format!("Homograph number {} is not a number", n)),
+        }
+    }
+    let mut headword = None;
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "form" => match child.attr("type") {
+                Some("lemma") if headword.is_none() => {
+                    headword = Some(child.child_text("orth").unwrap_or_default());
+                    entry.pronunciations.extend(read_prons(child, &["orth"], report));
+                },
+                Some("inflected") => {
+                    entry.inflected_forms.push(InflectedForm {
+                        text: child.child_text("orth").unwrap_or_default(),
+                        tag: child.child("gramGrp").map(|g| g.elements_named("gram")
+                            .map(|g| g.text()).collect::<Vec<_>>().join(" ")),
+                        pronunciations: read_prons(child, &["orth", "gramGrp"], report),
+                        labels: child.elements_named("usg").map(|u| u.text()).collect(),
+                    });
+                },
+                _ => unmapped(report, child, elem),
+            },
+            "gramGrp" => entry.parts_of_speech.extend(read_pos(child, report)),
+            "usg" => entry.labels.push(child.text()),
+            "sense" => entry.senses.push(read_sense(child, report)),
+            "etym" => entry.etymologies.push(read_etym(child)),
+            _ => unmapped(report, child, elem),
+        }
+    }
+    entry.headword = headword.ok_or_else(|| TeiError::MissingLemma(location(elem)))?;
+    Ok(entry)
+}
+
+/// Read the `<pron>` children of a form, reporting anything other than the
+/// expected children
+fn read_prons(form : &Element, expected : &[&str], report : &mut ConversionReport) -> Vec<Pronunciation> {
+    let mut prons = Vec::new();
+    for child in form.elements() {
+        if child.name == "pron" {
+            prons.push(Pronunciation {
+                transcriptions: vec![Transcription {
+                    text: child.text(),
+                    scheme: child.attr("notation").map(|s| s.to_string()),
+                }],
+                ..Pronunciation::default()
+            });
+        } else if !expected.contains(&child.name.as_str()) && child.name != "usg" {
+            unmapped(report, child, form);
+        }
+    }
+    prons
+}
+
+fn read_pos(gram_grp : &Element, report : &mut ConversionReport) -> Vec<String> {
+    let mut pos = Vec::new();
+    for gram in gram_grp.elements() {
+        if gram.name == "gram" && gram.attr("type") == Some("pos") {
+            let text = gram.text();
+            if text.is_empty() {
+                pos.extend(gram.attr("norm").map(|s| s.to_string()));
+            } else {
+                pos.push(text);
+            }
+        } else {
+            report.warn(location(gram), format!("<{} type=\"{}\"> in <gramGrp> not mapped",
+                    gram.name, gram.attr("type").unwrap_or("")));
+        }
+    }
+    pos
+}
+
+fn read_sense(elem : &Element, report : &mut ConversionReport) -> Sense {
+    let mut sense = Sense {
+        id: elem.attr("xml:id").map(|s| s.to_string()),
+        ..Sense::default()
+    };
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "usg" if child.attr("type") == Some("hint") && sense.indicator.is_none() => {
+                sense.indicator = Some(child.text());
+            },
+            "usg" => sense.labels.push(child.text()),
+            "def" => sense.definitions.push(Definition {
+                text: child.text(),
+                definition_type: child.attr("type").map(|s| s.to_string()),
+                ..Definition::default()
+            }),
+            "cit" => match child.attr("type") {
+                Some("example") => sense.examples.push(read_example(child, report)),
+                Some("translationEquivalent") => sense.headword_translations.push(read_translation(child, report)),
+                _ => unmapped(report, child, elem),
+            },
+            _ => unmapped(report, child, elem),
+        }
+    }
+    sense
+}
+
+fn read_example(elem : &Element, report : &mut ConversionReport) -> Example {
+    let mut example = Example::default();
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "quote" => example.text = child.text(),
+            "bibl" => example.source_elaboration = Some(child.text()),
+            "usg" => example.labels.push(child.text()),
+            "cit" if child.attr("type") == Some("translation") => {
+                example.example_translations.push(ExampleTranslation {
+                    text: child.child_text("quote").unwrap_or_default(),
+                    lang_code: child.attr("xml:lang").map(|l| LangCode(l.to_string())),
+                    labels: child.elements_named("usg").map(|u| u.text()).collect(),
+                    ..ExampleTranslation::default()
+                });
+            },
+            _ => unmapped(report, child, elem),
+        }
+    }
+    example
+}
+
+fn read_translation(elem : &Element, report : &mut ConversionReport) -> HeadwordTranslation {
+    let mut translation = HeadwordTranslation {
+        lang_code: elem.attr("xml:lang").map(|l| LangCode(l.to_string())),
+        ..HeadwordTranslation::default()
+    };
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "form" => {
+                translation.text = child.child_text("orth").unwrap_or_default();
+                translation.pronunciations.extend(read_prons(child, &["orth"], report));
+            },
+            "quote" => translation.text = child.text(),
+            "gramGrp" => translation.parts_of_speech.extend(read_pos(child, report)),
+            "usg" => translation.labels.push(child.text()),
+            _ => unmapped(report, child, elem),
+        }
+    }
+    translation
+}
+
+fn read_etym(elem : &Element) -> Etymology {
+    let mut etymology = Etymology::default();
+    let mut description = String::new();
+    for child in elem.children.iter() {
+        match child {
+            Node::Element(e) if e.name == "cit" && e.attr("type") == Some("etymon") => {
+                etymology.etymons.push(read_etymon(e));
+            },
+            Node::Element(e) => {
+                if e.name == "mentioned" {
+                    etymology.etymons.push(Etymon {
+                        etymon_units: vec![EtymonUnit {
+                            lang_code: LangCode(e.attr("xml:lang").unwrap_or("und").to_string()),
+                            text: e.text(),
+                            ..EtymonUnit::default()
+                        }],
+                        ..Etymon::default()
+                    });
+                }
+                description.push(' ');
+                description.push_str(&e.text());
+            },
+            Node::Text(t) => description.push_str(t),
+        }
+    }
+    let description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !description.is_empty() {
+        etymology.description = Some(description);
+    }
+    etymology
+}
+
+fn read_etymon(elem : &Element) -> Etymon {
+    let lang = elem.attr("xml:lang").unwrap_or("und");
+    let mut etymon = Etymon {
+        _type: elem.attr("subtype").map(|s| s.to_string()),
+        when: elem.child_text("date"),
+        note: elem.child_text("note"),
+        translation: elem.child_text("gloss"),
+        ..Etymon::default()
+    };
+    for child in elem.elements() {
+        if child.name == "form" || child.name == "mentioned" {
+            etymon.etymon_units.push(EtymonUnit {
+                lang_code: LangCode(child.attr("xml:lang").unwrap_or(lang).to_string()),
+                text: child.child_text("orth").unwrap_or_else(|| child.text()),
+                reconstructed: if child.attr("type") == Some("reconstructed") { Some(true) } else { None },
+                parts_of_speech: child.elements_named("gramGrp")
+                    .flat_map(|g| g.elements_named("gram").map(|g| g.text()))
+                    .collect(),
+                translation: child.child_text("gloss"),
+            });
+        }
+    }
+    etymon
+}
+
+fn write_entry(entry : &Entry, resource : &LexicographicResource, report : &mut ConversionReport) -> Element {
+    let here = format!("entry {}", entry.id.as_deref().unwrap_or(&entry.headword));
+    let mut elem = Element::new("entry")
+        .with_opt_attr("xml:id", &entry.id)
+        .with_opt_attr("n", &entry.homograph_number.map(|n| n.to_string()));
+    let mut lemma = Element::new("form").with_attr("type", "lemma")
+        .with_child(Element::new("orth").with_text(&entry.headword));
+    write_prons(&mut lemma, &entry.pronunciations, &here, report);
+    elem.push(lemma);
+    if !entry.placeholder_markers.is_empty() {
+        report.warn(&here, "Placeholder markers are not mapped");
+    }
+    if let Some(gram_grp) = write_pos(&entry.parts_of_speech) {
+        elem.push(gram_grp);
+    }
+    for label in entry.labels.iter() {
+        elem.push(write_label(label, resource));
+    }
+    for form in entry.inflected_forms.iter() {
+        let mut f = Element::new("form").with_attr("type", "inflected");
+        if let Some(tag) = &form.tag {
+            f.push(Element::new("gramGrp")
+                .with_child(Element::new("gram").with_attr("type", "msd").with_text(tag)));
+        }
+        f.push(Element::new("orth").with_text(&form.text));
+        for label in form.labels.iter() {
+            f.push(write_label(label, resource));
+        }
+        write_prons(&mut f, &form.pronunciations, &here, report);
+        elem.push(f);
+    }
+    for sense in entry.senses.iter() {
+        elem.push(write_sense(sense, resource, &here, report));
+    }
+    for etymology in entry.etymologies.iter() {
+        elem.push(write_etym(etymology));
+    }
+    elem
+}
+
+fn write_prons(form : &mut Element, prons : &[Pronunciation], here : &str, report : &mut ConversionReport) {
+    for pron in prons.iter() {
+        for transcription in pron.transcriptions.iter() {
+            form.push(Element::new("pron")
+                .with_opt_attr("notation", &transcription.scheme)
+                .with_text(&transcription.text));
+        }
+        if pron.sound_file.is_some() {
+            report.warn(here, "Pronunciation sound files are not mapped");
+        }
+        if !pron.labels.is_empty() {
+            report.warn(here, "Pronunciation labels are not mapped");
+        }
+    }
+}
+
+fn write_pos(parts_of_speech : &[String]) -> Option<Element> {
+    if parts_of_speech.is_empty() {
+        None
+    } else {
+        let mut gram_grp = Element::new("gramGrp");
+        for pos in parts_of_speech.iter() {
+            gram_grp.push(Element::new("gram").with_attr("type", "pos").with_text(pos));
+        }
+        Some(gram_grp)
+    }
+}
+
+fn write_label(label : &str, resource : &LexicographicResource) -> Element {
+    let label_type = resource.label_tags.iter()
+        .find(|t| t.tag == label)
+        .and_then(|t| t.type_tag.clone())
+        .filter(|t| t != "hint");
+    Element::new("usg").with_opt_attr("type", &label_type).with_text(label)
+}
+
+fn write_sense(sense : &Sense, resource : &LexicographicResource, entry_loc : &str, report : &mut ConversionReport) -> Element {
+    let here = format!("{}, sense {}", entry_loc, sense.id.as_deref().unwrap_or(""));
+    let mut elem = Element::new("sense").with_opt_attr("xml:id", &sense.id);
+    if let Some(indicator) = &sense.indicator {
+        elem.push(Element::new("usg").with_attr("type", "hint").with_text(indicator));
+    }
+    for label in sense.labels.iter() {
+        elem.push(write_label(label, resource));
+    }
+    for definition in sense.definitions.iter() {
+        elem.push(Element::new("def")
+            .with_opt_attr("type", &definition.definition_type)
+            .with_text(&definition.text));
+        if !definition.headword_markers.is_empty() || !definition.collocate_markers.is_empty() {
+            report.warn(&here, "Markers in definitions are not mapped");
+        }
+    }
+    for example in sense.examples.iter() {
+        let mut cit = Element::new("cit").with_attr("type", "example")
+            .with_child(Element::new("quote").with_text(&example.text));
+        for label in example.labels.iter() {
+            cit.push(write_label(label, resource));
+        }
+        if let Some(source) = &example.source_elaboration {
+            cit.push(Element::new("bibl").with_text(source));
+        }
+        for translation in example.example_translations.iter() {
+            let mut t = Element::new("cit").with_attr("type", "translation")
+                .with_opt_attr("xml:lang", &translation.lang_code.as_ref().map(|l| l.0.clone()))
+                .with_child(Element::new("quote").with_text(&translation.text));
+            for label in translation.labels.iter() {
+                t.push(write_label(label, resource));
+            }
+            if translation.sound_file.is_some() || !translation.headword_markers.is_empty()
+                || !translation.collocate_markers.is_empty() {
+                report.warn(&here, "Sound files and markers of example translations are not mapped");
+            }
+            cit.push(t);
+        }
+        if example.source_identity.is_some() {
+            report.warn(&here, "Example source identities are not mapped");
+        }
+        if example.sound_file.is_some() {
+            report.warn(&here, "Example sound files are not mapped");
+        }
+        if !example.headword_markers.is_empty() || !example.collocate_markers.is_empty() {
+            report.warn(&here, "Markers in examples are not mapped");
+        }
+        elem.push(cit);
+    }
+    for translation in sense.headword_translations.iter() {
+        let mut form = Element::new("form")
+            .with_child(Element::new("orth").with_text(&translation.text));
+        write_prons(&mut form, &translation.pronunciations, &here, report);
+        let mut cit = Element::new("cit").with_attr("type", "translationEquivalent")
+            .with_opt_attr("xml:lang", &translation.lang_code.as_ref().map(|l| l.0.clone()))
+            .with_child(form);
+        if let Some(gram_grp) = write_pos(&translation.parts_of_speech) {
+            cit.push(gram_grp);
+        }
+        for label in translation.labels.iter() {
+            cit.push(write_label(label, resource));
+        }
+        if !translation.inflected_forms.is_empty() || !translation.placeholder_markers.is_empty() {
+            report.warn(&here, "Inflected forms and markers of headword translations are not mapped");
+        }
+        elem.push(cit);
+    }
+    if !sense.headword_explanations.is_empty() {
+        report.warn(&here, "Headword explanations are not mapped");
+    }
+    elem
+}
+
+fn write_etym(etymology : &Etymology) -> Element {
+    let mut elem = Element::new("etym");
+    if let Some(description) = &etymology.description {
+        elem = elem.with_text(description);
+    }
+    for etymon in etymology.etymons.iter() {
+        let mut cit = Element::new("cit").with_attr("type", "etymon")
+            .with_opt_attr("subtype", &etymon._type);
+        if let Some(when) = &etymon.when {
+            cit.push(Element::new("date").with_text(when));
+        }
+        for unit in etymon.etymon_units.iter() {
+            let mut form = Element::new("form").with_attr("xml:lang", &unit.lang_code.0);
+            if unit.reconstructed == Some(true) {
+                form = form.with_attr("type", "reconstructed");
+            }
+            form.push(Element::new("orth").with_text(&unit.text));
+            if let Some(gram_grp) = write_pos(&unit.parts_of_speech) {
+                form.push(gram_grp);
+            }
+            if let Some(translation) = &unit.translation {
+                form.push(Element::new("gloss").with_text(translation));
+            }
+            cit.push(form);
+        }
+        if let Some(translation) = &etymon.translation {
+            cit.push(Element::new("gloss").with_text(translation));
+        }
+        if let Some(note) = &etymon.note {
+            cit.push(Element::new("note").with_text(note));
+        }
+        elem.push(cit);
+    }
+    elem
+}
+
+#[derive(Error, Debug)]
+pub enum TeiError {
+    #[error("XML error: {0}")]
+    Xml(#[from] xml::reader::Error),
+    #[error("XML write error: {0}")]
+    XmlWrite(#[from] xml::writer::Error),
+    #[error("Expected a TEI document but found <{0}>")]
+    UnexpectedRoot(String),
+    #[error("Entry at {0} has no <form type=\"lemma\">")]
+    MissingLemma(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    fn round_trip(fname : &str) -> (LexicographicResource, LexicographicResource, ConversionReport) {
+        let resource = parse(File::open(fname).unwrap(), &Format::XML, &None).unwrap();
+        let mut out = Vec::new();
+        let report = write_tei(&mut out, &resource).unwrap();
+        let (resource2, read_report) = read_tei(out.as_slice()).unwrap();
+        assert!(read_report.is_empty(), "{}", read_report);
+        (resource, resource2, report)
+    }
+
+    #[test]
+    fn test_round_trip_tei_0() {
+        let (resource, resource2, report) = round_trip("examples/0.xml");
+        assert!(report.is_empty(), "{}", report);
+        assert_eq!(resource, resource2);
+    }
+
+    #[test]
+    fn test_round_trip_tei_etymology() {
+        let (resource, resource2, _) = round_trip("examples/24.xml");
+        assert_eq!(resource.entries[0].etymologies, resource2.entries[0].etymologies);
+    }
+
+    #[test]
+    fn test_read_tei_lex0() {
+        let doc = r##"<TEI xmlns="http://www.tei-c.org/ns/1.0" xml:lang="en">
+  <teiHeader><fileDesc><titleStmt><title>Sample</title></titleStmt></fileDesc></teiHeader>
+  <text><body>
+    <entry xml:id="cat-n" n="1">
+      <form type="lemma"><orth>cat</orth><pron notation="ipa">kæt</pron></form>
+      <gramGrp><gram type="pos">noun</gram><gram type="gender">n</gram></gramGrp>
+      <form type="inflected"><gramGrp><gram type="number">pl</gram></gramGrp><orth>cats</orth></form>
+      <sense xml:id="cat-n-1">
+        <usg type="hint">animal</usg>
+        <usg type="domain">zoology</usg>
+        <def>A small domesticated carnivorous mammal.</def>
+        <cit type="example"><quote>The cat sat on the mat.</quote>
+          <cit type="translation" xml:lang="fr"><quote>Le chat était assis sur le tapis.</quote></cit>
+        </cit>
+        <cit type="translationEquivalent" xml:lang="fr"><form><orth>chat</orth></form></cit>
+        <xr type="synonymy"><ref target="#puss-n-1">puss</ref></xr>
+        <sense><def>A nested sense</def></sense>
+      </sense>
+      <etym>From <lang>Latin</lang> <mentioned xml:lang="la">cattus</mentioned>.</etym>
+    </entry>
+  </body></text>
+</TEI>"##;
+        let (resource, report) = read_tei(doc.as_bytes()).unwrap();
+        assert_eq!(resource.title, Some("Sample".to_string()));
+        assert_eq!(resource.lang_code.0, "en");
+        let entry = &resource.entries[0];
+        assert_eq!(entry.headword, "cat");
+        assert_eq!(entry.homograph_number, Some(1));
+        assert_eq!(entry.parts_of_speech, vec!["noun"]);
+        assert_eq!(entry.pronunciations[0].transcriptions[0].text, "kæt");
+        assert_eq!(entry.inflected_forms[0].tag, Some("pl".to_string()));
+        let sense = &entry.senses[0];
+        assert_eq!(sense.indicator, Some("animal".to_string()));
+        assert_eq!(sense.labels, vec!["zoology"]);
+        assert_eq!(sense.definitions[0].text, "A small domesticated carnivorous mammal.");
+        assert_eq!(sense.examples[0].example_translations[0].lang_code, Some(LangCode("fr".to_string())));
+        assert_eq!(sense.headword_translations[0].text, "chat");
+        assert_eq!(entry.etymologies[0].description, Some("From Latin cattus.".to_string()));
+        assert_eq!(entry.etymologies[0].etymons[0].etymon_units[0].text, "cattus");
+        let messages : Vec<&str> = report.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(messages, vec![
+            "<gram type=\"gender\"> in <gramGrp> not mapped",
+            "<xr> in <sense> not mapped",
+            "<sense> in <sense> not mapped",
+        ]);
+        assert_eq!(report.warnings[1].location, "line 16");
+    }
+
+    #[test]
+    fn test_write_report() {
+        let resource = parse(File::open("examples/24.xml").unwrap(), &Format::XML, &None).unwrap();
+        let mut out = Vec::new();
+        let report = write_tei(&mut out, &resource).unwrap();
+        assert!(report.warnings.iter().any(|w| w.message == "Etymon languages and types are not mapped"));
+    }
+}
@@ -0,0 +1,222 @@
+//! Turtle output of DMLEX resources
+use crate::rdf::{ToRDF, DMLEX, LEXICOG, LIME, ONTOLEX, SKOS, VARTRANS};
+use crate::{Graph, WriteError};
+use sophia::iri::IriBox;
+use sophia::ns::Namespace;
+use sophia::prefix::PrefixBox;
+use sophia::serializer::{Stringifier, TripleSerializer};
+use sophia::serializer::turtle::{TurtleSerializer, TurtleConfig};
+use std::io::Write;
+
+/// Options controlling the Turtle serialization of a resource
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TurtleOptions {
+    /// Additional prefixes as pairs of prefix and namespace. These override
+    /// the standard prefixes if they use the same name.
+    pub prefixes: Vec<(String, String)>,
+    /// If set, a `BASE` is declared and IRIs under it are written relatively
+    pub base: Option<String>,
+    /// The prefix to use for the default (data) namespace
+    pub data_prefix: Option<String>,
+}
+
+impl TurtleOptions {
+    pub fn new() -> TurtleOptions {
+        TurtleOptions::default()
+    }
+
+    pub fn with_prefix(mut self, prefix : &str, namespace : &str) -> TurtleOptions {
+        self.prefixes.push((prefix.to_string(), namespace.to_string()));
+        self
+    }
+
+    pub fn with_base(mut self, base : &str) -> TurtleOptions {
+        self.base = Some(base.to_string());
+        self
+    }
+
+    pub fn with_data_prefix(mut self, prefix : &str) -> TurtleOptions {
+        self.data_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// The prefix map used for the output. The OntoLex prefixes are only
+    /// included if the output uses the OntoLex model.
+    pub fn prefix_map(&self, default_namespace : &str, ontolex : bool) -> Vec<(String, String)> {
+        let mut prefixes = vec![
+            ("rdf".to_string(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#".to_string()),
+            ("rdfs".to_string(), "http://www.w3.org/2000/01/rdf-schema#".to_string()),
+            ("xsd".to_string(), "http://www.w3.org/2001/XMLSchema#".to_string()),
+            ("dmlex".to_string(), DMLEX.to_string()),
+        ];
+        if ontolex {
+            prefixes.push(("ontolex".to_string(), ONTOLEX.to_string()));
+            prefixes.push(("lime".to_string(), LIME.to_string()));
+            prefixes.push(("lexicog".to_string(), LEXICOG.to_string()));
+            prefixes.push(("vartrans".to_string(), VARTRANS.to_string()));
+            prefixes.push(("skos".to_string(), SKOS.to_string()));
+        }
+        if let Some(data_prefix) = &self.data_prefix {
+            add_prefix(&mut prefixes, data_prefix, default_namespace);
+        }
+        for (prefix, namespace) in self.prefixes.iter() {
+            add_prefix(&mut prefixes, prefix, namespace);
+        }
+        prefixes
+    }
+}
+
+fn add_prefix(prefixes : &mut Vec<(String, String)>, prefix : &str, namespace : &str) {
+    if let Some(existing) = prefixes.iter_mut().find(|(p, _)| p == prefix) {
+        existing.1 = namespace.to_string();
+    } else {
+        prefixes.push((prefix.to_string(), namespace.to_string()));
+    }
+}
+
+/// Write an element (resource or entry) as Turtle
+pub(crate) fn write_turtle<W : Write, T : ToRDF>(mut output : W, elem : &T,
+    default_namespace : &Option<String>, ontolex : bool, options : &TurtleOptions) -> Result<(), WriteError> {
+    let ns = default_namespace.as_ref().ok_or(WriteError::NoDefaultNamespace)?;
+    let mut g = Graph::new();
+    let data = Namespace::new(ns.as_str())?;
+    let dmlex = Namespace::new(DMLEX).expect("DMLEX namespace is invalid");
+    elem.to_rdf(&mut g, &data, &dmlex, 0, ontolex, &None)?;
+    let mut prefix_map = Vec::new();
+    for (prefix, namespace) in options.prefix_map(ns, ontolex) {
+        prefix_map.push((PrefixBox::new(prefix.into())?, IriBox::new(namespace.into())?));
+    }
+    let config = TurtleConfig::new().with_pretty(true).with_own_prefix_map(prefix_map);
+    if let Some(base) = &options.base {
+        IriBox::new(base.as_str().into())?;
+        let mut serializer = TurtleSerializer::new_stringifier_with_config(config);
+        serializer.serialize_graph(&g)?;
+        writeln!(output, "BASE <{}>", base)?;
+        output.write_all(relativize_turtle(serializer.as_str(), base).as_bytes())?;
+    } else {
+        let mut serializer = TurtleSerializer::new_with_config(output, config);
+        serializer.serialize_graph(&g)?;
+    }
+    Ok(())
+}
+
+/// Rewrite the IRIs in serialized Turtle relative to a base IRI. String
+/// literals are skipped, the serializer always writes them in short quotes.
+fn relativize_turtle(turtle : &str, base : &str) -> String {
+    let mut result = String::with_capacity(turtle.len());
+    let mut rest = turtle;
+    let mut in_string = false;
+    while let Some(c) = rest.chars().next() {
+        if in_string {
+            if c == '\\' {
+                let escaped : String = rest.chars().take(2).collect();
+                result.push_str(&escaped);
+                rest = &rest[escaped.len()..];
+                continue;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == '<' {
+            if let Some(end) = rest.find('>') {
+                let iri = &rest[1..end];
+                result.push('<');
+                result.push_str(relativize(iri, base).unwrap_or(iri));
+                result.push('>');
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    result
+}
+
+/// The IRI relative to the base, if it can be written so unambiguously
+fn relativize<'a>(iri : &'a str, base : &str) -> Option<&'a str> {
+    if let Some(stem) = base.strip_suffix('#') {
+        iri.strip_prefix(stem).filter(|rel| rel.starts_with('#'))
+    } else if base.ends_with('/') {
+        iri.strip_prefix(base).filter(|rel| {
+            let segment = rel.split(['/', '?', '#']).next().unwrap_or("");
+            !rel.starts_with('/') && !segment.contains(':')
+        })
+    } else {
+        iri.strip_prefix(base).filter(|rel| rel.is_empty() || rel.starts_with('#'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use sophia::graph::inmem::LightGraph;
+    use sophia::graph::Graph as _;
+    use sophia::term::{TTerm, TermKind};
+    use sophia::triple::Triple;
+    use sophia::triple::stream::TripleSource;
+    use std::fs::File;
+
+    fn write_example(fname : &str, ontolex : bool, options : &TurtleOptions) -> String {
+        let resource = parse(File::open(fname).unwrap(), &Format::XML, &None).unwrap();
+        let mut out = Vec::new();
+        write_turtle(&mut out, &resource, &Some("http://example.com/dict/".to_string()), ontolex, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn triple_count(turtle : &str) -> usize {
+        let g : LightGraph = sophia::parser::turtle::parse_str(turtle).collect_triples().unwrap();
+        g.triples().count()
+    }
+
+    #[test]
+    fn test_ontolex_prefixes() {
+        let turtle = write_example("examples/0.xml", true, &TurtleOptions::new());
+        assert!(turtle.contains("PREFIX ontolex: <http://www.w3.org/ns/lemon/ontolex#>"));
+        assert!(turtle.contains("ontolex:LexicalEntry"));
+        let turtle = write_example("examples/0.xml", false, &TurtleOptions::new());
+        assert!(!turtle.contains("PREFIX ontolex:"));
+    }
+
+    #[test]
+    fn test_data_prefix_and_extra_prefixes() {
+        let options = TurtleOptions::new()
+            .with_data_prefix("dict")
+            .with_prefix("ex", "http://example.org/");
+        let turtle = write_example("examples/0.xml", false, &options);
+        assert!(turtle.contains("PREFIX dict: <http://example.com/dict/>"));
+        assert!(turtle.contains("PREFIX ex: <http://example.org/>"));
+        assert_eq!(triple_count(&turtle),
+            triple_count(&write_example("examples/0.xml", false, &TurtleOptions::new())));
+    }
+
+    #[test]
+    fn test_base() {
+        let options = TurtleOptions::new().with_base("http://example.com/dict/");
+        let turtle = write_example("examples/0.xml", false, &options);
+        assert!(turtle.starts_with("BASE <http://example.com/dict/>"));
+        assert!(!turtle.lines().skip(1).any(|line| line.contains("<http://example.com/dict/")));
+        let g1 : LightGraph = sophia::parser::turtle::parse_str(&turtle).collect_triples().unwrap();
+        let plain = write_example("examples/0.xml", false, &TurtleOptions::new());
+        let g2 : LightGraph = sophia::parser::turtle::parse_str(&plain).collect_triples().unwrap();
+        assert_eq!(g1.triples().count(), g2.triples().count());
+        for t in g2.triples() {
+            let t = t.unwrap();
+            if t.s().kind() == TermKind::Iri && t.o().kind() != TermKind::BlankNode {
+                assert!(g1.contains(t.s(), t.p(), t.o()).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_relativize() {
+        assert_eq!(relativize("http://ex.com/a/b", "http://ex.com/a/"), Some("b"));
+        assert_eq!(relativize("http://ex.com/a/x:y", "http://ex.com/a/"), None);
+        assert_eq!(relativize("http://ex.com/a#b", "http://ex.com/a#"), Some("#b"));
+        assert_eq!(relativize("http://ex.com/ab", "http://ex.com/a"), None);
+        assert_eq!(relativize_turtle("<http://ex.com/a/b> <p> \"<http://ex.com/a/c> \\\" <http://ex.com/a/d>\" .", "http://ex.com/a/"),
+            "<b> <p> \"<http://ex.com/a/c> \\\" <http://ex.com/a/d>\" .");
+    }
+}
@@ -0,0 +1,641 @@
+//! Import and export of Global WordNet Association WN-LMF XML
+//!
+//! Lexical entries become entries and their senses become senses, with the
+//! definitions and examples of the synset a sense belongs to copied into the
+//! sense. DMLEX has no synsets, so synset membership is kept as a relation of
+//! type `synset` between the member senses. Synset relations become relations
+//! whose members have the roles `sourceSynset` and `targetSynset`, sense
+//! relations ones whose members have the roles `source` and `target`, and a
+//! relation type is declared for each `relType` used. The exporter reverses
+//! this, giving each sense not in a `synset` relation its own synset and
+//! writing relations with the synset roles between whole synsets as synset
+//! relations and all other relations as sense relations.
+use crate::model::*;
+use crate::registry::{ReadOptions, ResourceReader, ResourceWriter, WriteOptions};
+use crate::report::{note_unmapped_tags, ConversionReport};
+use crate::xml_tree::{location, read_tree, unmapped, write_tree, Element};
+use crate::{ParseError, WriteError};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use thiserror::Error;
+
+/// The relation type that groups the senses of a synset
+pub static SYNSET_RELATION : &str = "synset";
+/// The role of the members of a `synset` relation
+pub static MEMBER_ROLE : &str = "member";
+/// The roles of the members of a sense relation
+pub static SOURCE_ROLE : &str = "source";
+pub static TARGET_ROLE : &str = "target";
+/// The roles of the members of a synset relation, which are the senses of
+/// the synsets
+pub static SOURCE_SYNSET_ROLE : &str = "sourceSynset";
+pub static TARGET_SYNSET_ROLE : &str = "targetSynset";
+
+type Result<T> = std::result::Result<T, WordNetError>;
+
+/// Read a WN-LMF document
+pub fn read_wordnet<R : Read>(input : R) -> Result<(LexicographicResource, ConversionReport)> {
+    let root = read_tree(input)?;
+    if root.name != "LexicalResource" {
+        return Err(WordNetError::UnexpectedRoot(root.name));
+    }
+    let mut report = ConversionReport::new();
+    let mut resource = LexicographicResource::default();
+    let mut lexicons = root.elements_named("Lexicon");
+    let lexicon = lexicons.next().ok_or_else(|| WordNetError::NoLexicon(location(&root)))?;
+    for other in lexicons {
+        report.warn(location(other), format!("Only the first lexicon is read, lexicon {} not mapped",
+            other.attr("id").unwrap_or("")));
+    }
+    resource.title = lexicon.attr("label").map(|s| s.to_string());
+    resource.uri = lexicon.attr("url").map(|s| s.to_string());
+    resource.lang_code = LangCode(lexicon.attr("language").unwrap_or("und").to_string());
+
+    let synsets : HashMap<&str, &Element> = lexicon.elements_named("Synset")
+        .filter_map(|s| s.attr("id").map(|id| (id, s)))
+        .collect();
+    // The senses of each synset, in document order
+    let mut synset_senses : HashMap<&str, Vec<String>> = HashMap::new();
+    let mut sense_relations = Vec::new();
+    for child in lexicon.elements() {
+        match child.name.as_str() {
+            "LexicalEntry" => {
+                let entry = read_entry(child, &synsets, &mut synset_senses, &mut sense_relations, &mut report)?;
+                resource.entries.push(entry);
+            },
+            "Synset" | "Requires" | "Extends" => (),
+            _ => unmapped(&mut report, child, lexicon),
+        }
+    }
+    let mut ili_count = 0;
+    for synset in lexicon.elements_named("Synset") {
+        let id = synset.attr("id").unwrap_or("");
+        let members = synset_senses.get(id).cloned().unwrap_or_default();
+        if synset.attr("ili").map(|ili| !ili.is_empty()).unwrap_or(false) {
+            ili_count += 1;
+        }
+        if members.is_empty() {
+            report.warn(location(synset), format!("Synset {} has no senses and is not mapped", id));
+            continue;
+        }
+        if members.len() > 1 {
+            resource.relations.push(Relation {
+                _type: SYNSET_RELATION.to_string(),
+                members: members.iter().map(|m| member(m, MEMBER_ROLE)).collect(),
+                ..Relation::default()
+            });
+        }
+        for child in synset.elements() {
+            match child.name.as_str() {
+                "SynsetRelation" => {
+                    let rel_type = child.attr("relType").unwrap_or("other");
+                    let targets = child.attr("target").and_then(|t| synset_senses.get(t));
+                    match targets {
+                        Some(targets) => resource.relations.push(Relation {
+                            _type: rel_type.to_string(),
+                            members: members.iter().map(|m| member(m, SOURCE_SYNSET_ROLE))
+                                .chain(targets.iter().map(|m| member(m, TARGET_SYNSET_ROLE)))
+                                .collect(),
+                            ..Relation::default()
+                        }),
+                        None => report.warn(location(child), format!("Target of {} relation is not a synset with senses",
+                            rel_type)),
+                    }
+                    declare_relation_type(&mut resource.relation_types, rel_type, SOURCE_SYNSET_ROLE, TARGET_SYNSET_ROLE);
+                },
+                "Definition" | "Example" | "ILIDefinition" => (),
+                _ => unmapped(&mut report, child, synset),
+            }
+        }
+    }
+    if ili_count > 0 {
+        report.warn(location(lexicon), format!("{} ILI identifier(s) of synsets not mapped", ili_count));
+    }
+    for (id, rel_type, target) in sense_relations {
+        declare_relation_type(&mut resource.relation_types, &rel_type, SOURCE_ROLE, TARGET_ROLE);
+        resource.relations.push(Relation {
+            _type: rel_type,
+            members: vec![member(&id, SOURCE_ROLE), member(&target, TARGET_ROLE)],
+            ..Relation::default()
+        });
+    }
+    if resource.relations.iter().any(|r| r._type == SYNSET_RELATION) {
+        resource.relation_types.insert(0, RelationType {
+            _type: SYNSET_RELATION.to_string(),
+            description: Some("senses that belong to the same WordNet synset".to_string()),
+            member_types: vec![MemberType {
+                role: Some(MEMBER_ROLE.to_string()),
+                _type: MemberTypeType::Sense,
+                min: Some(2),
+                hint: Some(Hint::Navigate),
+                ..MemberType::default()
+            }],
+            ..RelationType::default()
+        });
+    }
+    Ok((resource, report))
+}
+
+/// Write a resource as a WN-LMF document
+pub fn write_wordnet<W : Write>(output : W, resource : &LexicographicResource) -> Result<ConversionReport> {
+    let mut report = ConversionReport::new();
+    let lexicon_id = lexicon_id(resource);
+    let mut lexicon = Element::new("Lexicon")
+        .with_attr("id", &lexicon_id)
+        .with_attr("label", resource.title.as_deref().unwrap_or(&lexicon_id))
+        .with_attr("language", &resource.lang_code.0)
+        .with_attr("email", "")
+        .with_attr("license", "")
+        .with_attr("version", "1.0")
+        .with_opt_attr("url", &resource.uri);
+
+    // Give every entry and sense an id, as WN-LMF requires them
+    let mut entry_ids = Vec::new();
+    let mut sense_ids : HashMap<(usize, usize), String> = HashMap::new();
+    for (i, entry) in resource.entries.iter().enumerate() {
+        let entry_id = entry.id.clone().unwrap_or_else(|| format!("{}-{}", lexicon_id, i + 1));
+        for (j, sense) in entry.senses.iter().enumerate() {
+            sense_ids.insert((i, j), sense.id.clone().unwrap_or_else(|| format!("{}-{}", entry_id, j + 1)));
+        }
+        entry_ids.push(entry_id);
+    }
+    let senses : HashMap<&str, (usize, usize)> = sense_ids.iter().map(|(k, v)| (v.as_str(), *k)).collect();
+
+    // Group the senses into synsets
+    let mut synset_of : HashMap<&str, String> = HashMap::new();
+    let mut synset_members : Vec<(String, Vec<&str>)> = Vec::new();
+    for relation in resource.relations.iter().filter(|r| r._type == SYNSET_RELATION) {
+        let members : Vec<&str> = relation.members.iter()
+            .map(|m| m.ref_.as_str())
+            .filter(|m| senses.contains_key(m) && !synset_of.contains_key(m))
+            .collect();
+        if let Some(first) = members.first() {
+            let synset_id = format!("{}-synset", first);
+            for m in members.iter() {
+                synset_of.insert(m, synset_id.clone());
+            }
+            synset_members.push((synset_id, members));
+        }
+    }
+    for (i, entry) in resource.entries.iter().enumerate() {
+        for j in 0..entry.senses.len() {
+            let sense_id = sense_ids[&(i, j)].as_str();
+            if !synset_of.contains_key(sense_id) {
+                let synset_id = format!("{}-synset", sense_id);
+                synset_of.insert(sense_id, synset_id.clone());
+                synset_members.push((synset_id, vec![sense_id]));
+            }
+        }
+    }
+
+    // Relations become synset relations if their members have the synset
+    // roles and are whole synsets, otherwise sense relations
+    let mut synset_relations : HashMap<String, Vec<Element>> = HashMap::new();
+    let mut sense_relations : HashMap<&str, Vec<Element>> = HashMap::new();
+    for (index, relation) in resource.relations.iter().enumerate() {
+        if relation._type == SYNSET_RELATION {
+            continue;
+        }
+        let here = format!("relation {} ({})", index + 1, relation._type);
+        let (sources, targets, synset_roles) = match split_members(relation, &mut report, &here) {
+            Some(split) => split,
+            None => continue,
+        };
+        if let Some(m) = sources.iter().chain(targets.iter()).find(|m| !senses.contains_key(*m)) {
+            report.warn(&here, format!("Member {} is not a sense, relation not mapped", m));
+            continue;
+        }
+        match (synset_roles, whole_synset(&sources, &synset_of, &synset_members),
+            whole_synset(&targets, &synset_of, &synset_members)) {
+            (true, Some(source), Some(target)) => {
+                synset_relations.entry(source).or_default().push(Element::new("SynsetRelation")
+                    .with_attr("relType", &relation._type)
+                    .with_attr("target", &target));
+            },
+            _ => {
+                for source in sources.iter() {
+                    for target in targets.iter() {
+                        sense_relations.entry(source).or_default().push(Element::new("SenseRelation")
+                            .with_attr("relType", &relation._type)
+                            .with_attr("target", target));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut pos_of_synset : HashMap<&str, String> = HashMap::new();
+    for (i, entry) in resource.entries.iter().enumerate() {
+        let here = format!("entry {}", entry_ids[i]);
+        let pos = wordnet_pos(entry, &here, &mut report);
+        let mut lemma = Element::new("Lemma")
+            .with_attr("writtenForm", &entry.headword)
+            .with_attr("partOfSpeech", &pos);
+        for pron in entry.pronunciations.iter() {
+            for transcription in pron.transcriptions.iter() {
+                lemma.push(Element::new("Pronunciation")
+                    .with_opt_attr("notation", &transcription.scheme)
+                    .with_text(&transcription.text));
+            }
+            if pron.sound_file.is_some() || !pron.labels.is_empty() {
+                report.warn(&here, "Pronunciation sound files and labels are not mapped");
+            }
+        }
+        let mut elem = Element::new("LexicalEntry")
+            .with_attr("id", &entry_ids[i])
+            .with_child(lemma);
+        for form in entry.inflected_forms.iter() {
+            let mut f = Element::new("Form").with_attr("writtenForm", &form.text);
+            if let Some(tag) = &form.tag {
+                f.push(Element::new("Tag").with_attr("category", "inflection").with_text(tag));
+            }
+            if !form.labels.is_empty() || !form.pronunciations.is_empty() {
+                report.warn(&here, "Labels and pronunciations of inflected forms are not mapped");
+            }
+            elem.push(f);
+        }
+        for (j, sense) in entry.senses.iter().enumerate() {
+            let sense_id = sense_ids[&(i, j)].as_str();
+            let synset_id = synset_of[sense_id].as_str();
+            pos_of_synset.entry(synset_id).or_insert_with(|| pos.clone());
+            let mut s = Element::new("Sense")
+                .with_attr("id", sense_id)
+                .with_attr("synset", synset_id);
+            for relation in sense_relations.remove(sense_id).unwrap_or_default() {
+                s.push(relation);
+            }
+            for example in sense.examples.iter() {
+                s.push(Element::new("Example").with_text(&example.text));
+            }
+            write_sense_report(sense, &format!("{}, sense {}", here, sense_id), &mut report);
+            elem.push(s);
+        }
+        if !entry.labels.is_empty() || !entry.placeholder_markers.is_empty() || entry.homograph_number.is_some() {
+            report.warn(&here, "Entry labels, placeholder markers and homograph numbers are not mapped");
+        }
+        if !entry.etymologies.is_empty() {
+            report.warn(&here, "Etymologies are not mapped");
+        }
+        lexicon.push(elem);
+    }
+
+    for (synset_id, members) in synset_members.iter() {
+        let mut synset = Element::new("Synset")
+            .with_attr("id", synset_id)
+            .with_attr("ili", "")
+            .with_attr("partOfSpeech", pos_of_synset.get(synset_id.as_str()).map(|p| p.as_str()).unwrap_or("u"))
+            .with_attr("members", &members.join(" "));
+        let mut definitions : Vec<&str> = Vec::new();
+        for m in members.iter() {
+            let (i, j) = senses[m];
+            for definition in resource.entries[i].senses[j].definitions.iter() {
+                if !definitions.contains(&definition.text.as_str()) {
+                    definitions.push(&definition.text);
+                }
+            }
+        }
+        for definition in definitions {
+            synset.push(Element::new("Definition").with_text(definition));
+        }
+        for relation in synset_relations.remove(synset_id).unwrap_or_default() {
+            synset.push(relation);
+        }
+        lexicon.push(synset);
+    }
+
+    let here = "lexicographicResource";
+    note_unmapped_tags(resource, here, &mut report);
+    if !resource.translation_languages.is_empty() {
+        report.warn(here, "Translation languages are not mapped");
+    }
+    let root = Element::new("LexicalResource").with_child(lexicon);
+    write_tree(output, &root, None)?;
+    Ok(report)
+}
+
+/// The WN-LMF format for use in a `FormatRegistry`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WordNetFormat;
+
+impl ResourceReader for WordNetFormat {
+    fn read_resource(&self, input : &mut dyn Read, options : &ReadOptions) -> std::result::Result<LexicographicResource, ParseError> {
+        Ok(self.read_resource_with_report(input, options)?.0)
+    }
+
+    fn read_resource_with_report(&self, input : &mut dyn Read, _options : &ReadOptions) -> std::result::Result<(LexicographicResource, ConversionReport), ParseError> {
+        Ok(read_wordnet(input)?)
+    }
+}
+
+impl ResourceWriter for WordNetFormat {
+    fn write_resource(&self, output : &mut dyn Write, resource : &LexicographicResource, options : &WriteOptions) -> std::result::Result<(), WriteError> {
+        self.write_resource_with_report(output, resource, options)?;
+        Ok(())
+    }
+
+    fn write_resource_with_report(&self, output : &mut dyn Write, resource : &LexicographicResource, _options : &WriteOptions) -> std::result::Result<ConversionReport, WriteError> {
+        Ok(write_wordnet(output, resource)?)
+    }
+}
+
+fn member(ref_ : &str, role : &str) -> Member {
+    Member {
+        ref_: ref_.to_string(),
+        role: Some(role.to_string()),
+        obverse_listing_order: None,
+    }
+}
+
+/// Declare a relation type between source and target senses with the given
+/// roles, adding the member types for the roles if the type is already
+/// declared
+fn declare_relation_type(relation_types : &mut Vec<RelationType>, rel_type : &str, source_role : &str, target_role : &str) {
+    let index = match relation_types.iter().position(|t| t._type == rel_type) {
+        Some(index) => index,
+        None => {
+            relation_types.push(RelationType {
+                _type: rel_type.to_string(),
+                description: Some("WordNet relation".to_string()),
+                ..RelationType::default()
+            });
+            relation_types.len() - 1
+        }
+    };
+    let member_types = &mut relation_types[index].member_types;
+    for role in [source_role, target_role] {
+        if !member_types.iter().any(|t| t.role.as_deref() == Some(role)) {
+            member_types.push(MemberType {
+                role: Some(role.to_string()),
+                _type: MemberTypeType::Sense,
+                min: Some(1),
+                hint: Some(Hint::Navigate),
+                ..MemberType::default()
+            });
+        }
+    }
+}
+
+fn read_entry<'a>(elem : &Element, synsets : &HashMap<&'a str, &'a Element>,
+    synset_senses : &mut HashMap<&'a str, Vec<String>>, sense_relations : &mut Vec<(String, String, String)>,
+    report : &mut ConversionReport) -> Result<Entry> {
+    let lemma = elem.child("Lemma").ok_or_else(|| WordNetError::MissingLemma(location(elem)))?;
+    let mut entry = Entry {
+        id: elem.attr("id").map(|s| s.to_string()),
+        headword: lemma.attr("writtenForm").unwrap_or_default().to_string(),
+        parts_of_speech: lemma.attr("partOfSpeech").map(|p| vec![p.to_string()]).unwrap_or_default(),
+        ..Entry::default()
+    };
+    for child in lemma.elements() {
+        match child.name.as_str() {
+            "Pronunciation" => entry.pronunciations.push(Pronunciation {
+                transcriptions: vec![Transcription {
+                    text: child.text(),
+                    scheme: child.attr("notation").map(|s| s.to_string()),
+                }],
+                ..Pronunciation::default()
+            }),
+            _ => unmapped(report, child, lemma),
+        }
+    }
+    for child in elem.elements() {
+        match child.name.as_str() {
+            "Lemma" => (),
+            "Form" => {
+                let tags : Vec<String> = child.elements_named("Tag").map(|t| t.text()).collect();
+                entry.inflected_forms.push(InflectedForm {
+                    text: child.attr("writtenForm").unwrap_or_default().to_string(),
+                    tag: if tags.is_empty() { None } else { Some(tags.join(" ")) },
+                    ..InflectedForm::default()
+                });
+            },
+            "Sense" => {
+                let id = child.attr("id").unwrap_or_default().to_string();
+                let mut sense = Sense {
+                    id: Some(id.clone()),
+                    ..Sense::default()
+                };
+                let synset = child.attr("synset").and_then(|s| synsets.get_key_value(s));
+                match synset {
+                    Some((synset_id, synset)) => {
+                        synset_senses.entry(synset_id).or_default().push(id.clone());
+                        sense.definitions.extend(synset.elements_named("Definition").map(|d| Definition {
+                            text: d.text(),
+                            ..Definition::default()
+                        }));
+                        sense.examples.extend(synset.elements_named("Example").map(example));
+                    },
+                    None => report.warn(location(child), format!("Sense {} does not refer to a synset", id)),
+                }
+                for s in child.elements() {
+                    match s.name.as_str() {
+                        "Example" => sense.examples.push(example(s)),
+                        "SenseRelation" => sense_relations.push((id.clone(),
+                            s.attr("relType").unwrap_or("other").to_string(),
+                            s.attr("target").unwrap_or_default().to_string())),
+                        _ => unmapped(report, s, child),
+                    }
+                }
+                entry.senses.push(sense);
+            },
+            _ => unmapped(report, child, elem),
+        }
+    }
+    Ok(entry)
+}
+
+fn example(elem : &Element) -> Example {
+    Example {
+        text: elem.text(),
+        ..Example::default()
+    }
+}
+
+/// An identifier for the lexicon, based on the language
+fn lexicon_id(resource : &LexicographicResource) -> String {
+    format!("{}wn", resource.lang_code.0)
+}
+
+/// The part of speech of an entry, which WN-LMF requires exactly one of
+fn wordnet_pos(entry : &Entry, here : &str, report : &mut ConversionReport) -> String {
+    match entry.parts_of_speech.first() {
+        Some(pos) => {
+            if entry.parts_of_speech.len() > 1 {
+                report.warn(here, "Only the first part of speech is mapped");
+            }
+            pos.clone()
+        },
+        None => "u".to_string(),
+    }
+}
+
+/// Split the members of a relation into sources and targets, and whether
+/// they have the synset roles. Relations that use neither the synset roles
+/// nor the `source` and `target` roles are read from the first member.
+fn split_members<'a>(relation : &'a Relation, report : &mut ConversionReport, here : &str) -> Option<(Vec<&'a str>, Vec<&'a str>, bool)> {
+    let with_role = |role : &str| relation.members.iter()
+        .filter(|m| m.role.as_deref() == Some(role))
+        .map(|m| m.ref_.as_str())
+        .collect::<Vec<_>>();
+    for (source_role, target_role, synset_roles) in [(SOURCE_SYNSET_ROLE, TARGET_SYNSET_ROLE, true), (SOURCE_ROLE, TARGET_ROLE, false)] {
+        let sources = with_role(source_role);
+        let targets = with_role(target_role);
+        if !sources.is_empty() && !targets.is_empty() && sources.len() + targets.len() == relation.members.len() {
+            return Some((sources, targets, synset_roles));
+        }
+    }
+    if relation.members.len() >= 2 {
+        report.warn(here, "Members without source and target roles, written as relations from the first member");
+        let refs : Vec<&str> = relation.members.iter().map(|m| m.ref_.as_str()).collect();
+        Some((vec![refs[0]], refs[1..].to_vec(), false))
+    } else {
+        report.warn(here, "Relations with fewer than two members are not mapped");
+        None
+    }
+}
+
+/// The synset whose members are exactly the given senses, if any
+fn whole_synset(senses : &[&str], synset_of : &HashMap<&str, String>, synset_members : &[(String, Vec<&str>)]) -> Option<String> {
+    let synset_id = synset_of.get(senses.first()?)?;
+    let (_, members) = synset_members.iter().find(|(id, _)| id == synset_id)?;
+    if members.len() == senses.len() && senses.iter().all(|s| members.contains(s)) {
+        Some(synset_id.clone())
+    } else {
+        None
+    }
+}
+
+fn write_sense_report(sense : &Sense, here : &str, report : &mut ConversionReport) {
+    if sense.indicator.is_some() || !sense.labels.is_empty() {
+        report.warn(here, "Sense indicators and labels are not mapped");
+    }
+    if !sense.headword_explanations.is_empty() || !sense.headword_translations.is_empty() {
+        report.warn(here, "Headword explanations and translations are not mapped");
+    }
+    if sense.definitions.iter().any(|d| d.definition_type.is_some()
+        || !d.headword_markers.is_empty() || !d.collocate_markers.is_empty()) {
+        report.warn(here, "Definition types and markers are not mapped");
+    }
+    if sense.examples.iter().any(|e| e.source_identity.is_some() || e.source_elaboration.is_some()
+        || !e.labels.is_empty() || e.sound_file.is_some() || !e.example_translations.is_empty()
+        || !e.headword_markers.is_empty() || !e.collocate_markers.is_empty()) {
+        report.warn(here, "Only the text of examples is mapped");
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum WordNetError {
+    #[error("XML error: {0}")]
+    Xml(#[from] xml::reader::Error),
+    #[error("XML write error: {0}")]
+    XmlWrite(#[from] xml::writer::Error),
+    #[error("Expected a WN-LMF document but found <{0}>")]
+    UnexpectedRoot(String),
+    #[error("No <Lexicon> in the document at {0}")]
+    NoLexicon(String),
+    #[error("Lexical entry at {0} has no <Lemma>")]
+    MissingLemma(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Format};
+    use std::fs::File;
+
+    static SAMPLE : &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE LexicalResource SYSTEM "http://globalwordnet.github.io/schemas/WN-LMF-1.1.dtd">
+<LexicalResource xmlns:dc="https://globalwordnet.github.io/schemas/dc/">
+  <Lexicon id="ewn" label="Example WordNet" language="en" email="ewn@example.com"
+           license="https://creativecommons.org/licenses/by/4.0/" version="1.0">
+    <LexicalEntry id="ewn-cat-n">
+      <Lemma writtenForm="cat" partOfSpeech="n"><Pronunciation>kæt</Pronunciation></Lemma>
+      <Form writtenForm="cats"><Tag category="number">pl</Tag></Form>
+      <Sense id="ewn-cat-n-1" synset="ewn-1-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="ewn-puss-n">
+      <Lemma writtenForm="puss" partOfSpeech="n"/>
+      <Sense id="ewn-puss-n-1" synset="ewn-1-n">
+        <Example>Here, puss!</Example>
+      </Sense>
+    </LexicalEntry>
+    <LexicalEntry id="ewn-feline-n">
+      <Lemma writtenForm="feline" partOfSpeech="n"/>
+      <Sense id="ewn-feline-n-1" synset="ewn-2-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="ewn-dog-n">
+      <Lemma writtenForm="dog" partOfSpeech="n"/>
+      <Sense id="ewn-dog-n-1" synset="ewn-3-n">
+        <SenseRelation relType="antonym" target="ewn-cat-n-1"/>
+      </Sense>
+    </LexicalEntry>
+    <Synset id="ewn-1-n" ili="i46593" partOfSpeech="n" members="ewn-cat-n-1 ewn-puss-n-1">
+      <Definition>a small domesticated feline</Definition>
+      <SynsetRelation relType="hypernym" target="ewn-2-n"/>
+    </Synset>
+    <Synset id="ewn-2-n" ili="" partOfSpeech="n" members="ewn-feline-n-1">
+      <Definition>any of the family of cats</Definition>
+      <SynsetRelation relType="hyponym" target="ewn-1-n"/>
+    </Synset>
+    <Synset id="ewn-3-n" ili="" partOfSpeech="n" members="ewn-dog-n-1">
+      <Definition>a domesticated canine</Definition>
+    </Synset>
+  </Lexicon>
+</LexicalResource>"#;
+
+    #[test]
+    fn test_read_wordnet() {
+        let (resource, report) = read_wordnet(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(resource.title, Some("Example WordNet".to_string()));
+        assert_eq!(resource.lang_code.0, "en");
+        assert_eq!(resource.entries.len(), 4);
+        let cat = &resource.entries[0];
+        assert_eq!(cat.headword, "cat");
+        assert_eq!(cat.parts_of_speech, vec!["n"]);
+        assert_eq!(cat.pronunciations[0].transcriptions[0].text, "kæt");
+        assert_eq!(cat.inflected_forms[0].tag, Some("pl".to_string()));
+        assert_eq!(cat.senses[0].definitions[0].text, "a small domesticated feline");
+        assert_eq!(resource.entries[1].senses[0].definitions[0].text, "a small domesticated feline");
+        assert_eq!(resource.entries[1].senses[0].examples[0].text, "Here, puss!");
+        let types : Vec<&str> = resource.relation_types.iter().map(|t| t._type.as_str()).collect();
+        assert_eq!(types, vec!["synset", "hypernym", "hyponym", "antonym"]);
+        let hypernym = resource.relations.iter().find(|r| r._type == "hypernym").unwrap();
+        assert_eq!(hypernym.members, vec![
+            member("ewn-cat-n-1", SOURCE_SYNSET_ROLE),
+            member("ewn-puss-n-1", SOURCE_SYNSET_ROLE),
+            member("ewn-feline-n-1", TARGET_SYNSET_ROLE),
+        ]);
+        let antonym = resource.relations.iter().find(|r| r._type == "antonym").unwrap();
+        assert_eq!(antonym.members[0].ref_, "ewn-dog-n-1");
+        let messages : Vec<&str> = report.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(messages, vec!["1 ILI identifier(s) of synsets not mapped"]);
+    }
+
+    #[test]
+    fn test_round_trip_wordnet() {
+        let (resource, _) = read_wordnet(SAMPLE.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        let report = write_wordnet(&mut out, &resource).unwrap();
+        assert!(report.is_empty(), "{}", report);
+        let (resource2, _) = read_wordnet(out.as_slice()).unwrap();
+        assert_eq!(resource.entries, resource2.entries);
+        let mut relations = resource.relations.clone();
+        let mut relations2 = resource2.relations.clone();
+        relations.sort_by(|a, b| a._type.cmp(&b._type));
+        relations2.sort_by(|a, b| a._type.cmp(&b._type));
+        assert_eq!(relations, relations2);
+        assert_eq!(resource.relation_types, resource2.relation_types);
+    }
+
+    #[test]
+    fn test_write_dmlex_relations() {
+        let resource = parse(File::open("examples/12.xml").unwrap(), &Format::XML, &None).unwrap();
+        let mut out = Vec::new();
+        let report = write_wordnet(&mut out, &resource).unwrap();
+        assert!(report.warnings.iter().any(|w| w.message.starts_with("Members without source and target roles")));
+        let (resource2, _) = read_wordnet(out.as_slice()).unwrap();
+        assert_eq!(resource2.entries.len(), 3);
+        let meronymy : Vec<&Relation> = resource2.relations.iter().filter(|r| r._type == "meronymy").collect();
+        assert_eq!(meronymy.len(), 2);
+        assert_eq!(meronymy[0].members, vec![member("glasses-1", SOURCE_ROLE), member("lens-1", TARGET_ROLE)]);
+    }
+}
@@ -0,0 +1,202 @@
+//! A minimal element tree used by the readers and writers of other XML
+//! dictionary formats
+use crate::report::ConversionReport;
+use std::io::{Read, Write};
+use xml::common::Position;
+use xml::reader::{EventReader, XmlEvent};
+use xml::writer::EventWriter;
+
+/// An XML element. Names are local names, except for attributes in the XML
+/// namespace which keep their `xml:` prefix.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+    /// The line the element starts on, if it was read from a document
+    pub line: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn new(name : &str) -> Element {
+        Element {
+            name: name.to_string(),
+            ..Element::default()
+        }
+    }
+
+    pub fn with_attr(mut self, name : &str, value : &str) -> Element {
+        self.attributes.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_opt_attr(self, name : &str, value : &Option<String>) -> Element {
+        match value {
+            Some(value) => self.with_attr(name, value),
+            None => self,
+        }
+    }
+
+    pub fn with_child(mut self, child : Element) -> Element {
+        self.children.push(Node::Element(child));
+        self
+    }
+
+    pub fn with_text(mut self, text : &str) -> Element {
+        self.children.push(Node::Text(text.to_string()));
+        self
+    }
+
+    pub fn push(&mut self, child : Element) {
+        self.children.push(Node::Element(child));
+    }
+
+    pub fn attr(&self, name : &str) -> Option<&str> {
+        self.attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// The child elements
+    pub fn elements(&self) -> impl Iterator<Item=&Element> {
+        self.children.iter().filter_map(|n| match n {
+            Node::Element(e) => Some(e),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// The child elements with a given name
+    pub fn elements_named<'a>(&'a self, name : &'a str) -> impl Iterator<Item=&'a Element> {
+        self.elements().filter(move |e| e.name == name)
+    }
+
+    pub fn child(&self, name : &str) -> Option<&Element> {
+        self.elements().find(|e| e.name == name)
+    }
+
+    /// The text content of the element and all its descendants with
+    /// whitespace normalized
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.collect_text(&mut text);
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn collect_text(&self, text : &mut String) {
+        for child in self.children.iter() {
+            match child {
+                Node::Element(e) => e.collect_text(text),
+                Node::Text(t) => text.push_str(t),
+            }
+        }
+    }
+
+    /// The text of the first child with a given name
+    pub fn child_text(&self, name : &str) -> Option<String> {
+        self.child(name).map(|e| e.text())
+    }
+}
+
+/// The location of an element for a conversion report, its line if known
+pub(crate) fn location(elem : &Element) -> String {
+    match elem.line {
+        Some(line) => format!("line {}", line),
+        None => format!("<{}>", elem.name),
+    }
+}
+
+/// Report an element that a reader does not map
+pub(crate) fn unmapped(report : &mut ConversionReport, elem : &Element, parent : &Element) {
+    report.warn(location(elem), format!("<{}> in <{}> not mapped", elem.name, parent.name));
+}
+
+/// Read the document element of an XML document
+pub fn read_tree<R : Read>(input : R) -> Result<Element, xml::reader::Error> {
+    let mut reader = EventReader::new_with_config(input, xml::ParserConfig::new()
+        .trim_whitespace(false)
+        .whitespace_to_characters(true)
+        .cdata_to_characters(true));
+    let mut stack : Vec<Element> = Vec::new();
+    loop {
+        match reader.next()? {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let mut elem = Element::new(&name.local_name);
+                elem.line = Some(reader.position().row + 1);
+                for attr in attributes {
+                    if attr.name.prefix.as_deref() == Some("xml") {
+                        elem.attributes.push((format!("xml:{}", attr.name.local_name), attr.value));
+                    } else {
+                        elem.attributes.push((attr.name.local_name, attr.value));
+                    }
+                }
+                stack.push(elem);
+            },
+            XmlEvent::EndElement { .. } => {
+                let elem = stack.pop().expect("End element without start element");
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Node::Element(elem)),
+                    None => return Ok(elem),
+                }
+            },
+            XmlEvent::Characters(text) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(Node::Text(text));
+                }
+            },
+            XmlEvent::EndDocument => {
+                unreachable!("Document ended before the root element was closed")
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Write an element tree as an XML document, declaring a default namespace
+/// on the document element if given
+pub fn write_tree<W : Write>(output : W, root : &Element, namespace : Option<&str>) -> Result<(), xml::writer::Error> {
+    let mut writer = xml::EmitterConfig::new().perform_indent(true).create_writer(output);
+    write_element(&mut writer, root, namespace)
+}
+
+fn write_element<W : Write>(writer : &mut EventWriter<W>, elem : &Element, namespace : Option<&str>) -> Result<(), xml::writer::Error> {
+    let mut start = xml::writer::XmlEvent::start_element(elem.name.as_str());
+    if let Some(ns) = namespace {
+        start = start.default_ns(ns);
+    }
+    for (name, value) in elem.attributes.iter() {
+        start = start.attr(name.as_str(), value);
+    }
+    writer.write(start)?;
+    for child in elem.children.iter() {
+        match child {
+            Node::Element(e) => write_element(writer, e, None)?,
+            Node::Text(t) => writer.write(xml::writer::XmlEvent::characters(t))?,
+        }
+    }
+    writer.write(xml::writer::XmlEvent::end_element())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let doc = r#"<a xmlns="http://example.com/" xml:lang="en"><b n="1">x <c>y</c> z</b><![CDATA[w]]></a>"#;
+        let tree = read_tree(doc.as_bytes()).unwrap();
+        assert_eq!(tree.attr("xml:lang"), Some("en"));
+        assert_eq!(tree.child("b").unwrap().attr("n"), Some("1"));
+        assert_eq!(tree.child_text("b"), Some("x y z".to_string()));
+        assert_eq!(tree.text(), "x y zw");
+        let mut out = Vec::new();
+        write_tree(&mut out, &tree, Some("http://example.com/")).unwrap();
+        let tree2 = read_tree(out.as_slice()).unwrap();
+        assert_eq!(tree2.text(), tree.text());
+        assert_eq!(tree2.attributes, tree.attributes);
+    }
+}